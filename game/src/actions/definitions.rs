@@ -2,13 +2,13 @@ use spawning_pool::{EntityId};
 use crate::geo::*;
 use crate::spells;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum ActionTarget {
     Entity(EntityId),
     Position(Point)
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Action {
     pub actor: Option<EntityId>,
     pub target: Option<ActionTarget>,
@@ -27,18 +27,19 @@ impl Action {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LevelUpChoice {
     Strength,
     Defense
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Command {
     Win,
     CreateGame,
     LoadGame,
     DescendStairs,
+    AscendStairs,
     WalkDirection{dir: Point},
     AttackEntity{bonus_strength: i32, bonus_defense: i32},
     OpenDoor{entity: EntityId},
@@ -48,10 +49,11 @@ pub enum Command {
     UnequipItem{item_id: EntityId},
     DestroyItem{item_id: EntityId},
     CastSpell{spell: spells::Spell},
-    WriteRune{spell: spells::Spells},
+    WriteRune{spell: String},
     DropItem{item_id: EntityId},
     Heal{amount: i32},
     SpawnFog{pos: Point},
+    SpawnLight{pos: Point, radius: i32},
     KillEntity,
     PickUpItem{item_id: EntityId},
     LightningStrike{damage: i32},
@@ -60,7 +62,79 @@ pub enum Command {
     Stun,
     GainPoint,
     LevelUp(LevelUpChoice),
+    Fall,
     Wait,
     Abort
 }
 
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CommandKind {
+    Win,
+    CreateGame,
+    LoadGame,
+    DescendStairs,
+    AscendStairs,
+    WalkDirection,
+    AttackEntity,
+    OpenDoor,
+    TakeDamage,
+    UseItem,
+    EquipItem,
+    UnequipItem,
+    DestroyItem,
+    CastSpell,
+    WriteRune,
+    DropItem,
+    Heal,
+    SpawnFog,
+    SpawnLight,
+    KillEntity,
+    PickUpItem,
+    LightningStrike,
+    Confuse,
+    Slow,
+    Stun,
+    GainPoint,
+    LevelUp,
+    Fall,
+    Wait,
+    Abort
+}
+
+impl Command {
+    pub fn kind(&self) -> CommandKind {
+        match *self {
+            Command::Win => CommandKind::Win,
+            Command::CreateGame => CommandKind::CreateGame,
+            Command::LoadGame => CommandKind::LoadGame,
+            Command::DescendStairs => CommandKind::DescendStairs,
+            Command::AscendStairs => CommandKind::AscendStairs,
+            Command::WalkDirection{..} => CommandKind::WalkDirection,
+            Command::AttackEntity{..} => CommandKind::AttackEntity,
+            Command::OpenDoor{..} => CommandKind::OpenDoor,
+            Command::TakeDamage{..} => CommandKind::TakeDamage,
+            Command::UseItem{..} => CommandKind::UseItem,
+            Command::EquipItem{..} => CommandKind::EquipItem,
+            Command::UnequipItem{..} => CommandKind::UnequipItem,
+            Command::DestroyItem{..} => CommandKind::DestroyItem,
+            Command::CastSpell{..} => CommandKind::CastSpell,
+            Command::WriteRune{..} => CommandKind::WriteRune,
+            Command::DropItem{..} => CommandKind::DropItem,
+            Command::Heal{..} => CommandKind::Heal,
+            Command::SpawnFog{..} => CommandKind::SpawnFog,
+            Command::SpawnLight{..} => CommandKind::SpawnLight,
+            Command::KillEntity => CommandKind::KillEntity,
+            Command::PickUpItem{..} => CommandKind::PickUpItem,
+            Command::LightningStrike{..} => CommandKind::LightningStrike,
+            Command::Confuse => CommandKind::Confuse,
+            Command::Slow => CommandKind::Slow,
+            Command::Stun => CommandKind::Stun,
+            Command::GainPoint => CommandKind::GainPoint,
+            Command::LevelUp(_) => CommandKind::LevelUp,
+            Command::Fall => CommandKind::Fall,
+            Command::Wait => CommandKind::Wait,
+            Command::Abort => CommandKind::Abort
+        }
+    }
+}
@@ -1,14 +1,11 @@
-use inflector::Inflector;
-
 use crate::actions::definitions::*;
-use crate::utils;
-use crate::messages::*;
+use crate::events::GameEvent;
 use crate::game::*;
 
 pub fn perform_use_item(action: &Action, game_state: &mut GameState) {
     if let Command::UseItem{item_id} = action.command {
-        let name = utils::get_actor_name(action, &game_state.spawning_pool);
-        let item_name = utils::get_entity_name(item_id, &game_state.spawning_pool);
-        game_state.messages.log(MessageLevel::Info, format!("{} uses {}", name.to_sentence_case(), item_name));
+        if let Some(actor) = action.actor {
+            game_state.events.push(GameEvent::ItemUsed{entity: actor, item: item_id});
+        }
     }
 }
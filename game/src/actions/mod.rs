@@ -1,8 +1,8 @@
-use inflector::Inflector;
-
 use geo::*;
 use std::cmp::min;
-use tcod::colors;
+use geo::colors;
+use rand::Rng;
+use spawning_pool::EntityId;
 use crate::game::*;
 use crate::components;
 use crate::components::{SpawningPool};
@@ -11,10 +11,10 @@ mod items;
 mod definitions;
 
 use crate::utils;
-use crate::messages::*;
+use crate::noise;
+use crate::events::GameEvent;
 pub use self::definitions::*;
 use self::items::*;
-use crate::spells;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ActionResult {
@@ -27,22 +27,41 @@ pub fn perform_action(action: &Action, game_state: &mut GameState) -> ActionResu
         Command::Wait => ActionResult::Performed{time: 100},
         Command::Abort => ActionResult::Failed,
         Command::DescendStairs => {
-            game_state.new_level();
+            let level = game_state.level + 1;
+            game_state.change_level(level);
+            ActionResult::Performed{time: 100}
+        },
+        Command::AscendStairs => {
+            if game_state.level <= 1 {
+                return ActionResult::Failed;
+            }
+            let level = game_state.level - 1;
+            game_state.change_level(level);
             ActionResult::Performed{time: 100}
         },
         Command::AttackEntity{..} => {
             ActionResult::Performed{time: 100}
         },
         Command::WalkDirection{dir} => {
+            let mut time = 50;
             if let Some(physics) = game_state.spawning_pool.get_mut::<components::Physics>(action.actor.unwrap()) {
                 physics.coord += dir;
+                time = game_state.map.get_cell(physics.coord.x, physics.coord.y).tile_type.walk_time();
             }
-            ActionResult::Performed{time: 50}
+            ActionResult::Performed{time}
+        },
+        Command::Fall => {
+            perform_fall(action, game_state);
+            ActionResult::Performed{time: 0}
         },
         Command::SpawnFog{..} => {
             perform_spawn_fog(action, game_state);
             ActionResult::Performed{time: 0}
         },
+        Command::SpawnLight{..} => {
+            perform_spawn_light(action, game_state);
+            ActionResult::Performed{time: 0}
+        },
         Command::WriteRune{..} => {
             if perform_write_rune(action, game_state) {
                 ActionResult::Performed{time: 300}
@@ -102,28 +121,11 @@ pub fn perform_action(action: &Action, game_state: &mut GameState) -> ActionResu
             ActionResult::Performed{time: 0}
         },
         Command::CastSpell{ref spell} => {
-            let msg = match action.actor {
-                Some(_) => {
-                    let actor = utils::get_actor_name(action, &game_state.spawning_pool);
-                    match action.target {
-                        Some(_) => {
-                            let target = utils::get_target_name(action, &game_state.spawning_pool);
-                            format!("The {} is casting {} on {} ", actor, spell.name, target)
-                        },
-                        None => format!("The {} is casting {}", actor, spell.name)
-                    }
-                }, 
-                None => {
-                    match action.target {
-                        Some(_) => {
-                            let target = utils::get_target_name(action, &game_state.spawning_pool);
-                            format!("The {} is {}", target, spell.name)
-                        },
-                        None => format!("{} is cast", spell.name)
-                    }
-                }
-            };
-            game_state.messages.log(MessageLevel::Spell, msg);
+            game_state.events.push(GameEvent::SpellCast{
+                caster: action.actor,
+                target: action.target,
+                spell: spell.clone()
+            });
             ActionResult::Performed{time: 200}
         },
         Command::DestroyItem{..} => {
@@ -138,6 +140,12 @@ pub fn perform_action(action: &Action, game_state: &mut GameState) -> ActionResu
             perform_gain_point(action, game_state);
             ActionResult::Performed{time: 100}
         },
+        Command::LightningStrike{..} => {
+            if let Some(ActionTarget::Entity(target)) = action.target {
+                game_state.events.push(GameEvent::LightningStruck{target});
+            }
+            ActionResult::Performed{time: 0}
+        },
         _ => {
             ActionResult::Performed{time: 0}
         }
@@ -149,16 +157,15 @@ fn perform_level_up(action: &Action, state: &mut GameState) {
     if let Some(actor) = action.actor {
         if let Command::LevelUp(ref choice) = action.command {
             if let Some(stats) = state.spawning_pool.get_mut::<Stats>(actor) {
+                if stats.points <= 0 {
+                    return;
+                }
+                stats.points -= 1;
                 match choice {
-                    LevelUpChoice::Strength => {
-                        state.messages.log(MessageLevel::Important, "The player grows stronger");
-                        stats.strength += 2
-                    },
-                    LevelUpChoice::Defense => {
-                        state.messages.log(MessageLevel::Important, "The player's skin thickens");
-                        stats.defense += 1
-                    } 
+                    LevelUpChoice::Strength => stats.strength += 2,
+                    LevelUpChoice::Defense => stats.defense += 1
                 }
+                state.events.push(GameEvent::LevelledUp{entity: actor, choice: choice.clone()});
             }
         }
     }
@@ -170,6 +177,7 @@ fn perform_gain_point(action: &Action, state: &mut GameState) {
     if let Some(ActionTarget::Entity(target)) = action.target {
         if let Some(stats) = state.spawning_pool.get_mut::<Stats>(target) {
             stats.points += 1;
+            state.events.push(GameEvent::PointGained{entity: target});
         }
     }
 }
@@ -188,9 +196,49 @@ fn perform_spawn_fog(action: &Action, state: &mut GameState) {
     };
     for coord in coords {
         create_fog_at(coord, &mut state.spawning_pool);
+        state.events.push(GameEvent::FogSpawned{position: coord});
+    }
+}
+
+fn perform_spawn_light(action: &Action, state: &mut GameState) {
+    if let Command::SpawnLight{pos, radius} = action.command {
+        if state.map.is_floor(pos) {
+            create_light_at(pos, radius, &mut state.spawning_pool);
+            state.events.push(GameEvent::LightSpawned{position: pos});
+        }
     }
 }
 
+// A floating ball of light that fades away after a while.
+fn create_light_at(pos: Point, radius: i32, spawning_pool: &mut SpawningPool) {
+    let light = spawning_pool.spawn_entity();
+    spawning_pool.set(light, components::Visual{
+        always_display: false,
+        glyph: '*',
+        color: colors::LIGHTEST_YELLOW
+    });
+    spawning_pool.set(light, components::Physics{
+        coord: pos,
+    });
+    spawning_pool.set(light, components::Duration{
+        spawn_time: 0,
+        expire_time: 0,
+        duration: 1000
+    });
+    spawning_pool.set(light, components::Flags{
+        block_sight: false,
+        solid: false
+    });
+    spawning_pool.set(light, components::Information{
+        faction: components::Faction::Neutral,
+        name: "Light".to_string()
+    });
+    spawning_pool.set(light, components::Light{
+        radius,
+        color: colors::LIGHTEST_YELLOW
+    });
+}
+
 fn create_fog_at(pos: Point, spawning_pool: &mut SpawningPool) {
     let fog = spawning_pool.spawn_entity();
     spawning_pool.set(fog, components::Visual{
@@ -221,6 +269,7 @@ fn perform_slow(action: &Action, state: &mut GameState) {
     if let Some(ActionTarget::Entity(target)) = action.target {
         if let Some(stats) = state.spawning_pool.get_mut::<Stats>(target) {
             stats.effects.insert(Effect::Slow, state.scheduler.time + 500);
+            state.events.push(GameEvent::EffectApplied{actor: action.actor, target, effect: Effect::Slow});
         }
     }
 }
@@ -231,6 +280,7 @@ fn perform_stun(action: &Action, state: &mut GameState) {
     if let Some(ActionTarget::Entity(target)) = action.target {
         if let Some(stats) = state.spawning_pool.get_mut::<Stats>(target) {
             stats.effects.insert(Effect::Stun, state.scheduler.time + 500);
+            state.events.push(GameEvent::EffectApplied{actor: action.actor, target, effect: Effect::Stun});
         }
     }
 }
@@ -238,25 +288,14 @@ fn perform_stun(action: &Action, state: &mut GameState) {
 fn perform_confuse(action: &Action, state: &mut GameState) -> bool {
     use components::*;
 
-    let performed = if let Some(ActionTarget::Entity(target)) = action.target {
+    if let Some(ActionTarget::Entity(target)) = action.target {
         if let Some(stats) = state.spawning_pool.get_mut::<Stats>(target) {
             stats.effects.insert(Effect::Confuse, state.scheduler.time + 500);
+            state.events.push(GameEvent::EffectApplied{actor: action.actor, target, effect: Effect::Confuse});
             return true;
         };
-        false
-    } else {
-        false
-    };
-
-    if performed {
-        let name = utils::get_target_name(action, &state.spawning_pool);
-        if action.actor.unwrap() == state.player {
-            state.messages.log(MessageLevel::Important, format!("The {} is confused!", name));
-        } else {
-            state.messages.log(MessageLevel::Info, format!("The {} is confused!", name));
-        };
     }
-    performed
+    false
 }
 
 fn perform_heal(action: &Action, game_state: &mut GameState) {
@@ -264,20 +303,10 @@ fn perform_heal(action: &Action, game_state: &mut GameState) {
         Command::Heal{amount} => amount,
         _ => 0
     };
-    let mut performed = false;
     if let Some(ActionTarget::Entity(target)) = action.target {
         if let Some(stats) = game_state.spawning_pool.get_mut::<components::Stats>(target) {
             stats.health = min(stats.health + amount, stats.max_health);
-            performed = true;
-        }
-        if performed {
-            let actor_name = utils::get_actor_name(action, &game_state.spawning_pool);
-            let target_name = utils::get_target_name(action, &game_state.spawning_pool);
-            if actor_name == target_name {
-                game_state.messages.log(MessageLevel::Info, format!("{} healed for {}", target_name.to_sentence_case(), amount));
-            } else {
-                game_state.messages.log(MessageLevel::Info, format!("{} healed {} for {}", actor_name.to_sentence_case(), target_name, amount));
-            }
+            game_state.events.push(GameEvent::EntityHealed{healer: action.actor, target, amount});
         }
     }
 }
@@ -296,6 +325,7 @@ fn perform_drop_item(action: &Action, game_state: &mut GameState) {
     };
     if let Some(pos) = pos {
         game_state.spawning_pool.set(item_id.unwrap(), components::Physics{coord: pos});
+        game_state.events.push(GameEvent::ItemDropped{entity: action.actor.unwrap(), item: item_id.unwrap()});
     }
 }
 
@@ -313,9 +343,7 @@ fn perform_equip_item(action: &Action, game_state: &mut GameState) {
             }
         }
         if performed {
-            let name = utils::get_actor_name(action, &game_state.spawning_pool);
-            let item_name = utils::get_entity_name(item_id, &game_state.spawning_pool);
-            game_state.messages.log(MessageLevel::Info, format!("{} equips {}", name.to_sentence_case(), item_name));
+            game_state.events.push(GameEvent::ItemEquipped{entity: action.actor.unwrap(), item: item_id});
         }
     }
 }
@@ -334,9 +362,7 @@ fn perform_unequip_item(action: &Action, game_state: &mut GameState) {
             }
         }
         if performed {
-            let name = utils::get_actor_name(action, &game_state.spawning_pool);
-            let item_name = utils::get_entity_name(item_id, &game_state.spawning_pool);
-            game_state.messages.log(MessageLevel::Info, format!("{} takes off the {}", name.to_sentence_case(), item_name));
+            game_state.events.push(GameEvent::ItemUnequipped{entity: action.actor.unwrap(), item: item_id});
         }
     }
 }
@@ -356,9 +382,7 @@ fn perform_pick_up_item(action: &Action, game_state: &mut GameState) {
         }
         if picked {
             game_state.spawning_pool.remove::<components::Physics>(item_id);
-            let name = utils::get_actor_name(action, &game_state.spawning_pool);
-            let item_name = utils::get_entity_name(item_id, &game_state.spawning_pool);
-            game_state.messages.log(MessageLevel::Info, format!("{} picked up {}", name.to_sentence_case(), item_name));
+            game_state.events.push(GameEvent::ItemPickedUp{entity: action.actor.unwrap(), item: item_id});
         }
     }
 
@@ -367,15 +391,43 @@ fn perform_pick_up_item(action: &Action, game_state: &mut GameState) {
 fn perform_kill_entity(action: &Action, game_state: &mut GameState) {
     if game_state.spawning_pool.get::<components::Stats>(action.actor.unwrap()).is_some() {
         let name = utils::get_actor_name(action, &game_state.spawning_pool);
-        if action.actor.unwrap() == game_state.player {
-            game_state.messages.log(MessageLevel::Important, format!("The {} has died!", name));
-        } else {
-            game_state.messages.log(MessageLevel::Info, format!("The {} has died!", name));
-        }
+        game_state.events.push(GameEvent::EntityDied{entity: action.actor.unwrap(), name});
     }
+    drop_loot(action.actor.unwrap(), game_state);
     game_state.spawning_pool.remove_entity(action.actor.unwrap());
 }
 
+// The player lands on the next level, anything else dies in the fall.
+fn perform_fall(action: &Action, game_state: &mut GameState) {
+    let actor = action.actor.unwrap();
+    let name = utils::get_actor_name(action, &game_state.spawning_pool);
+    game_state.events.push(GameEvent::EntityFell{entity: actor, name});
+    if actor == game_state.player {
+        let level = game_state.level + 1;
+        game_state.change_level(level);
+    } else {
+        perform_kill_entity(action, game_state);
+    }
+}
+
+fn drop_loot(entity: EntityId, game_state: &mut GameState) {
+    let pos = match utils::get_position(entity, &game_state.spawning_pool) {
+        Some(pos) => pos,
+        None => return
+    };
+    let loot = match game_state.spawning_pool.get::<components::Loot>(entity) {
+        Some(loot) => loot.items.clone(),
+        None => return
+    };
+    for entry in loot {
+        if game_state.rng.gen_range(0, 100) < entry.chance {
+            if let Some(data) = game_state.data.item(&entry.item) {
+                crate::items::create_item(data, pos, &mut game_state.spawning_pool);
+            }
+        }
+    }
+}
+
 fn perform_take_damage(action: &Action, game_state: &mut GameState) {
     let damage = match action.command {
         Command::TakeDamage{damage} => {
@@ -388,24 +440,9 @@ fn perform_take_damage(action: &Action, game_state: &mut GameState) {
         if let Some(stats) = game_state.spawning_pool.get_mut::<components::Stats>(target) {
             stats.health -= damage;
         }
-    }
-
-    let attacker_name = utils::get_actor_name(action, &game_state.spawning_pool);
-    let target_name = utils::get_target_name(action, &game_state.spawning_pool);
-
-    let is_player = if let Some(ActionTarget::Entity(target)) = action.target {
-        if target == game_state.player {
-            true
-        } else {
-            false
-        }
-    } else {
-        false
-    };
-    if is_player {
-        game_state.messages.log(MessageLevel::Important, format!("The {} attacked the {} for {}", attacker_name, target_name, damage));
-    } else {
-        game_state.messages.log(MessageLevel::Info, format!("The {} attacked the {} for {}", attacker_name, target_name, damage));
+        game_state.events.push(GameEvent::EntityDamaged{attacker: action.actor, target, damage});
+        // getting hurt wakes anyone up
+        noise::set_awareness(target, components::Awareness::Hunting, game_state);
     }
 }
 
@@ -421,10 +458,11 @@ fn perform_open_door(action: &Action, game_state: &mut GameState) {
     let flags = game_state.spawning_pool.get_mut::<components::Flags>(id).unwrap();
     flags.solid = false;
     flags.block_sight = false;
+    game_state.events.push(GameEvent::DoorOpened{entity: action.actor, door: id});
 }
 
 fn perform_write_rune(action: &Action, state: &mut GameState) -> bool {
-    if let Command::WriteRune{spell} = action.command {
+    if let Command::WriteRune{ref spell} = action.command {
         let faction = match state.spawning_pool.get::<components::Information>(action.actor.unwrap()) {
             Some(info) => info.faction,
             None => components::Faction::Neutral
@@ -439,14 +477,10 @@ fn perform_write_rune(action: &Action, state: &mut GameState) -> bool {
                 }
                 if !has_rune {
                     write_rune_at(spell, pos, faction, &mut state.spawning_pool);
-
-                    let actor = utils::get_actor_name(action, &state.spawning_pool);
-                    let cspell = spells::Spell::create(spell);
-                    let msg = format!("The {} carves a {} rune on the floor", actor, cspell.name);
-                    state.messages.log(MessageLevel::Spell, msg);
+                    state.events.push(GameEvent::RuneWritten{entity: actor, spell: spell.clone(), position: pos});
                     return true;
                 } else {
-                    state.messages.log(MessageLevel::Info, "There is already a rune there");
+                    state.events.push(GameEvent::RuneBlocked{entity: actor, position: pos});
                 }
             }
         }
@@ -454,7 +488,7 @@ fn perform_write_rune(action: &Action, state: &mut GameState) -> bool {
     false
 }
 
-fn write_rune_at(spell: spells::Spells, pos: Point, faction: components::Faction, spawning_pool: &mut SpawningPool) {
+pub fn write_rune_at(spell: &str, pos: Point, faction: components::Faction, spawning_pool: &mut SpawningPool) {
     let rune = spawning_pool.spawn_entity();
     spawning_pool.set(rune, components::Visual{
         always_display: false,
@@ -472,8 +506,63 @@ fn write_rune_at(spell: spells::Spells, pos: Point, faction: components::Faction
         faction: faction,
         name: "Stun Rune".to_string()
     });
+    spawning_pool.set(rune, components::Light{
+        radius: 2,
+        color: colors::LIGHTEST_BLUE
+    });
     spawning_pool.set(rune, components::Trigger{
         kind: components::TriggerKind::Step,
-        on_trigger: Some(components::OnTriggerCallback::Spell(spell))
+        on_trigger: Some(components::OnTriggerCallback::Spell(spell.to_string()))
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::actions::*;
+    use crate::map::TileType;
+    use crate::testing;
+
+    #[test]
+    fn test_falling_player_lands_a_level_down() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let fall = Action::new(Some(player), None, Command::Fall);
+        assert_eq!(perform_action(&fall, &mut game.state), ActionResult::Performed{time: 0});
+        assert_eq!(game.state.level, 2);
+        assert!(get_entity_position(player, &game.state).is_some());
+    }
+
+    #[test]
+    fn test_falling_monsters_die_and_drop_their_loot() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let monster = testing::first_monster(&game.state);
+        let pos = get_entity_position(monster, &game.state).unwrap();
+        game.state.spawning_pool.set(monster, components::Loot{
+            items: vec![components::LootEntry{item: "sword".to_string(), chance: 100}]
+        });
+        let fall = Action::new(Some(monster), None, Command::Fall);
+        perform_action(&fall, &mut game.state);
+
+        assert_eq!(game.state.level, 1);
+        assert!(game.state.events.iter().any(|event| match *event {
+            GameEvent::EntityDied{entity, ..} => entity == monster,
+            _ => false
+        }));
+        assert!(game.state.spawning_pool.get_all::<components::Item>().iter().any(|&(item, _)| {
+            utils::get_position(item, &game.state.spawning_pool) == Some(pos)
+        }));
+    }
+
+    #[test]
+    fn test_water_and_rubble_are_slow_to_walk_through() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let dir = Point::new(1, 0);
+        for &(tile_type, time) in &[(TileType::Floor, 50), (TileType::Water, 100), (TileType::DeepWater, 100), (TileType::Rubble, 100)] {
+            let pos = get_entity_position(player, &game.state).unwrap();
+            testing::set_tile(&mut game.state, pos + dir, tile_type);
+            let walk = Action::new(Some(player), None, Command::WalkDirection{dir});
+            assert_eq!(perform_action(&walk, &mut game.state), ActionResult::Performed{time});
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use tcod::line::{Line};
+use geo::Line;
 use spawning_pool::{EntityId};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use crate::map::*;
 use geo::*;
 use crate::game::*;
@@ -24,16 +24,36 @@ pub fn melee_attack_entity(actor: EntityId, target: EntityId, state: &mut GameSt
     }
 }
 
+fn walk_direction(actor: EntityId, dir: Point) -> Option<Vec<Action>> {
+    Some(vec![Action::new(
+        Some(actor),
+        None,
+        Command::WalkDirection{dir}
+    )])
+}
+
+// Follows the map every creature shares towards the player, going around
+// anyone in the way.
+pub fn walk_towards_player(actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
+    let actor_position = get_entity_position(actor, state)?;
+    let next = state.navigation.to_player.downhill(actor_position, |pos| can_step(pos, state))?;
+    walk_direction(actor, next - actor_position)
+}
+
+// Running from the player follows the shared flee map, which avoids dead
+// ends. Anything else is just stepped away from.
 pub fn walk_to_away_from(actor: EntityId, pos: Point, state: &mut GameState) -> Option<Vec<Action>> {
     let actor_position = get_entity_position(actor, state)?;
+    if get_entity_position(state.player, state) == Some(pos) {
+        let next = state.navigation.from_player.downhill(actor_position, |pos| can_step(pos, state));
+        if let Some(next) = next {
+            return walk_direction(actor, next - actor_position);
+        }
+    }
     let mut dir: Point = actor_position.direction_to(pos).into();
     dir = dir * -1;
     if can_walk(actor_position + dir, &state.spatial_table, &state.map) {
-        Some(vec![Action::new(
-            Some(actor),
-            None,
-            Command::WalkDirection{dir}
-        )])
+        walk_direction(actor, dir)
     } else {
         None
     }
@@ -42,12 +62,7 @@ pub fn walk_to_away_from(actor: EntityId, pos: Point, state: &mut GameState) ->
 pub fn walk_to_position(actor: EntityId, end: Point, state: &mut GameState) -> Option<Vec<Action>> {
     let start= get_entity_position(actor, state)?;
     let next_pos = step_towards_position(actor, start, end, state)?;
-    let dir = Point::new(next_pos.x - start.x, next_pos.y - start.y);
-    Some(vec![Action::new(
-        Some(actor),
-        None,
-        Command::WalkDirection{dir}
-    )])
+    walk_direction(actor, next_pos - start)
 }
 
 pub fn wait_and_forget(actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
@@ -62,6 +77,21 @@ pub fn wait_and_forget(actor: EntityId, state: &mut GameState) -> Option<Vec<Act
     )])
 }
 
+// Goes to where the last noise came from, and forgets about it once there.
+// Creatures that heard nothing wait.
+pub fn investigate_noise(actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
+    use components::*;
+    if let Some(position) = state.spawning_pool.get::<AiMemory>(actor)?.noise_position {
+        if let Some(actions) = walk_to_position(actor, position, state) {
+            return Some(actions);
+        }
+        if let Some(mem) = state.spawning_pool.get_mut::<AiMemory>(actor) {
+            mem.noise_position = None;
+        }
+    }
+    wait_and_forget(actor, state)
+}
+
 pub fn cast_spell_at(actor: EntityId, target: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
     use components::*;
     let actor_position = get_entity_position(actor, state)?;
@@ -70,7 +100,7 @@ pub fn cast_spell_at(actor: EntityId, target: EntityId, state: &mut GameState) -
     if !is_visible {
         return None
     }
-    let spell = select_spell(actor, &state.spawning_pool)?;
+    let spell = select_spell(actor, state)?;
     if actor_position.distance(target_position) < spell.range as f32 {
         match spell.target {
             spells::SpellTargetType::Projectile => {
@@ -102,57 +132,44 @@ pub fn cast_spell_at(actor: EntityId, target: EntityId, state: &mut GameState) -
     }
 }
 
-fn select_spell(entity: EntityId, spawning_pool: &components::SpawningPool) -> Option<spells::Spell> {
-    if let Some(spell_book) = spawning_pool.get::<components::SpellBook>(entity) {
-        let spell = thread_rng().choose(&spell_book.spells)?;
-        Some(spells::Spell::create(*spell))
+fn select_spell(entity: EntityId, state: &mut GameState) -> Option<spells::Spell> {
+    if let Some(spell_book) = state.spawning_pool.get::<components::SpellBook>(entity) {
+        let spell = state.rng.choose(&spell_book.spells)?;
+        state.data.spell(spell)
     } else {
         None
     }
 }
 
+// Doors are walked into, which opens them.
+fn can_step(position: Point, state: &GameState) -> bool {
+    can_walk(position, &state.spatial_table, &state.map)
+        || is_closed_door(position, &state.spatial_table, &state.spawning_pool)
+}
+
+// Follows the remembered path while it is clear, finding a new one around
+// whatever got in the way otherwise.
 fn step_towards_position(actor: EntityId, start: Point, end: Point, state: &mut GameState) -> Option<Point> {
     use components::*;
-    if start == end {
-        return None;
+    let remembered = state.spawning_pool.get_mut::<AiMemory>(actor)?.path_memory.remember_path_to(start, end);
+    if let Some(next) = remembered {
+        if can_step(next, state) {
+            return Some(next);
+        }
     }
-    if let Some(mem) = state.spawning_pool.get_mut::<AiMemory>(actor) {
-        match mem.path_memory.remember_path_to(start, end) {
-            Some(next) => {
-                if can_walk(next, &state.spatial_table, &state.map) {
-                    Some(next)
-                } else {
-                    match path::path(start, end, &state.spatial_table, &state.map) {
-                        Some(mut path) => {
-                            let next = path.pop();
-                            mem.path_memory.path = Some(path);
-                            mem.path_memory.goal = Some(end);
-                            next
-                        },
-                        None => {
-                            mem.path_memory.forget();
-                            None
-                        }
-                    }
-                }
-            },
-            None => {
-                match path::path(start, end, &state.spatial_table, &state.map) {
-                    Some(mut path) => {
-                        let next = path.pop();
-                        mem.path_memory.path = Some(path);
-                        mem.path_memory.goal = Some(end);
-                        next
-                    },
-                    None => {
-                        mem.path_memory.forget();
-                        None
-                    }
-                }
-            }
+    let path = path::find_path(start, end, &state.map, path::walk_cost(actor, state));
+    let mem = state.spawning_pool.get_mut::<AiMemory>(actor)?;
+    match path {
+        Ok(mut path) => {
+            let next = path.pop();
+            mem.path_memory.path = Some(path);
+            mem.path_memory.goal = Some(end);
+            next
+        },
+        Err(_) => {
+            mem.path_memory.forget();
+            None
         }
-    } else {
-        None
     }
 }
 
@@ -1,76 +1,68 @@
 use spawning_pool::{EntityId};
 use crate::*;
+use crate::game::*;
 use crate::actions::*;
+use crate::noise;
 
 mod behaviour;
+pub mod tree;
 
-pub fn perform_basic_ai(actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
+// Creatures without an awareness always hunt. Sleeping ones don't look
+// around, suspicious ones start hunting once they spot the player.
+fn is_hunting(actor: EntityId, state: &mut GameState) -> bool {
     use components::*;
-    match behaviour::melee_attack_entity(actor, state.player, state) {
-        Some(actions) => Some(actions),
-        None => {
-            if !behaviour::can_see_entity(actor, state.player, state) {
-                match behaviour::recall_player_position(actor, state) {
-                    Some(position) => {
-                        match behaviour::walk_to_position(actor, position, state) {
-                            Some(actions) => Some(actions),
-                            None => behaviour::wait_and_forget(actor, state)
-                        }
-                    },
-                    None => behaviour::wait_and_forget(actor, state)
-                }
+    let awareness = match state.spawning_pool.get::<AiMemory>(actor) {
+        Some(memory) => memory.awareness,
+        None => return true
+    };
+    match awareness {
+        Awareness::Hunting => true,
+        Awareness::Unaware => false,
+        Awareness::Suspicious => {
+            if behaviour::can_see_entity(actor, state.player, state) {
+                noise::set_awareness(actor, Awareness::Hunting, state);
+                true
             } else {
-                let player_position = get_entity_position(state.player, state)?;
-                if let Some(mem) = state.spawning_pool.get_mut::<AiMemory>(actor) {
-                    mem.player_position = Some(player_position);
-                }
-                match behaviour::walk_to_position(actor, player_position, state) {
-                    Some(actions) => Some(actions),
-                    None => behaviour::wait_and_forget(actor, state)
-                }
+                false
             }
         }
     }
 }
 
-pub fn perform_spell_ai(actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
-    use components::*;
-    let actor_position = get_entity_position(actor, state)?;
-    if !behaviour::can_see_entity(actor, state.player, state) {
-        match behaviour::recall_player_position(actor, state) {
-            Some(position) => {
-                match behaviour::walk_to_position(actor, position, state) {
-                    Some(actions) => Some(actions),
-                    None => behaviour::wait_and_forget(actor, state)
-                }
-            },
-            None => behaviour::wait_and_forget(actor, state)
-        }
-    } else {
-        let player_position = get_entity_position(state.player, state)?;
-        if let Some(mem) = state.spawning_pool.get_mut::<AiMemory>(actor) {
-            mem.player_position = Some(player_position);
-        }
-        if actor_position.distance(player_position) < 3.0 {
-            match behaviour::walk_to_away_from(actor, player_position, state) {
-                Some(actions) => Some(actions),
-                None => {
-                    match behaviour::cast_spell_at(actor, state.player, state) {
-                        Some(actions) => Some(actions),
-                        None => behaviour::wait_and_forget(actor, state)
-                    }
-                }
-            }
-        } else {
-            match behaviour::cast_spell_at(actor, state.player, state) {
-                Some(actions) => Some(actions),
-                None => {
-                    match behaviour::walk_to_position(actor, player_position, state) {
-                        Some(actions) => Some(actions),
-                        None => behaviour::wait_and_forget(actor, state)
-                    }
-                }
-            }
-        }
+// Runs the behaviour tree the creature was given in its data.
+pub fn perform_ai(actor: EntityId, behaviour: &str, state: &mut GameState) -> Option<Vec<Action>> {
+    let data = state.data.clone();
+    data.behaviour(behaviour)?.run(actor, state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai::*;
+    use crate::testing;
+
+    #[test]
+    fn test_suspicious_creatures_hunt_once_they_see_the_player() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let monster = testing::first_monster(&game.state);
+        let player = get_entity_position(game.state.player, &game.state).unwrap();
+        let set_awareness = |state: &mut GameState, awareness| {
+            state.spawning_pool.get_mut::<components::AiMemory>(monster).unwrap().awareness = awareness;
+        };
+        let see_player = |state: &mut GameState, visible| {
+            state.spawning_pool.get_mut::<components::MapMemory>(monster).unwrap().set_visible(player.x, player.y, visible);
+        };
+
+        set_awareness(&mut game.state, components::Awareness::Unaware);
+        see_player(&mut game.state, true);
+        assert!(!is_hunting(monster, &mut game.state));
+
+        set_awareness(&mut game.state, components::Awareness::Suspicious);
+        see_player(&mut game.state, false);
+        assert!(!is_hunting(monster, &mut game.state));
+        see_player(&mut game.state, true);
+        assert!(is_hunting(monster, &mut game.state));
+
+        let memory = game.state.spawning_pool.get::<components::AiMemory>(monster).unwrap();
+        assert_eq!(memory.awareness, components::Awareness::Hunting);
     }
 }
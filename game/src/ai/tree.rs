@@ -0,0 +1,326 @@
+use std::convert::TryFrom;
+use spawning_pool::{EntityId};
+use yaml_rust::Yaml;
+use crate::*;
+use crate::game::*;
+use crate::actions::*;
+use crate::data::{DataError, load_file, entries};
+use crate::ai::{behaviour, is_hunting};
+
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Hunting,
+    SeesPlayer,
+    PlayerWithin(f32),
+    HealthBelow(i32)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Task {
+    Attack,
+    CastSpell,
+    WalkTowardsPlayer,
+    WalkToLastSeenPlayer,
+    Flee,
+    InvestigateNoise,
+    Wait
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Selector(Vec<Node>),
+    Sequence(Vec<Node>),
+    Not(Box<Node>),
+    Condition(Condition),
+    Task(Task)
+}
+
+enum Outcome {
+    Failure,
+    Success,
+    Act(Vec<Action>)
+}
+
+#[derive(Debug)]
+pub struct BehaviourTree {
+    pub name: String,
+    pub root: Node
+}
+
+impl BehaviourTree {
+    pub fn uses(&self, task: Task) -> bool {
+        self.root.uses(task)
+    }
+
+    // Creatures whose tree comes up with nothing to do wait.
+    pub fn run(&self, actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
+        match self.root.run(actor, state) {
+            Outcome::Act(actions) => Some(actions),
+            Outcome::Success | Outcome::Failure => behaviour::wait_and_forget(actor, state)
+        }
+    }
+}
+
+impl Node {
+    fn uses(&self, task: Task) -> bool {
+        match *self {
+            Node::Selector(ref children) | Node::Sequence(ref children) => children.iter().any(|child| child.uses(task)),
+            Node::Not(ref child) => child.uses(task),
+            Node::Condition(_) => false,
+            Node::Task(t) => t == task
+        }
+    }
+
+    // Selectors try their children until one doesn't fail, sequences until
+    // one does. The first task to come up with actions ends the turn.
+    fn run(&self, actor: EntityId, state: &mut GameState) -> Outcome {
+        match *self {
+            Node::Selector(ref children) => {
+                for child in children {
+                    match child.run(actor, state) {
+                        Outcome::Failure => continue,
+                        outcome => return outcome
+                    }
+                }
+                Outcome::Failure
+            },
+            Node::Sequence(ref children) => {
+                for child in children {
+                    match child.run(actor, state) {
+                        Outcome::Success => continue,
+                        outcome => return outcome
+                    }
+                }
+                Outcome::Success
+            },
+            Node::Not(ref child) => match child.run(actor, state) {
+                Outcome::Success => Outcome::Failure,
+                Outcome::Failure => Outcome::Success,
+                outcome => outcome
+            },
+            Node::Condition(ref condition) => {
+                if condition.check(actor, state) {
+                    Outcome::Success
+                } else {
+                    Outcome::Failure
+                }
+            },
+            Node::Task(task) => match task.perform(actor, state) {
+                Some(actions) => Outcome::Act(actions),
+                None => Outcome::Failure
+            }
+        }
+    }
+}
+
+impl Condition {
+    fn check(&self, actor: EntityId, state: &mut GameState) -> bool {
+        use components::*;
+        match *self {
+            Condition::Hunting => is_hunting(actor, state),
+            // seeing the player is remembered for when they get out of sight
+            Condition::SeesPlayer => {
+                if !behaviour::can_see_entity(actor, state.player, state) {
+                    return false;
+                }
+                let player_position = get_entity_position(state.player, state);
+                if let Some(mem) = state.spawning_pool.get_mut::<AiMemory>(actor) {
+                    mem.player_position = player_position;
+                }
+                true
+            },
+            Condition::PlayerWithin(distance) => {
+                match (get_entity_position(actor, state), get_entity_position(state.player, state)) {
+                    (Some(actor_position), Some(player_position)) => actor_position.distance(player_position) < distance,
+                    _ => false
+                }
+            },
+            Condition::HealthBelow(percent) => {
+                state.spawning_pool.get::<Stats>(actor).map_or(false, |stats| stats.health * 100 < stats.max_health * percent)
+            }
+        }
+    }
+}
+
+impl Task {
+    fn perform(self, actor: EntityId, state: &mut GameState) -> Option<Vec<Action>> {
+        match self {
+            Task::Attack => behaviour::melee_attack_entity(actor, state.player, state),
+            Task::CastSpell => behaviour::cast_spell_at(actor, state.player, state),
+            Task::WalkTowardsPlayer => behaviour::walk_towards_player(actor, state),
+            Task::WalkToLastSeenPlayer => {
+                let position = behaviour::recall_player_position(actor, state)?;
+                behaviour::walk_to_position(actor, position, state)
+            },
+            Task::Flee => {
+                let player_position = get_entity_position(state.player, state)?;
+                behaviour::walk_to_away_from(actor, player_position, state)
+            },
+            Task::InvestigateNoise => behaviour::investigate_noise(actor, state),
+            Task::Wait => behaviour::wait_and_forget(actor, state)
+        }
+    }
+}
+
+const TASK_NAMES: [(&str, Task); 7] = [
+    ("attack", Task::Attack),
+    ("cast spell", Task::CastSpell),
+    ("walk towards player", Task::WalkTowardsPlayer),
+    ("walk to last seen player", Task::WalkToLastSeenPlayer),
+    ("flee", Task::Flee),
+    ("investigate noise", Task::InvestigateNoise),
+    ("wait", Task::Wait)
+];
+
+pub fn load_behaviours(file: &str) -> Result<Vec<BehaviourTree>, DataError> {
+    let base = load_file(file)?;
+    let mut behaviours = vec![];
+    for entry in entries(file, &base)? {
+        if !entry.has("tree") {
+            return Err(entry.error("tree", "missing".to_string()));
+        }
+        behaviours.push(BehaviourTree{
+            name: entry.str("name")?.to_string(),
+            root: get_node(&entry.yaml["tree"]).map_err(|message| entry.error("tree", message))?
+        });
+    }
+    Ok(behaviours)
+}
+
+// A node is either the name of a task or condition, or a single entry map
+// of a composite to its children or a condition to its amount.
+fn get_node(node: &Yaml) -> Result<Node, String> {
+    match *node {
+        Yaml::String(ref name) => {
+            if let Some((_, task)) = TASK_NAMES.iter().find(|(task, _)| task == name) {
+                return Ok(Node::Task(*task));
+            }
+            match name.as_str() {
+                "hunting" => Ok(Node::Condition(Condition::Hunting)),
+                "sees player" => Ok(Node::Condition(Condition::SeesPlayer)),
+                _ => Err(format!("unknown task or condition \"{}\"", name))
+            }
+        },
+        Yaml::Hash(ref hash) if hash.len() == 1 => {
+            let (name, value) = hash.iter().next().unwrap();
+            let name = name.as_str().ok_or_else(|| "node names must be strings".to_string())?;
+            let amount = || value.as_i64()
+                .and_then(|amount| i32::try_from(amount).ok())
+                .ok_or_else(|| format!("expected a number for {}", name));
+            match name {
+                "selector" => Ok(Node::Selector(get_children(name, value)?)),
+                "sequence" => Ok(Node::Sequence(get_children(name, value)?)),
+                "not" => Ok(Node::Not(Box::new(get_node(value)?))),
+                "player within" => Ok(Node::Condition(Condition::PlayerWithin(amount()? as f32))),
+                "health below" => Ok(Node::Condition(Condition::HealthBelow(amount()?))),
+                _ => Err(format!("unknown node \"{}\"", name))
+            }
+        },
+        _ => Err("expected a task or condition name, or a map of node to its children or amount".to_string())
+    }
+}
+
+fn get_children(name: &str, children: &Yaml) -> Result<Vec<Node>, String> {
+    match *children {
+        Yaml::Array(ref children) if !children.is_empty() => children.iter().map(get_node).collect(),
+        _ => Err(format!("expected a list of nodes for {}", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yaml_rust::YamlLoader;
+    use crate::ai::tree::*;
+    use crate::data::{GameData, TEST_DATA};
+    use geo::Point;
+    use crate::navigation::Navigation;
+    use crate::testing;
+
+    fn parse(source: &str) -> Result<Node, String> {
+        get_node(&YamlLoader::load_from_str(source).unwrap()[0])
+    }
+
+    // What running the node comes to for a monster far from the player at
+    // full health.
+    fn run(source: &str) -> &'static str {
+        let mut game = testing::new_game([7, 7, 7, 7]);
+        let actor = testing::first_monster(&game.state);
+        match parse(source).unwrap().run(actor, &mut game.state) {
+            Outcome::Failure => "failure",
+            Outcome::Success => "success",
+            Outcome::Act(_) => "act"
+        }
+    }
+
+    #[test]
+    fn test_composites() {
+        let healthy = "{ not: { health below: 100 } }";
+        let hurt = "{ health below: 100 }";
+        assert_eq!(run(healthy), "success");
+        assert_eq!(run(hurt), "failure");
+        assert_eq!(run("attack"), "failure");
+        assert_eq!(run("wait"), "act");
+
+        assert_eq!(run(&format!("{{ selector: [{}, wait] }}", hurt)), "act");
+        assert_eq!(run(&format!("{{ selector: [{}, {}] }}", hurt, healthy)), "success");
+        assert_eq!(run(&format!("{{ selector: [{}, attack] }}", hurt)), "failure");
+        assert_eq!(run(&format!("{{ sequence: [{}, wait] }}", healthy)), "act");
+        assert_eq!(run(&format!("{{ sequence: [{}, wait] }}", hurt)), "failure");
+        assert_eq!(run(&format!("{{ sequence: [{}, {}] }}", healthy, healthy)), "success");
+        // a task that acts ends the turn whatever comes after it
+        assert_eq!(run(&format!("{{ sequence: [wait, {}] }}", hurt)), "act");
+        assert_eq!(run("{ not: wait }"), "act");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("{ selector: [attack, { sequence: [hunting, flee] }] }").is_ok());
+        assert_eq!(parse("dance").unwrap_err(), "unknown task or condition \"dance\"");
+        assert_eq!(parse("{ dance: [] }").unwrap_err(), "unknown node \"dance\"");
+        assert_eq!(parse("{ selector: [] }").unwrap_err(), "expected a list of nodes for selector");
+        assert_eq!(parse("{ player within: far }").unwrap_err(), "expected a number for player within");
+        assert!(parse("{ selector: [attack], sequence: [attack] }").is_err());
+        assert!(parse("3").is_err());
+    }
+
+    #[test]
+    fn test_shipped_trees() {
+        let data = GameData::load_from(TEST_DATA).unwrap();
+        assert!(data.behaviour("spell caster").unwrap().uses(Task::CastSpell));
+        assert!(!data.behaviour("basic").unwrap().uses(Task::CastSpell));
+        assert!(data.behaviour("cowardly").unwrap().uses(Task::Flee));
+        for behaviour in &data.behaviours {
+            assert!(data.creatures.iter().any(|creature| creature.ai == behaviour.name), "nobody uses {}", behaviour.name);
+        }
+    }
+
+    // Which way a hunting coward three steps east of the player walks.
+    fn coward_steps(health: i32) -> i32 {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let actor = testing::first_monster(&game.state);
+        let start = Point::new(10, 10);
+        testing::empty_level(&mut game.state, 1.0);
+        game.state.spawning_pool.set(player, components::Physics{coord: start});
+        game.state.spawning_pool.set(actor, components::Physics{coord: start + Point::new(3, 0)});
+        game.state.spatial_table.reset(&game.state.spawning_pool);
+        game.state.navigation = Navigation::new(&game.state);
+        game.state.spawning_pool.get_mut::<components::AiMemory>(actor).unwrap().awareness = components::Awareness::Hunting;
+        game.state.spawning_pool.get_mut::<components::MapMemory>(actor).unwrap().set_visible(start.x, start.y, true);
+        let stats = game.state.spawning_pool.get_mut::<components::Stats>(actor).unwrap();
+        stats.health = stats.max_health * health / 100;
+
+        let data = game.state.data.clone();
+        let actions = data.behaviour("cowardly").unwrap().run(actor, &mut game.state).unwrap();
+        match actions[0].command {
+            Command::WalkDirection{dir} => dir.x,
+            _ => panic!("expected a step, got {:?}", actions[0].command)
+        }
+    }
+
+    #[test]
+    fn test_hurt_cowards_run_away() {
+        assert_eq!(coward_steps(100), -1);
+        assert_eq!(coward_steps(40), 1);
+    }
+}
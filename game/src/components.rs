@@ -1,9 +1,9 @@
 use std::collections::HashMap;
+use fnv::FnvHashMap;
 use std::fmt;
 use std;
-use tcod::colors;
+use geo::colors;
 use geo::*;
-use crate::spells;
 
 use spawning_pool::EntityId;
 use spawning_pool::storage::{Storage, VectorStorage, HashMapStorage};
@@ -65,16 +65,36 @@ impl PathMemory {
     }
 }
 
+// Creatures start out asleep. A noise makes them suspicious and go look for
+// where it came from, spotting the player makes them hunt.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Awareness {
+    Unaware,
+    Suspicious,
+    Hunting
+}
+
+// Games saved before creatures could sleep only had hunters in them.
+fn hunting() -> Awareness {
+    Awareness::Hunting
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiMemory {
     pub player_position: Option<Point>,
-    pub path_memory: PathMemory
+    pub path_memory: PathMemory,
+    #[serde(default = "hunting")]
+    pub awareness: Awareness,
+    #[serde(default)]
+    pub noise_position: Option<Point>
 }
 
 impl AiMemory {
     pub fn new() -> AiMemory {
         AiMemory {
             player_position: None,
+            awareness: Awareness::Unaware,
+            noise_position: None,
             path_memory: PathMemory {
                 previous_position: None,
                 goal: None,
@@ -84,18 +104,19 @@ impl AiMemory {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+// Monsters run the behaviour tree of that name from the data.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AI {
     Player,
-    Basic,
-    SpellCaster
+    Behaviour(String)
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Effect {
     Slow,
     Stun,
-    Confuse
+    Confuse,
+    Burning
 }
 
 impl fmt::Display for Effect {
@@ -103,7 +124,8 @@ impl fmt::Display for Effect {
         match self {
             Effect::Slow => write!(f, "slow"),
             Effect::Stun => write!(f, "stun"),
-            Effect::Confuse => write!(f, "confuse")
+            Effect::Confuse => write!(f, "confuse"),
+            Effect::Burning => write!(f, "burning")
         }
     }
 }
@@ -115,7 +137,7 @@ pub struct Stats {
     pub strength: i32,
     pub defense: i32,
     pub points: i32,
-    pub effects: HashMap<Effect, i32>
+    pub effects: FnvHashMap<Effect, i32>
 }
 
 impl Stats {
@@ -133,7 +155,58 @@ impl Stats {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpellBook {
-    pub spells: Vec<spells::Spells>
+    pub spells: Vec<String>
+}
+
+// Scales the time an entity's actions take, a speed of 2 acts twice as often.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Speed {
+    pub multiplier: f32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sight {
+    pub radius: i32
+}
+
+// Lights up the tiles around the entity, fading out towards the radius.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Light {
+    pub radius: i32,
+    pub color: colors::Color
+}
+
+// Adds to how far away the entity hears noises.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Perception {
+    pub value: i32
+}
+
+// Lets the entity move through deep water.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swimmer;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootEntry {
+    pub item: String,
+    pub chance: u32
+}
+
+// Items rolled for when the entity dies, each with a percent chance to drop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Loot {
+    pub items: Vec<LootEntry>
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum StairsDirection {
+    Up,
+    Down
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stairs {
+    pub direction: StairsDirection
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -215,7 +288,7 @@ pub struct StatisticsBonus {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum OnUseCallback {
-    Spell(spells::Spells)
+    Spell(String)
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -240,7 +313,7 @@ pub enum TriggerKind {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum OnTriggerCallback {
-    Spell(spells::Spells)
+    Spell(String)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -291,5 +364,12 @@ create_spawning_pool!(
     (Inventory, inventory, HashMapStorage),
     (Equipment, equipment, HashMapStorage),
     (SpellBook, spell_book, HashMapStorage),
-    (Duration, duration, HashMapStorage)
+    (Duration, duration, HashMapStorage),
+    (Speed, speed, HashMapStorage),
+    (Sight, sight, HashMapStorage),
+    (Swimmer, swimmer, HashMapStorage),
+    (Light, light, HashMapStorage),
+    (Perception, perception, HashMapStorage),
+    (Loot, loot, HashMapStorage),
+    (Stairs, stairs, HashMapStorage)
 );
@@ -1,11 +1,10 @@
-use std::io::Read;
-use std::fs::File;
-use tcod::colors;
+use geo::colors;
+use yaml_rust::Yaml;
 
 use spawning_pool::{EntityId};
 use geo::*;
 use crate::components;
-use crate::spells;
+use crate::data::{DataError, Entry, load_file, entries};
 
 #[derive(Debug)]
 pub struct CreatureData {
@@ -15,44 +14,127 @@ pub struct CreatureData {
     pub health: i32,
     pub strength: i32,
     pub defense: i32,
-    pub ai: components::AI
+    pub ai: String,
+    pub faction: components::Faction,
+    pub spells: Vec<String>,
+    pub speed: f32,
+    pub sight: i32,
+    pub perception: i32,
+    pub min_depth: u32,
+    pub max_depth: Option<u32>,
+    pub weight: u32,
+    pub cost: u32,
+    pub minions: Vec<String>,
+    pub loot: Vec<components::LootEntry>,
+    pub swims: bool
 }
 
-pub fn load_creatures() -> Vec<CreatureData> {
-    use yaml_rust::YamlLoader;
-    let mut data = String::new();
+impl CreatureData {
+    // How likely the creature is to be picked on a level, zero outside of
+    // its depth range.
+    pub fn spawn_weight(&self, level: u32) -> u32 {
+        if level < self.min_depth || self.max_depth.map_or(false, |max| level > max) {
+            0
+        } else {
+            self.weight
+        }
+    }
+
+    pub fn is_caster(&self) -> bool {
+        !self.spells.is_empty()
+    }
+}
+
+const FACTION_NAMES: [(&str, components::Faction); 3] = [
+    ("player", components::Faction::Player),
+    ("neutral", components::Faction::Neutral),
+    ("enemy", components::Faction::Enemy)
+];
+
+pub fn load_creatures(file: &str) -> Result<Vec<CreatureData>, DataError> {
+    let base = load_file(file)?;
     let mut creatures = vec![];
-    if let Ok(mut file) = File::open("./data/creatures.yaml") {
-        let _ = file.read_to_string(&mut data);
-        let docs = YamlLoader::load_from_str(&data).unwrap();
-        for base_docs in docs[0]["Base"].as_vec().unwrap() {
-            let base = base_docs.as_hash().unwrap();
-            for (_, data) in base.iter() {
-                let ai = match data["ai"].as_str().unwrap() {
-                    "basic" => components::AI::Basic,
-                    "spell caster" => components::AI::SpellCaster,
-                    _ => unreachable!()
-                };
-                creatures.push(CreatureData{
-                    name: data["name"].as_str().unwrap().to_string(),
-                    glyph: data["glyph"].as_str().unwrap().chars().next().unwrap(),
-                    color: get_color(data["color"].as_str().unwrap()),
-                    health: data["health"].as_i64().unwrap() as i32,
-                    strength: data["strength"].as_i64().unwrap() as i32,
-                    defense: data["defense"].as_i64().unwrap() as i32,
-                    ai
-                });
-            }
+    for entry in entries(file, &base)? {
+        let faction = if entry.has("faction") {
+            entry.one_of("faction", &FACTION_NAMES)?
+        } else {
+            components::Faction::Enemy
+        };
+        let spells = get_names(&entry, "spells")?;
+        let speed = if entry.has("speed") { entry.f32("speed")? } else { 1.0 };
+        if speed <= 0.0 {
+            return Err(entry.error("speed", "must be above zero".to_string()));
+        }
+        let min_depth = entry.opt_i32("min depth", 1)?;
+        let max_depth = if entry.has("max depth") { Some(entry.i32("max depth")?) } else { None };
+        if min_depth < 1 || max_depth.map_or(false, |max| max < min_depth) {
+            return Err(entry.error("max depth", "depth range must start at 1 and not end before it starts".to_string()));
+        }
+        let weight = entry.opt_i32("weight", 1)?;
+        if weight < 0 {
+            return Err(entry.error("weight", "must not be negative".to_string()));
+        }
+        let cost = entry.opt_i32("cost", 1)?;
+        if cost < 1 {
+            return Err(entry.error("cost", "must be at least 1".to_string()));
         }
+        creatures.push(CreatureData{
+            name: entry.str("name")?.to_string(),
+            glyph: entry.glyph("glyph")?,
+            color: entry.color("color")?,
+            health: entry.i32("health")?,
+            strength: entry.i32("strength")?,
+            defense: entry.i32("defense")?,
+            ai: entry.str("ai")?.to_string(),
+            faction,
+            spells,
+            speed,
+            sight: entry.opt_i32("sight", 20)?,
+            perception: entry.opt_i32("perception", 0)?,
+            min_depth: min_depth as u32,
+            max_depth: max_depth.map(|max| max as u32),
+            weight: weight as u32,
+            cost: cost as u32,
+            minions: get_names(&entry, "minions")?,
+            loot: get_loot(&entry)?,
+            swims: entry.opt_bool("swims", false)?
+        });
+    }
+    Ok(creatures)
+}
+
+fn get_names(entry: &Entry, field: &str) -> Result<Vec<String>, DataError> {
+    match entry.yaml[field] {
+        Yaml::BadValue => Ok(vec![]),
+        Yaml::Array(ref names) => names
+            .iter()
+            .map(|name| name.as_str()
+                .map(|name| name.to_string())
+                .ok_or_else(|| entry.error(field, "expected a list of strings".to_string())))
+            .collect(),
+        _ => Err(entry.error(field, "expected a list of strings".to_string()))
     }
-    creatures
 }
 
-fn get_color(name: &str) -> colors::Color {
-    match name {
-        "light green" => colors::LIGHT_GREEN,
-        _ => colors::PINK
+// Loot maps item names to the percent chance of dropping them.
+fn get_loot(entry: &Entry) -> Result<Vec<components::LootEntry>, DataError> {
+    let mut loot = vec![];
+    match entry.yaml["loot"] {
+        Yaml::BadValue => {},
+        Yaml::Hash(ref hash) => {
+            for (item, chance) in hash.iter() {
+                match (item.as_str(), chance.as_i64()) {
+                    (Some(item), Some(chance)) if chance >= 0 && chance <= 100 => loot.push(components::LootEntry{
+                        item: item.to_string(),
+                        chance: chance as u32
+                    }),
+                    _ => return Err(entry.error("loot", "expected item names with a chance from 0 to 100".to_string()))
+                }
+            }
+        },
+        _ => return Err(entry.error("loot", "expected a map of item name to chance".to_string()))
     }
+    Ok(loot)
 }
 
 pub fn create_creature(data: &CreatureData, pos: Point, width: i32, height: i32, spawning_pool: &mut components::SpawningPool) -> EntityId {
@@ -70,10 +152,10 @@ pub fn create_creature(data: &CreatureData, pos: Point, width: i32, height: i32,
         solid: true
     });
     spawning_pool.set(creature, components::Controller{
-        ai: data.ai
+        ai: components::AI::Behaviour(data.ai.clone())
     });
     spawning_pool.set(creature, components::Information{
-        faction: components::Faction::Enemy,
+        faction: data.faction,
         name: data.name.to_string()
     });
     spawning_pool.set(creature, components::Stats::new(
@@ -83,10 +165,27 @@ pub fn create_creature(data: &CreatureData, pos: Point, width: i32, height: i32,
     ));
     spawning_pool.set(creature, components::MapMemory::new(width, height));
     spawning_pool.set(creature, components::AiMemory::new());
-    if data.ai == components::AI::SpellCaster {
+    spawning_pool.set(creature, components::Speed{
+        multiplier: data.speed
+    });
+    spawning_pool.set(creature, components::Sight{
+        radius: data.sight
+    });
+    spawning_pool.set(creature, components::Perception{
+        value: data.perception
+    });
+    if !data.spells.is_empty() {
         spawning_pool.set(creature, components::SpellBook{
-            spells: vec![spells::Spells::MagicMissile]
+            spells: data.spells.clone()
         });
     }
+    if !data.loot.is_empty() {
+        spawning_pool.set(creature, components::Loot{
+            items: data.loot.clone()
+        });
+    }
+    if data.swims {
+        spawning_pool.set(creature, components::Swimmer);
+    }
     creature
 }
@@ -0,0 +1,713 @@
+use std::fmt;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::io::Read;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use geo::colors;
+use yaml_rust::{Yaml, YamlLoader};
+use map_generator::vaults::Vault;
+
+use crate::spells::{self, Spell};
+use crate::creatures::{self, CreatureData};
+use crate::items::{self, ItemData};
+use crate::levels::{self, LevelData};
+use crate::components::OnUseCallback;
+use crate::ai::tree::{self, BehaviourTree, Task};
+
+// Files of the data folder.
+const SPELLS_FILE: &str = "spells.yaml";
+const CREATURES_FILE: &str = "creatures.yaml";
+const ITEMS_FILE: &str = "items.yaml";
+const LEVELS_FILE: &str = "levels.yaml";
+const AI_FILE: &str = "ai.yaml";
+const VAULTS_DIR: &str = "vaults";
+
+// Tests run from the crate folder, the data folder is next to it.
+#[cfg(test)]
+pub const TEST_DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../data");
+
+// Definitions loaded from the data folder. They are not part of the save
+// game, they are loaded once at startup and shared by every game.
+#[derive(Default)]
+pub struct GameData {
+    pub root: PathBuf,
+    pub spells: Vec<Spell>,
+    pub creatures: Vec<CreatureData>,
+    pub items: Vec<ItemData>,
+    pub levels: Vec<LevelData>,
+    pub vaults: Vec<Vault>,
+    pub behaviours: Vec<BehaviourTree>
+}
+
+impl GameData {
+    pub fn load() -> Result<GameData, DataError> {
+        GameData::load_from("./data")
+    }
+
+    pub fn load_from<P: AsRef<Path>>(root: P) -> Result<GameData, DataError> {
+        let root = root.as_ref().to_path_buf();
+        let path = |file: &str| root.join(file).to_string_lossy().to_string();
+        let mut data = GameData {
+            spells: spells::load_spells(&path(SPELLS_FILE))?,
+            creatures: creatures::load_creatures(&path(CREATURES_FILE))?,
+            items: items::load_items(&path(ITEMS_FILE))?,
+            levels: levels::load_levels(&path(LEVELS_FILE))?,
+            vaults: load_vaults(&path(VAULTS_DIR))?,
+            behaviours: tree::load_behaviours(&path(AI_FILE))?,
+            root
+        };
+        // random picks walk these lists in order, sorting them keeps the
+        // game the same when entries are moved around in the files
+        data.creatures.sort_by(|a, b| a.name.cmp(&b.name));
+        data.items.sort_by(|a, b| a.name.cmp(&b.name));
+        data.levels.sort_by_key(|level| level.depth);
+        data.validate()?;
+        Ok(data)
+    }
+
+    // Checks the references between files, every file on its own has been
+    // checked while loading.
+    fn validate(&self) -> Result<(), DataError> {
+        let reference_error = |file: &str, entry: &str, field: &str, message: String| DataError {
+            file: self.path(file),
+            entry: Some(entry.to_string()),
+            field: Some(field.to_string()),
+            message
+        };
+        for item in &self.items {
+            if let Some(OnUseCallback::Spell(ref spell)) = item.on_use {
+                if self.spell(spell).is_none() {
+                    return Err(reference_error(ITEMS_FILE, &item.name, "spell", format!("unknown spell \"{}\"", spell)));
+                }
+            }
+        }
+        for pair in self.creatures.windows(2) {
+            if pair[0].name == pair[1].name {
+                return Err(reference_error(CREATURES_FILE, &pair[1].name, "name", "used by more than one creature".to_string()));
+            }
+        }
+        for pair in self.items.windows(2) {
+            if pair[0].name == pair[1].name {
+                return Err(reference_error(ITEMS_FILE, &pair[1].name, "name", "used by more than one item".to_string()));
+            }
+        }
+        match self.levels.first() {
+            Some(level) if level.depth == 1 => {},
+            _ => return Err(DataError::file(&self.path(LEVELS_FILE), "no level starts at depth 1".to_string()))
+        }
+        for pair in self.levels.windows(2) {
+            if pair[0].depth == pair[1].depth {
+                return Err(reference_error(LEVELS_FILE, &pair[1].name, "depth", format!("depth {} is already used by {}", pair[1].depth, pair[0].name)));
+            }
+            if pair[0].last {
+                return Err(reference_error(LEVELS_FILE, &pair[0].name, "last", "only the deepest level can be the last".to_string()));
+            }
+        }
+        for level in &self.levels {
+            for monster in &level.monsters {
+                if self.creature(monster).is_none() {
+                    return Err(reference_error(LEVELS_FILE, &level.name, "monsters", format!("unknown creature \"{}\"", monster)));
+                }
+            }
+        }
+        for creature in &self.creatures {
+            for minion in &creature.minions {
+                match self.creature(minion) {
+                    Some(minion) if minion.minions.is_empty() => {},
+                    Some(_) => return Err(reference_error(CREATURES_FILE, &creature.name, "minions", format!("\"{}\" leads minions of its own", minion))),
+                    None => return Err(reference_error(CREATURES_FILE, &creature.name, "minions", format!("unknown creature \"{}\"", minion)))
+                }
+            }
+            match self.behaviour(&creature.ai) {
+                Some(behaviour) if behaviour.uses(Task::CastSpell) && creature.spells.is_empty() => {
+                    return Err(reference_error(CREATURES_FILE, &creature.name, "spells", format!("\"{}\" casts spells, it needs at least one", creature.ai)));
+                },
+                Some(_) => {},
+                None => return Err(reference_error(CREATURES_FILE, &creature.name, "ai", format!("unknown behaviour \"{}\"", creature.ai)))
+            }
+            for spell in &creature.spells {
+                if self.spell(spell).is_none() {
+                    return Err(reference_error(CREATURES_FILE, &creature.name, "spells", format!("unknown spell \"{}\"", spell)));
+                }
+            }
+            for loot in &creature.loot {
+                if self.item(&loot.item).is_none() {
+                    return Err(reference_error(CREATURES_FILE, &creature.name, "loot", format!("unknown item \"{}\"", loot.item)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.root.join(file).to_string_lossy().to_string()
+    }
+
+    pub fn spell(&self, id: &str) -> Option<Spell> {
+        self.spells.iter().find(|spell| spell.id == id).cloned()
+    }
+
+    pub fn creature(&self, name: &str) -> Option<&CreatureData> {
+        self.creatures.iter().find(|creature| creature.name == name)
+    }
+
+    pub fn item(&self, name: &str) -> Option<&ItemData> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    pub fn behaviour(&self, name: &str) -> Option<&BehaviourTree> {
+        self.behaviours.iter().find(|behaviour| behaviour.name == name)
+    }
+
+    // The configuration of the deepest entry starting at or above `depth`.
+    pub fn level(&self, depth: u32) -> &LevelData {
+        self.levels
+            .iter()
+            .filter(|level| level.depth <= depth)
+            .last()
+            .expect("levels are validated to start at depth 1")
+    }
+
+    // The last level holds the portal out of the dungeon instead of stairs
+    // further down.
+    pub fn is_final_level(&self, depth: u32) -> bool {
+        self.levels.last().map_or(false, |level| level.last && level.depth == depth)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataError {
+    pub file: String,
+    pub entry: Option<String>,
+    pub field: Option<String>,
+    pub message: String
+}
+
+impl DataError {
+    pub fn file(file: &str, message: String) -> DataError {
+        DataError {
+            file: file.to_string(),
+            entry: None,
+            field: None,
+            message
+        }
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(ref entry) = self.entry {
+            write!(f, ", entry {}", entry)?;
+        }
+        if let Some(ref field) = self.field {
+            write!(f, ", field {}", field)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Error for DataError {}
+
+// A single named entry of a data file, with typed accessors that report
+// which file, entry and field a problem was found in.
+pub struct Entry<'a> {
+    pub file: &'a str,
+    pub name: String,
+    pub yaml: &'a Yaml
+}
+
+impl<'a> Entry<'a> {
+    pub fn error(&self, field: &str, message: String) -> DataError {
+        DataError {
+            file: self.file.to_string(),
+            entry: Some(self.name.clone()),
+            field: Some(field.to_string()),
+            message
+        }
+    }
+
+    pub fn has(&self, field: &str) -> bool {
+        !self.yaml[field].is_badvalue()
+    }
+
+    pub fn str(&self, field: &str) -> Result<&'a str, DataError> {
+        match self.yaml[field] {
+            Yaml::String(ref value) => Ok(value),
+            Yaml::BadValue => Err(self.error(field, "missing".to_string())),
+            _ => Err(self.error(field, "expected a string".to_string()))
+        }
+    }
+
+    pub fn opt_str(&self, field: &str) -> Result<Option<&'a str>, DataError> {
+        if self.has(field) {
+            self.str(field).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn i32(&self, field: &str) -> Result<i32, DataError> {
+        match self.yaml[field] {
+            Yaml::Integer(value) => i32::try_from(value)
+                .map_err(|_| self.error(field, format!("{} is out of range", value))),
+            Yaml::BadValue => Err(self.error(field, "missing".to_string())),
+            _ => Err(self.error(field, "expected a number".to_string()))
+        }
+    }
+
+    pub fn f32(&self, field: &str) -> Result<f32, DataError> {
+        match self.yaml[field] {
+            Yaml::Integer(value) => Ok(value as f32),
+            Yaml::Real(_) => self.yaml[field].as_f64()
+                .map(|value| value as f32)
+                .ok_or_else(|| self.error(field, "expected a number".to_string())),
+            Yaml::BadValue => Err(self.error(field, "missing".to_string())),
+            _ => Err(self.error(field, "expected a number".to_string()))
+        }
+    }
+
+    pub fn opt_i32(&self, field: &str, default: i32) -> Result<i32, DataError> {
+        if self.has(field) {
+            self.i32(field)
+        } else {
+            Ok(default)
+        }
+    }
+
+    pub fn opt_bool(&self, field: &str, default: bool) -> Result<bool, DataError> {
+        match self.yaml[field] {
+            Yaml::BadValue => Ok(default),
+            Yaml::Boolean(value) => Ok(value),
+            _ => Err(self.error(field, "expected true or false".to_string()))
+        }
+    }
+
+    pub fn glyph(&self, field: &str) -> Result<char, DataError> {
+        let value = self.str(field)?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(glyph), None) => Ok(glyph),
+            _ => Err(self.error(field, format!("expected a single character, got \"{}\"", value)))
+        }
+    }
+
+    pub fn color(&self, field: &str) -> Result<colors::Color, DataError> {
+        if !self.has(field) {
+            return Err(self.error(field, "missing".to_string()));
+        }
+        get_color(&self.yaml[field]).map_err(|message| self.error(field, message))
+    }
+
+    // Matches the value of a field against a list of names.
+    pub fn one_of<T: Clone>(&self, field: &str, choices: &[(&str, T)]) -> Result<T, DataError> {
+        let value = self.str(field)?;
+        choices
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, choice)| choice.clone())
+            .ok_or_else(|| {
+                let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+                self.error(field, format!("unknown value \"{}\", expected one of: {}", value, names.join(", ")))
+            })
+    }
+}
+
+// Data files hold a list of single entry maps under `Base`, keyed by the
+// entry name.
+pub fn load_file(file: &str) -> Result<Vec<Yaml>, DataError> {
+    let mut data = String::new();
+    File::open(file)
+        .and_then(|mut f| f.read_to_string(&mut data))
+        .map_err(|err| DataError::file(file, err.to_string()))?;
+    let mut docs = YamlLoader::load_from_str(&data)
+        .map_err(|err| DataError::file(file, err.to_string()))?;
+    if docs.is_empty() {
+        return Err(DataError::file(file, "file is empty".to_string()));
+    }
+    match docs.swap_remove(0)["Base"] {
+        Yaml::Array(ref base) => Ok(base.clone()),
+        _ => Err(DataError::file(file, "expected a list of entries under Base".to_string()))
+    }
+}
+
+// Every .txt file in the folder holds one vault template, named after the
+// file.
+pub fn load_vaults(dir: &str) -> Result<Vec<Vault>, DataError> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir).map_err(|err| DataError::file(dir, err.to_string()))? {
+        let path = entry.map_err(|err| DataError::file(dir, err.to_string()))?.path();
+        if path.extension().map_or(false, |ext| ext == "txt") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut vaults = vec![];
+    for path in paths {
+        let file = path.to_string_lossy().to_string();
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let mut template = String::new();
+        File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut template))
+            .map_err(|err| DataError::file(&file, err.to_string()))?;
+        vaults.push(Vault::parse(&name, &template).map_err(|err| DataError::file(&file, err))?);
+    }
+    Ok(vaults)
+}
+
+pub fn entries<'a>(file: &'a str, base: &'a [Yaml]) -> Result<Vec<Entry<'a>>, DataError> {
+    let mut entries = vec![];
+    for (index, item) in base.iter().enumerate() {
+        let hash = item.as_hash()
+            .ok_or_else(|| DataError::file(file, format!("entry {} is not a map", index + 1)))?;
+        for (key, yaml) in hash.iter() {
+            let name = key.as_str()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("#{}", index + 1));
+            if yaml.as_hash().is_none() {
+                return Err(DataError{
+                    file: file.to_string(),
+                    entry: Some(name),
+                    field: None,
+                    message: "expected a map of fields".to_string()
+                });
+            }
+            entries.push(Entry{file, name, yaml});
+        }
+    }
+    Ok(entries)
+}
+
+// Colors are either a name or a list of red, green and blue values.
+pub fn get_color(value: &Yaml) -> Result<colors::Color, String> {
+    if let Some(rgb) = value.as_vec() {
+        if rgb.len() != 3 {
+            return Err("expected three color channels".to_string());
+        }
+        let mut channels = [0u8; 3];
+        for (channel, c) in channels.iter_mut().zip(rgb) {
+            match c.as_i64() {
+                Some(c) if c >= 0 && c <= 255 => *channel = c as u8,
+                _ => return Err("color channels must be numbers from 0 to 255".to_string())
+            }
+        }
+        return Ok(colors::Color{r: channels[0], g: channels[1], b: channels[2]});
+    }
+    let name = value.as_str().ok_or_else(|| "expected a color name or a list of red, green and blue".to_string())?;
+    color_by_name(name).ok_or_else(|| format!("unknown color \"{}\"", name))
+}
+
+pub fn color_by_name(name: &str) -> Option<colors::Color> {
+    let color = match name {
+        "black" => colors::BLACK,
+        "darkest grey" => colors::DARKEST_GREY,
+        "darker grey" => colors::DARKER_GREY,
+        "dark grey" => colors::DARK_GREY,
+        "grey" => colors::GREY,
+        "light grey" => colors::LIGHT_GREY,
+        "lighter grey" => colors::LIGHTER_GREY,
+        "lightest grey" => colors::LIGHTEST_GREY,
+        "white" => colors::WHITE,
+
+        "darkest sepia" => colors::DARKEST_SEPIA,
+        "darker sepia" => colors::DARKER_SEPIA,
+        "dark sepia" => colors::DARK_SEPIA,
+        "sepia" => colors::SEPIA,
+        "light sepia" => colors::LIGHT_SEPIA,
+        "lighter sepia" => colors::LIGHTER_SEPIA,
+        "lightest sepia" => colors::LIGHTEST_SEPIA,
+
+        "gold" => colors::GOLD,
+        "silver" => colors::SILVER,
+        "brass" => colors::BRASS,
+        "copper" => colors::COPPER,
+        "celadon" => colors::CELADON,
+        "peach" => colors::PEACH,
+
+        "desaturated red" => colors::DESATURATED_RED,
+        "lightest red" => colors::LIGHTEST_RED,
+        "lighter red" => colors::LIGHTER_RED,
+        "light red" => colors::LIGHT_RED,
+        "red" => colors::RED,
+        "dark red" => colors::DARK_RED,
+        "darker red" => colors::DARKER_RED,
+        "darkest red" => colors::DARKEST_RED,
+
+        "desaturated flame" => colors::DESATURATED_FLAME,
+        "lightest flame" => colors::LIGHTEST_FLAME,
+        "lighter flame" => colors::LIGHTER_FLAME,
+        "light flame" => colors::LIGHT_FLAME,
+        "flame" => colors::FLAME,
+        "dark flame" => colors::DARK_FLAME,
+        "darker flame" => colors::DARKER_FLAME,
+        "darkest flame" => colors::DARKEST_FLAME,
+
+        "desaturated orange" => colors::DESATURATED_ORANGE,
+        "lightest orange" => colors::LIGHTEST_ORANGE,
+        "lighter orange" => colors::LIGHTER_ORANGE,
+        "light orange" => colors::LIGHT_ORANGE,
+        "orange" => colors::ORANGE,
+        "dark orange" => colors::DARK_ORANGE,
+        "darker orange" => colors::DARKER_ORANGE,
+        "darkest orange" => colors::DARKEST_ORANGE,
+
+        "desaturated amber" => colors::DESATURATED_AMBER,
+        "lightest amber" => colors::LIGHTEST_AMBER,
+        "lighter amber" => colors::LIGHTER_AMBER,
+        "light amber" => colors::LIGHT_AMBER,
+        "amber" => colors::AMBER,
+        "dark amber" => colors::DARK_AMBER,
+        "darker amber" => colors::DARKER_AMBER,
+        "darkest amber" => colors::DARKEST_AMBER,
+
+        "desaturated yellow" => colors::DESATURATED_YELLOW,
+        "lightest yellow" => colors::LIGHTEST_YELLOW,
+        "lighter yellow" => colors::LIGHTER_YELLOW,
+        "light yellow" => colors::LIGHT_YELLOW,
+        "yellow" => colors::YELLOW,
+        "dark yellow" => colors::DARK_YELLOW,
+        "darker yellow" => colors::DARKER_YELLOW,
+        "darkest yellow" => colors::DARKEST_YELLOW,
+
+        "desaturated lime" => colors::DESATURATED_LIME,
+        "lightest lime" => colors::LIGHTEST_LIME,
+        "lighter lime" => colors::LIGHTER_LIME,
+        "light lime" => colors::LIGHT_LIME,
+        "lime" => colors::LIME,
+        "dark lime" => colors::DARK_LIME,
+        "darker lime" => colors::DARKER_LIME,
+        "darkest lime" => colors::DARKEST_LIME,
+
+        "desaturated chartreuse" => colors::DESATURATED_CHARTREUSE,
+        "lightest chartreuse" => colors::LIGHTEST_CHARTREUSE,
+        "lighter chartreuse" => colors::LIGHTER_CHARTREUSE,
+        "light chartreuse" => colors::LIGHT_CHARTREUSE,
+        "chartreuse" => colors::CHARTREUSE,
+        "dark chartreuse" => colors::DARK_CHARTREUSE,
+        "darker chartreuse" => colors::DARKER_CHARTREUSE,
+        "darkest chartreuse" => colors::DARKEST_CHARTREUSE,
+
+        "desaturated green" => colors::DESATURATED_GREEN,
+        "lightest green" => colors::LIGHTEST_GREEN,
+        "lighter green" => colors::LIGHTER_GREEN,
+        "light green" => colors::LIGHT_GREEN,
+        "green" => colors::GREEN,
+        "dark green" => colors::DARK_GREEN,
+        "darker green" => colors::DARKER_GREEN,
+        "darkest green" => colors::DARKEST_GREEN,
+
+        "desaturated sea" => colors::DESATURATED_SEA,
+        "lightest sea" => colors::LIGHTEST_SEA,
+        "lighter sea" => colors::LIGHTER_SEA,
+        "light sea" => colors::LIGHT_SEA,
+        "sea" => colors::SEA,
+        "dark sea" => colors::DARK_SEA,
+        "darker sea" => colors::DARKER_SEA,
+        "darkest sea" => colors::DARKEST_SEA,
+
+        "desaturated turquoise" => colors::DESATURATED_TURQUOISE,
+        "lightest turquoise" => colors::LIGHTEST_TURQUOISE,
+        "lighter turquoise" => colors::LIGHTER_TURQUOISE,
+        "light turquoise" => colors::LIGHT_TURQUOISE,
+        "turquoise" => colors::TURQUOISE,
+        "dark turquoise" => colors::DARK_TURQUOISE,
+        "darker turquoise" => colors::DARKER_TURQUOISE,
+        "darkest turquoise" => colors::DARKEST_TURQUOISE,
+
+        "desaturated cyan" => colors::DESATURATED_CYAN,
+        "lightest cyan" => colors::LIGHTEST_CYAN,
+        "lighter cyan" => colors::LIGHTER_CYAN,
+        "light cyan" => colors::LIGHT_CYAN,
+        "cyan" => colors::CYAN,
+        "dark cyan" => colors::DARK_CYAN,
+        "darker cyan" => colors::DARKER_CYAN,
+        "darkest cyan" => colors::DARKEST_CYAN,
+
+        "desaturated sky" => colors::DESATURATED_SKY,
+        "lightest sky" => colors::LIGHTEST_SKY,
+        "lighter sky" => colors::LIGHTER_SKY,
+        "light sky" => colors::LIGHT_SKY,
+        "sky" => colors::SKY,
+        "dark sky" => colors::DARK_SKY,
+        "darker sky" => colors::DARKER_SKY,
+        "darkest sky" => colors::DARKEST_SKY,
+
+        "desaturated azure" => colors::DESATURATED_AZURE,
+        "lightest azure" => colors::LIGHTEST_AZURE,
+        "lighter azure" => colors::LIGHTER_AZURE,
+        "light azure" => colors::LIGHT_AZURE,
+        "azure" => colors::AZURE,
+        "dark azure" => colors::DARK_AZURE,
+        "darker azure" => colors::DARKER_AZURE,
+        "darkest azure" => colors::DARKEST_AZURE,
+
+        "desaturated blue" => colors::DESATURATED_BLUE,
+        "lightest blue" => colors::LIGHTEST_BLUE,
+        "lighter blue" => colors::LIGHTER_BLUE,
+        "light blue" => colors::LIGHT_BLUE,
+        "blue" => colors::BLUE,
+        "dark blue" => colors::DARK_BLUE,
+        "darker blue" => colors::DARKER_BLUE,
+        "darkest blue" => colors::DARKEST_BLUE,
+
+        "desaturated han" => colors::DESATURATED_HAN,
+        "lightest han" => colors::LIGHTEST_HAN,
+        "lighter han" => colors::LIGHTER_HAN,
+        "light han" => colors::LIGHT_HAN,
+        "han" => colors::HAN,
+        "dark han" => colors::DARK_HAN,
+        "darker han" => colors::DARKER_HAN,
+        "darkest han" => colors::DARKEST_HAN,
+
+        "desaturated violet" => colors::DESATURATED_VIOLET,
+        "lightest violet" => colors::LIGHTEST_VIOLET,
+        "lighter violet" => colors::LIGHTER_VIOLET,
+        "light violet" => colors::LIGHT_VIOLET,
+        "violet" => colors::VIOLET,
+        "dark violet" => colors::DARK_VIOLET,
+        "darker violet" => colors::DARKER_VIOLET,
+        "darkest violet" => colors::DARKEST_VIOLET,
+
+        "desaturated purple" => colors::DESATURATED_PURPLE,
+        "lightest purple" => colors::LIGHTEST_PURPLE,
+        "lighter purple" => colors::LIGHTER_PURPLE,
+        "light purple" => colors::LIGHT_PURPLE,
+        "purple" => colors::PURPLE,
+        "dark purple" => colors::DARK_PURPLE,
+        "darker purple" => colors::DARKER_PURPLE,
+        "darkest purple" => colors::DARKEST_PURPLE,
+
+        "desaturated fuchsia" => colors::DESATURATED_FUCHSIA,
+        "lightest fuchsia" => colors::LIGHTEST_FUCHSIA,
+        "lighter fuchsia" => colors::LIGHTER_FUCHSIA,
+        "light fuchsia" => colors::LIGHT_FUCHSIA,
+        "fuchsia" => colors::FUCHSIA,
+        "dark fuchsia" => colors::DARK_FUCHSIA,
+        "darker fuchsia" => colors::DARKER_FUCHSIA,
+        "darkest fuchsia" => colors::DARKEST_FUCHSIA,
+
+        "desaturated magenta" => colors::DESATURATED_MAGENTA,
+        "lightest magenta" => colors::LIGHTEST_MAGENTA,
+        "lighter magenta" => colors::LIGHTER_MAGENTA,
+        "light magenta" => colors::LIGHT_MAGENTA,
+        "magenta" => colors::MAGENTA,
+        "dark magenta" => colors::DARK_MAGENTA,
+        "darker magenta" => colors::DARKER_MAGENTA,
+        "darkest magenta" => colors::DARKEST_MAGENTA,
+
+        "desaturated pink" => colors::DESATURATED_PINK,
+        "lightest pink" => colors::LIGHTEST_PINK,
+        "lighter pink" => colors::LIGHTER_PINK,
+        "light pink" => colors::LIGHT_PINK,
+        "pink" => colors::PINK,
+        "dark pink" => colors::DARK_PINK,
+        "darker pink" => colors::DARKER_PINK,
+        "darkest pink" => colors::DARKEST_PINK,
+
+        "desaturated crimson" => colors::DESATURATED_CRIMSON,
+        "lightest crimson" => colors::LIGHTEST_CRIMSON,
+        "lighter crimson" => colors::LIGHTER_CRIMSON,
+        "light crimson" => colors::LIGHT_CRIMSON,
+        "crimson" => colors::CRIMSON,
+        "dark crimson" => colors::DARK_CRIMSON,
+        "darker crimson" => colors::DARKER_CRIMSON,
+        "darkest crimson" => colors::DARKEST_CRIMSON,
+
+        _ => return None
+    };
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::*;
+    use crate::levels::Generator;
+    use crate::rng::GameRng;
+    use crate::testing;
+
+    #[test]
+    fn test_shipped_data_loads() {
+        let data = GameData::load_from(TEST_DATA).unwrap();
+        assert!(!data.creatures.is_empty());
+        assert!(!data.vaults.is_empty());
+        assert!(data.behaviour("basic").is_some());
+        assert_eq!(data.level(1).depth, 1);
+    }
+
+    #[test]
+    fn test_broken_references_name_the_file() {
+        let mut data = GameData::load_from(TEST_DATA).unwrap();
+        data.creatures[0].spells.push("no such spell".to_string());
+        let err = data.validate().unwrap_err();
+        assert!(err.file.ends_with(CREATURES_FILE));
+        assert_eq!(err.entry, Some(data.creatures[0].name.clone()));
+        assert_eq!(err.field, Some("spells".to_string()));
+
+        assert!(GameData::load_from("./no/such/folder").is_err());
+    }
+
+    #[test]
+    fn test_levels_cover_every_depth() {
+        let data = GameData::load_from(TEST_DATA).unwrap();
+        for depth in 1..20 {
+            let level = data.level(depth);
+            assert!(level.depth <= depth);
+            assert!(data.levels.iter().all(|other| other.depth <= level.depth || other.depth > depth));
+        }
+        let last = data.levels.last().unwrap();
+        assert!(data.is_final_level(last.depth));
+        assert!(!data.is_final_level(last.depth + 1));
+
+        let mut missing_first = GameData::load_from(TEST_DATA).unwrap();
+        missing_first.levels.remove(0);
+        let err = missing_first.validate().unwrap_err();
+        assert!(err.file.ends_with(LEVELS_FILE));
+        assert_eq!(err.entry, None);
+
+        let mut same_depth = GameData::load_from(TEST_DATA).unwrap();
+        same_depth.levels[1].depth = 1;
+        let err = same_depth.validate().unwrap_err();
+        assert_eq!(err.entry, Some(same_depth.levels[1].name.clone()));
+        assert_eq!(err.field, Some("depth".to_string()));
+
+        let mut last_too_early = GameData::load_from(TEST_DATA).unwrap();
+        last_too_early.levels[0].last = true;
+        let err = last_too_early.validate().unwrap_err();
+        assert_eq!(err.entry, Some(last_too_early.levels[0].name.clone()));
+        assert_eq!(err.field, Some("last".to_string()));
+    }
+
+    #[test]
+    fn test_no_chasms_on_the_last_level() {
+        let level = "Base:\n  - Deep:\n      depth: 1\n      generators:\n        caves: 1\n      terrain:\n        chasm: 1\n";
+        assert!(levels::load_levels(&testing::data_file("chasm", level)).is_ok());
+        let last = format!("{}      last: true\n", level);
+        let err = levels::load_levels(&testing::data_file("last-chasm", &last)).unwrap_err();
+        assert_eq!(err.entry, Some("Deep".to_string()));
+        assert_eq!(err.field, Some("terrain".to_string()));
+    }
+
+    #[test]
+    fn test_generators_are_picked_by_weight() {
+        let file = "Base:\n  - Mixed:\n      depth: 1\n      generators:\n        caves: 3\n        bsp: 1\n";
+        let levels = levels::load_levels(&testing::data_file("generators", file)).unwrap();
+        let mut rng = GameRng::new([1, 2, 3, 4]);
+        let caves = (0..4000)
+            .filter(|_| levels[0].pick_generator(&mut rng) == Generator::Caves)
+            .count();
+        assert!(caves > 2800 && caves < 3200, "caves picked {} times", caves);
+    }
+
+    #[test]
+    fn test_numbers_out_of_range_are_errors() {
+        let yaml = &YamlLoader::load_from_str("{ small: -2147483648, large: 2147483648 }").unwrap()[0];
+        let entry = Entry{file: "test.yaml", name: "Test".to_string(), yaml};
+        assert_eq!(entry.i32("small"), Ok(i32::min_value()));
+        let err = entry.i32("large").unwrap_err();
+        assert_eq!(err.field, Some("large".to_string()));
+        assert_eq!(err.message, "2147483648 is out of range");
+        assert!(entry.opt_i32("large", 0).is_err());
+    }
+}
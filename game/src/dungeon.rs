@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use spawning_pool::EntityId;
+
+use geo::*;
+use crate::components;
+use crate::game::GameState;
+use crate::map::{Map, empty_map};
+use crate::scheduler::Scheduler;
+
+// A level the player is not on. Its entities stay in the spawning pool, but
+// without their `Physics` they are neither drawn, scheduled nor looked up
+// by position until the level is entered again.
+#[derive(Serialize, Deserialize)]
+pub struct Level {
+    pub map: Map,
+    pub entities: Vec<(EntityId, components::Physics)>,
+    pub memory: Option<components::MapMemory>,
+    pub scheduler: Scheduler,
+    pub left_at: i32
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Dungeon {
+    levels: BTreeMap<u32, Level>
+}
+
+impl Dungeon {
+    pub fn new() -> Dungeon {
+        Dungeon {
+            levels: BTreeMap::new()
+        }
+    }
+
+    pub fn store(&mut self, depth: u32, level: Level) {
+        self.levels.insert(depth, level);
+    }
+
+    pub fn take(&mut self, depth: u32) -> Option<Level> {
+        self.levels.remove(&depth)
+    }
+}
+
+// Takes the current level out of the game state, leaving only the player.
+pub fn stash_level(state: &mut GameState) -> Level {
+    let player = state.player;
+    let entities: Vec<(EntityId, components::Physics)> = state.spawning_pool
+        .get_all::<components::Physics>()
+        .into_iter()
+        .filter(|(id, _)| *id != player)
+        .map(|(id, physics)| (id, physics.clone()))
+        .collect();
+    for (id, _) in &entities {
+        state.spawning_pool.remove::<components::Physics>(*id);
+    }
+
+    let dimensions = state.map.dimensions;
+    let time = state.scheduler.time;
+    let mut scheduler = mem::replace(&mut state.scheduler, Scheduler::new());
+    scheduler.current = None;
+
+    Level {
+        map: mem::replace(&mut state.map, empty_map(dimensions.x, dimensions.y)),
+        entities,
+        memory: state.spawning_pool.get::<components::MapMemory>(player).cloned(),
+        scheduler,
+        left_at: time
+    }
+}
+
+// Puts a stashed level back in place. Time stood still on the level while
+// the player was away, so everything that expires is moved forward by the
+// time spent elsewhere.
+pub fn restore_level(state: &mut GameState, level: Level, time: i32) {
+    let elapsed = time - level.left_at;
+    for (id, physics) in level.entities {
+        state.spawning_pool.set(id, physics);
+        if let Some(duration) = state.spawning_pool.get_mut::<components::Duration>(id) {
+            if duration.spawn_time != 0 {
+                duration.spawn_time += elapsed;
+                duration.expire_time += elapsed;
+            }
+        }
+        if let Some(stats) = state.spawning_pool.get_mut::<components::Stats>(id) {
+            for expire_time in stats.effects.values_mut() {
+                *expire_time += elapsed;
+            }
+        }
+    }
+    if let Some(memory) = level.memory {
+        state.spawning_pool.set(state.player, memory);
+    }
+    state.map = level.map;
+    state.scheduler = level.scheduler;
+    state.scheduler.shift_time(time);
+}
+
+pub fn find_stairs(direction: components::StairsDirection, state: &GameState) -> Option<Point> {
+    state.spawning_pool
+        .get_all::<components::Stairs>()
+        .into_iter()
+        .filter(|(_, stairs)| stairs.direction == direction)
+        .filter_map(|(id, _)| state.spawning_pool.get::<components::Physics>(id))
+        .map(|physics| physics.coord)
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use crate::dungeon::*;
+    use crate::testing;
+    use crate::game::{Game, GameState};
+
+    // What should come back unchanged when a level is entered again.
+    #[derive(Debug, PartialEq)]
+    struct Snapshot {
+        positions: Vec<(EntityId, Point)>,
+        memory: String,
+        map: String,
+        queue: Vec<(EntityId, i32)>
+    }
+
+    fn snapshot(state: &GameState) -> Snapshot {
+        let mut positions: Vec<(EntityId, Point)> = state.spawning_pool
+            .get_all::<components::Physics>()
+            .iter()
+            .filter(|&&(id, _)| id != state.player)
+            .map(|&(id, physics)| (id, physics.coord))
+            .collect();
+        positions.sort_by_key(|&(id, _)| id);
+        Snapshot {
+            positions,
+            memory: serde_json::to_string(&state.spawning_pool.get::<components::MapMemory>(state.player)).unwrap(),
+            map: serde_json::to_string(&state.map).unwrap(),
+            // turns are compared relative to the clock, which moves on
+            // while the player is away
+            queue: state.scheduler.queue().into_iter().map(|(id, time)| (id, time - state.scheduler.time)).collect()
+        }
+    }
+
+    // A game on level 1 with some of it explored and a monster under an
+    // effect.
+    fn new_game() -> (Game, EntityId) {
+        let mut game = testing::new_game([4, 3, 2, 1]);
+        game.update_fov();
+        let monster = testing::first_monster(&game.state);
+        let expire_time = game.state.scheduler.time + 300;
+        game.state.spawning_pool.get_mut::<components::Stats>(monster).unwrap().effects.insert(components::Effect::Slow, expire_time);
+        (game, monster)
+    }
+
+    fn effect_left(state: &GameState, monster: EntityId) -> i32 {
+        state.spawning_pool.get::<components::Stats>(monster).unwrap().effects[&components::Effect::Slow] - state.scheduler.time
+    }
+
+    #[test]
+    fn test_levels_are_restored_after_going_down_and_up() {
+        let (mut game, monster) = new_game();
+        let before = snapshot(&game.state);
+
+        game.state.change_level(2);
+        assert_eq!(game.state.level, 2);
+        assert!(game.state.spawning_pool.get::<components::Physics>(monster).is_none());
+        let time = game.state.scheduler.time;
+        game.state.scheduler.shift_time(time + 500);
+
+        game.state.change_level(1);
+        assert_eq!(game.state.scheduler.time, time + 500);
+        assert_eq!(snapshot(&game.state), before);
+        assert_eq!(effect_left(&game.state, monster), 300);
+        let player_position = game.state.spawning_pool.get::<components::Physics>(game.state.player).unwrap().coord;
+        assert_eq!(Some(player_position), find_stairs(components::StairsDirection::Down, &game.state));
+    }
+
+    #[test]
+    fn test_saved_games_carry_every_level() {
+        let (mut game, monster) = new_game();
+        let before = snapshot(&game.state);
+        game.state.change_level(2);
+        let time = game.state.scheduler.time + 500;
+        game.state.scheduler.shift_time(time);
+        let below = snapshot(&game.state);
+
+        let save = serde_json::to_string(&game.state).unwrap();
+        let mut state: GameState = serde_json::from_str(&save).unwrap();
+        state.data = game.state.data.clone();
+        assert_eq!(snapshot(&state), below);
+
+        state.change_level(1);
+        assert_eq!(state.scheduler.time, time);
+        assert_eq!(snapshot(&state), before);
+        assert_eq!(effect_left(&state, monster), 300);
+    }
+}
@@ -0,0 +1,39 @@
+use spawning_pool::EntityId;
+
+use geo::*;
+use crate::actions::{Action, ActionTarget, LevelUpChoice};
+use crate::components::{Effect, Awareness};
+use crate::spells;
+use crate::game::GameState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GameEvent {
+    EntityDamaged{attacker: Option<EntityId>, target: EntityId, damage: i32},
+    EntityHealed{healer: Option<EntityId>, target: EntityId, amount: i32},
+    EntityDied{entity: EntityId, name: String},
+    EntityFell{entity: EntityId, name: String},
+    EntityStunned{entity: EntityId},
+    EffectApplied{actor: Option<EntityId>, target: EntityId, effect: Effect},
+    EffectExpired{entity: EntityId, effect: Effect},
+    ItemPickedUp{entity: EntityId, item: EntityId},
+    ItemUsed{entity: EntityId, item: EntityId},
+    ItemEquipped{entity: EntityId, item: EntityId},
+    ItemUnequipped{entity: EntityId, item: EntityId},
+    ItemDropped{entity: EntityId, item: EntityId},
+    SpellCast{caster: Option<EntityId>, target: Option<ActionTarget>, spell: spells::Spell},
+    LightningStruck{target: EntityId},
+    RuneWritten{entity: EntityId, spell: String, position: Point},
+    RuneBlocked{entity: EntityId, position: Point},
+    FogSpawned{position: Point},
+    LightSpawned{position: Point},
+    DoorOpened{entity: Option<EntityId>, door: EntityId},
+    PointGained{entity: EntityId},
+    LevelledUp{entity: EntityId, choice: LevelUpChoice},
+    LevelEntered{level: u32},
+    AwarenessChanged{entity: EntityId, awareness: Awareness},
+    ActionRejected{rule: String, action: Action}
+}
+
+pub trait EventListener {
+    fn on_event(&mut self, event: &GameEvent, state: &GameState);
+}
@@ -1,37 +1,57 @@
-use tcod::colors;
-use tcod;
+use std::rc::Rc;
+use geo::colors;
 
 use spawning_pool::EntityId;
 
-use crate::render;
+use crate::events::{GameEvent, EventListener};
+use crate::statistics::Statistics;
+use crate::data::GameData;
 use crate::components;
 use crate::systems;
 
 use geo::*;
 use crate::map::*;
+use crate::dungeon::{self, Dungeon};
 use crate::spatial::*;
+use crate::lighting::{self, LightMap};
+use crate::noise;
+use crate::navigation::Navigation;
 use crate::ai;
 use crate::actions::*;
 use crate::rules::*;
-use crate::utils;
 use crate::consts::*;
 use crate::messages::*;
 use crate::spells;
 use crate::scheduler::{Scheduler};
+use crate::rng::{GameRng, Seed};
+use crate::journal::Journal;
 
 #[derive(Serialize, Deserialize)]
 pub struct GameState {
     pub spawning_pool: components::SpawningPool,
     pub player: EntityId,
     pub map: Map,
+    pub dungeon: Dungeon,
     pub scheduler: Scheduler,
     pub spatial_table: SpatialTable,
     pub messages: Messages,
-    pub level: u32
+    pub level: u32,
+    pub seed: Seed,
+    pub rng: GameRng,
+    pub journal: Journal,
+    pub statistics: Statistics,
+    #[serde(skip)]
+    pub light: LightMap,
+    #[serde(skip)]
+    pub navigation: Navigation,
+    #[serde(skip)]
+    pub events: Vec<GameEvent>,
+    #[serde(skip)]
+    pub data: Rc<GameData>
 }
 
 impl GameState {
-    pub fn new() -> GameState {
+    pub fn new(seed: Seed, data: Rc<GameData>) -> GameState {
         println!("new game state");
         let mut spawning_pool = components::SpawningPool::new();
         let player = create_player(&mut spawning_pool, MAP_WIDTH, MAP_HEIGHT);
@@ -41,45 +61,71 @@ impl GameState {
 
         let mut spatial_table = SpatialTable::new(map.dimensions.x, map.dimensions.y);
         spatial_table.reset(&spawning_pool);
-        spatial_table.dirty = true;
 
         GameState {
             spawning_pool,
             spatial_table,
             player,
             map,
+            dungeon: Dungeon::new(),
             scheduler,
             messages: vec![],
-            level: 0
+            level: 0,
+            seed,
+            rng: GameRng::new(seed),
+            journal: Journal::new(seed),
+            statistics: Default::default(),
+            light: Default::default(),
+            navigation: Default::default(),
+            events: vec![],
+            data
         }
     }
 
-    pub fn new_level(&mut self) {
-        for cell in &self.spatial_table.cells {
-            for entity in &cell.entities {
-                if entity != &self.player {
-                    self.spawning_pool.remove_entity(*entity);
-                }
-            }
-        }
-        self.level += 1;
-
-        let map = create_map(self.level, self.player, MAP_WIDTH, MAP_HEIGHT, &mut self.spawning_pool, &mut self.scheduler, None);
-        self.spatial_table.reset(&self.spawning_pool);
-        self.map = map;
-
-        if let Some(memory) = self.spawning_pool.get_mut::<components::MapMemory>(self.player) {
-            memory.reset();
-            // memory.explore_all();
+    // Leaves the current level for `level`, generating it on the first visit
+    // and restoring it as it was left otherwise.
+    pub fn change_level(&mut self, level: u32) {
+        let time = self.scheduler.time;
+        let descending = level > self.level;
+        if self.level > 0 {
+            let previous = dungeon::stash_level(self);
+            self.dungeon.store(self.level, previous);
         }
+        self.level = level;
 
-        if self.level > 1 {
-            if let Some(stats) = self.spawning_pool.get_mut::<components::Stats>(self.player) {
-                stats.max_health += 5;
-                stats.health = stats.max_health;
-                self.messages.log(MessageLevel::Important, "The player's wounds heal and his body grows stronger");
+        match self.dungeon.take(level) {
+            Some(stored) => {
+                dungeon::restore_level(self, stored, time);
+                let arrival = if descending {
+                    components::StairsDirection::Up
+                } else {
+                    components::StairsDirection::Down
+                };
+                if let Some(pos) = dungeon::find_stairs(arrival, self) {
+                    self.spawning_pool.set(self.player, components::Physics{coord: pos});
+                }
+            },
+            None => {
+                self.scheduler.time = time;
+                self.map = create_map(self.level, self.player, MAP_WIDTH, MAP_HEIGHT, &self.data, &mut self.spawning_pool, &mut self.scheduler, &mut self.rng);
+                if let Some(memory) = self.spawning_pool.get_mut::<components::MapMemory>(self.player) {
+                    memory.reset();
+                    // memory.explore_all();
+                }
+                if self.level > 1 {
+                    if let Some(stats) = self.spawning_pool.get_mut::<components::Stats>(self.player) {
+                        stats.max_health += 5;
+                        stats.health = stats.max_health;
+                    }
+                }
             }
         }
+        // the player is in the middle of their turn, which is finished on
+        // the new level
+        self.scheduler.current = Some(self.player);
+        self.spatial_table.reset(&self.spawning_pool);
+        self.light = LightMap::new(self);
+        self.events.push(GameEvent::LevelEntered{level: self.level});
     }
 }
 
@@ -89,9 +135,11 @@ pub struct Game {
     pub action_queue: Vec<Action>,
     pub reaction_queue: Vec<Action>,
     pub rejection_queue: Vec<Action>,
-    pub fov: tcod::map::Map,
     pub tick_time: i32,
-    pub systems: systems::DurationSystem
+    pub systems: systems::DurationSystem,
+    pub rules: RuleSet,
+    pub listeners: Vec<Box<EventListener>>,
+    events: Vec<GameEvent>
 }
 
 pub enum WaitResult {
@@ -115,9 +163,34 @@ enum ActionTickResult {
 }
 
 impl Game {
-    pub fn game_tick(&mut self, actions: Vec<Action>, animations: &mut Vec<render::Animation>) -> TickResult {
+    pub fn new(seed: Seed, data: Rc<GameData>) -> Game {
+        let mut state = GameState::new(seed, data);
+        state.change_level(1);
+
+        Game {
+            state,
+            tick_time: 0,
+            current_action: None,
+            action_queue: vec![],
+            reaction_queue: vec![],
+            rejection_queue: vec![],
+            systems: systems::DurationSystem::new(),
+            rules: standard_rules(),
+            listeners: vec![],
+            events: vec![]
+        }
+    }
+
+    pub fn game_tick(&mut self, actions: Vec<Action>, renderer: &mut EventListener) -> TickResult {
         self.state.scheduler.tick(&self.state.spawning_pool);
+        if self.state.navigation.is_stale(&self.state) {
+            self.state.navigation = Navigation::new(&self.state);
+        }
+        if self.state.light.is_stale(&self.state) {
+            self.state.light = LightMap::new(&self.state);
+        }
         self.systems.run(&mut self.state);
+        self.dispatch_events(renderer);
         if self.state.spawning_pool.get::<components::MapMemory>(self.state.scheduler.get_current()).is_some() {
             self.update_fov();
         }
@@ -135,7 +208,7 @@ impl Game {
             if self.current_action.is_none() {
                 self.current_action = Some(self.action_queue.remove(0));
             }
-            let res = self.action_tick(animations);
+            let res = self.action_tick(renderer);
             require_information = res == ActionTickResult::RequireInformation;
             if let ActionTickResult::Performed{time} = res {
                 performed_action = true;
@@ -163,6 +236,7 @@ impl Game {
             }
         } else if performed_action {
             let entity = self.state.scheduler.get_current();
+            self.end_turn(entity, renderer);
             let tick_time = update_tick_time(self.tick_time, &self.state);
             self.state.scheduler.schedule_entity(entity, tick_time, &self.state.spawning_pool);
             self.tick_time = 0;
@@ -170,6 +244,10 @@ impl Game {
             self.rejection_queue.clear();
             self.reaction_queue.clear();
             self.state.spatial_table.reset(&self.state.spawning_pool);
+            self.state.light = LightMap::new(&self.state);
+            if entity == self.state.player {
+                self.state.navigation = Navigation::new(&self.state);
+            }
             TickResult::Passed
         } else {
             let entity = self.state.scheduler.get_current();
@@ -182,6 +260,7 @@ impl Game {
                 self.rejection_queue.clear();
                 self.reaction_queue.clear();
                 self.state.spatial_table.reset(&self.state.spawning_pool);
+                self.state.light = LightMap::new(&self.state);
                 TickResult::Passed
             } else {
                 TickResult::Wait(WaitResult::Wait)
@@ -189,6 +268,28 @@ impl Game {
         }
     }
 
+    // Applies the terrain the entity stands on once its turn is over, before
+    // it is scheduled again.
+    fn end_turn(&mut self, entity: EntityId, renderer: &mut EventListener) {
+        self.action_queue = systems::terrain(entity, &mut self.state);
+        while !self.action_queue.is_empty() {
+            self.current_action = Some(self.action_queue.remove(0));
+            if let ActionTickResult::Performed{time} = self.action_tick(renderer) {
+                self.tick_time += time;
+            }
+        }
+    }
+
+    // events that have been dispatched since the last call, for the frontend
+    // to react to after a tick
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        self.events.drain(..).collect()
+    }
+
+    fn dispatch_events(&mut self, renderer: &mut EventListener) {
+        dispatch_events(&mut self.state, &mut self.listeners, &mut self.events, renderer);
+    }
+
     fn get_actions(&mut self, actions: Vec<Action>) -> Option<Vec<Action>>{
         // let pre_action = systems::confusion(self.state.scheduler.get_current(), &mut self.state);
         let pre_actions = systems::run(self.state.scheduler.get_current(), &mut self.state);
@@ -198,17 +299,21 @@ impl Game {
         }
     }
 
-    fn action_tick(&mut self, animations: &mut Vec<render::Animation>) -> ActionTickResult {
+    fn action_tick(&mut self, renderer: &mut EventListener) -> ActionTickResult {
         let mut require_information = false;
         let mut performed_action = false;
         let mut used_time = 0;
         if let Some(ref mut action) = self.current_action {
             require_information = check_require_information(action, &self.state);
             if !require_information {
-                let action_status = apply_rules(action, &self.state, &mut self.rejection_queue, &mut self.reaction_queue);
+                let action_status = self.rules.apply(action, &self.state, &mut self.rejection_queue, &mut self.reaction_queue);
                 match action_status {
                     ActionStatus::Accept => {
                         let action_result = perform_action(action, &mut self.state);
+                        if let ActionResult::Performed{..} = action_result {
+                            noise::make_noise(action, &mut self.state);
+                        }
+                        dispatch_events(&mut self.state, &mut self.listeners, &mut self.events, renderer);
                         if action_result == ActionResult::Failed {
                             self.reaction_queue.clear();
                             performed_action = false;
@@ -229,7 +334,6 @@ impl Game {
                             };
                         }
                         if performed_action {
-                            animate_action(action, animations, &self.state.spawning_pool);
                             if let Some(reaction) = self.reaction_queue.pop() {
                                 self.action_queue.insert(0, reaction);
                             }
@@ -238,6 +342,9 @@ impl Game {
                         }
                     }
                     ActionStatus::Reject => {
+                        if let Some(rejection) = self.rules.last_rejection() {
+                            self.state.events.push(GameEvent::ActionRejected{rule: rejection.rule.clone(), action: rejection.action.clone()});
+                        }
                         self.reaction_queue.clear();
                         self.action_queue = self.rejection_queue.drain(..).collect();
                         self.action_queue.reverse();
@@ -257,24 +364,21 @@ impl Game {
     fn get_entity_actions(&mut self, actions: Vec<Action>) -> Option<Vec<Action>> {
         use components::*;
         let ai = match self.state.spawning_pool.get::<Controller>(self.state.scheduler.get_current()) {
-            Some(controller) => Some(controller.ai),
+            Some(controller) => Some(controller.ai.clone()),
             None => None
         };
         if let Some(ai) = ai {
             return match ai {
                 AI::Player => {
                     if !actions.is_empty() {
+                        self.state.journal.record(self.state.scheduler.time, &actions);
                         Some(actions)
                     } else {
                         None
                     }
                 },
-                _ => {
-                    let acts = match ai {
-                        AI::Basic => ai::perform_basic_ai(self.state.scheduler.get_current(), &mut self.state),
-                        AI::SpellCaster => ai::perform_spell_ai(self.state.scheduler.get_current(), &mut self.state),
-                        _ => None
-                    };
+                AI::Behaviour(ref behaviour) => {
+                    let acts = ai::perform_ai(self.state.scheduler.get_current(), behaviour, &mut self.state);
                     acts.or_else(|| Some(vec![Action::new(
                         Some(self.state.scheduler.get_current()),
                         None,
@@ -293,31 +397,34 @@ impl Game {
             Some(physics) => physics.coord,
             None => return
         };
-        if self.state.spawning_pool.get::<components::MapMemory>(entity).is_some() {
-            self.calculate_fov(coord.x, coord.y, 20);
-        }
+        let radius = self.state.spawning_pool.get::<components::Sight>(entity).map_or(20, |sight| sight.radius);
+        let map = &self.state.map;
+        let spatial_table = &self.state.spatial_table;
+        let light = &self.state.light;
         if let Some(map_memory) = self.state.spawning_pool.get_mut::<components::MapMemory>(entity) {
             map_memory.clear_visible();
-            for x in 0..map_memory.dimensions.x {
-                for y in 0..map_memory.dimensions.y {
-                    if self.fov.is_in_fov(x, y) {
-                        map_memory.explore(x, y);
-                        map_memory.set_visible(x, y, true);
-                    }
+            let is_transparent = |pos| lighting::is_transparent(map, spatial_table, pos);
+            // Dark tiles in line of sight stay unseen, but everyone knows
+            // the tile they stand on.
+            field_of_view(coord, radius, is_transparent, |pos| {
+                if map.in_bounds(pos.x, pos.y) && (pos == coord || light.get(pos).is_lit()) {
+                    map_memory.explore(pos.x, pos.y);
+                    map_memory.set_visible(pos.x, pos.y, true);
                 }
-            }
+            });
         }
     }
-
-    fn calculate_fov(&mut self, x: i32, y: i32, sight_radius: i32) {
-        self.fov.compute_fov(x, y, sight_radius, true, tcod::map::FovAlgorithm::Basic);
-    }
 }
 
 fn update_tick_time(time: i32, state: &GameState) -> i32 {
     use components::*;
 
-    if let Some(stats) = state.spawning_pool.get::<Stats>(state.scheduler.get_current()) {
+    let entity = state.scheduler.get_current();
+    let time = match state.spawning_pool.get::<Speed>(entity) {
+        Some(speed) => ((time as f32 / speed.multiplier) as i32).max(1),
+        None => time
+    };
+    if let Some(stats) = state.spawning_pool.get::<Stats>(entity) {
         match stats.effects.get(&Effect::Slow) {
             Some(_) => time * 2,
             None => time
@@ -371,36 +478,19 @@ fn get_action_caster_target(spell: &spells::Spell, actor: EntityId, state: &Game
     }
 }
 
-fn animate_action(action: &Action, animations: &mut Vec<render::Animation>, spawning_pool: &components::SpawningPool) {
-    match action.command {
-        Command::LightningStrike{..} => {
-            if let Some(ActionTarget::Entity(target)) = action.target {
-                if let Some(pos) = utils::get_position(target, spawning_pool) {
-                    if let Some(glyph) = utils::get_glyph(target, spawning_pool) {
-                        animations.push(render::Animation::new(
-                            render::AnimationAnchor::Position{point: pos},
-                            200.0, // time
-                            Some(2000.0), // duration
-                            vec![None, Some((glyph, tcod::colors::LIGHT_SKY))]
-                        ));
-                    }
-                }
-            }
-        },
-        Command::TakeDamage{..} => {
-            if let Some(ActionTarget::Entity(target)) = action.target {
-                if let Some(glyph) = utils::get_glyph(target, spawning_pool) {
-                    animations.push(render::Animation::new(
-                        render::AnimationAnchor::Entity{entity: target},
-                        0.0, // time
-                        Some(300.0), // duration
-                        vec![Some((glyph, tcod::colors::RED))]
-                    ));
-                }
-            }
-        },
-        _ => {}
+fn dispatch_events(state: &mut GameState, listeners: &mut Vec<Box<EventListener>>, dispatched: &mut Vec<GameEvent>, renderer: &mut EventListener) {
+    let events: Vec<GameEvent> = state.events.drain(..).collect();
+    for event in &events {
+        if let Some((level, message)) = event_message(event, state) {
+            state.messages.log(level, message);
+        }
+        state.statistics.record(event, state.player);
+        renderer.on_event(event, state);
+        for listener in listeners.iter_mut() {
+            listener.on_event(event, state);
+        }
     }
+    dispatched.extend(events);
 }
 
 pub fn get_item_at(position: Point, game_state: &GameState) -> Option<EntityId> {
@@ -434,7 +524,148 @@ fn create_player(spawning_pool: &mut components::SpawningPool, width: i32, heigh
         3
     ));
     spawning_pool.set(player, components::SpellBook{
-        spells: vec![spells::Spells::Stun]
+        spells: vec!["stun".to_string(), "light".to_string()]
     });
+    // The torch
+    spawning_pool.set(player, components::Light{radius: 6, color: colors::LIGHT_AMBER});
     player
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use crate::game::*;
+    use crate::testing;
+    use crate::renderer::HeadlessRenderer;
+
+    // Ticks until the player is up, then has them do `command`.
+    fn play_turn(game: &mut Game, command: Command) {
+        let action = Action::new(Some(game.state.player), None, command);
+        play_actions(game, vec![action]);
+    }
+
+    fn play_actions(game: &mut Game, actions: Vec<Action>) {
+        wait_for_player(game);
+        if let TickResult::Passed = game.game_tick(actions, &mut HeadlessRenderer) {
+            game.state.spawning_pool.cleanup_removed();
+        }
+    }
+
+    fn wait_for_player(game: &mut Game) {
+        let mut renderer = HeadlessRenderer;
+        for _ in 0..1000 {
+            if game.state.scheduler.current == Some(game.state.player) {
+                break;
+            }
+            if let TickResult::Passed = game.game_tick(vec![], &mut renderer) {
+                game.state.spawning_pool.cleanup_removed();
+            }
+        }
+        assert_eq!(game.state.scheduler.current, Some(game.state.player));
+    }
+
+    fn play(seed: Seed, turns: usize) -> Game {
+        let mut game = testing::new_game(seed);
+        let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+        for turn in 0..turns {
+            let (x, y) = directions[turn / 5 % directions.len()];
+            play_turn(&mut game, Command::WalkDirection{dir: Point::new(x, y)});
+        }
+        game
+    }
+
+    struct Recorder(Rc<RefCell<Vec<GameEvent>>>);
+
+    impl EventListener for Recorder {
+        fn on_event(&mut self, event: &GameEvent, _state: &GameState) {
+            self.0.borrow_mut().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_events_reach_the_log_and_the_listeners() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let heard = Rc::new(RefCell::new(vec![]));
+        let rendered = Rc::new(RefCell::new(vec![]));
+        game.listeners.push(Box::new(Recorder(heard.clone())));
+        let player = game.state.player;
+        game.state.events.push(GameEvent::EntityDamaged{attacker: None, target: player, damage: 3});
+        game.game_tick(vec![], &mut Recorder(rendered.clone()));
+
+        let is_damage = |event: &GameEvent| match *event {
+            GameEvent::EntityDamaged{target, damage: 3, ..} => target == player,
+            _ => false
+        };
+        assert!(heard.borrow().iter().any(is_damage));
+        assert!(rendered.borrow().iter().any(is_damage));
+        assert!(game.take_events().iter().any(is_damage));
+        assert!(game.state.messages.iter().any(|(message, _)| message.ends_with("takes 3 damage")));
+        assert_eq!(game.state.statistics.damage_taken, 3);
+    }
+
+    fn positions(game: &Game) -> Vec<(EntityId, Point)> {
+        let mut positions: Vec<(EntityId, Point)> = game.state.spawning_pool
+            .get_all::<components::Physics>()
+            .iter()
+            .map(|&(id, physics)| (id, physics.coord))
+            .collect();
+        positions.sort_by_key(|&(id, _)| id);
+        positions
+    }
+
+    #[test]
+    fn test_headless_games_are_deterministic() {
+        let seed = [1, 2, 3, 4];
+        let game = play(seed, 40);
+        assert!(game.state.scheduler.time > 0);
+        assert!(get_entity_position(game.state.player, &game.state).is_some());
+
+        let again = play(seed, 40);
+        assert_eq!(again.state.scheduler.time, game.state.scheduler.time);
+        assert_eq!(positions(&again), positions(&game));
+    }
+
+    // Feeds a saved journal to a new game the way the replay screen does.
+    #[test]
+    fn test_replayed_journals_end_where_the_game_did() {
+        let game = play([5, 6, 7, 8], 40);
+        let saved = serde_json::to_string(&game.state.journal).unwrap();
+        let journal: Journal = serde_json::from_str(&saved).unwrap();
+        assert_eq!(journal.len(), 40);
+
+        let mut replay = testing::new_game(journal.seed);
+        for entry in journal.entries {
+            wait_for_player(&mut replay);
+            assert_eq!(replay.state.scheduler.time, entry.time);
+            play_actions(&mut replay, entry.actions);
+        }
+        assert_eq!(replay.state.scheduler.time, game.state.scheduler.time);
+        assert_eq!(positions(&replay), positions(&game));
+        assert_eq!(serde_json::to_string(&replay.state.journal).unwrap(), saved);
+        let stats = |game: &Game| serde_json::to_string(&game.state.spawning_pool.get::<components::Stats>(game.state.player)).unwrap();
+        assert_eq!(stats(&replay), stats(&game));
+    }
+
+    #[test]
+    fn test_dark_tiles_in_sight_stay_unseen() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let pos = Point::new(10, 10);
+        testing::empty_level(&mut game.state, 0.0);
+        game.state.spawning_pool.set(player, components::Physics{coord: pos});
+        testing::add_light(&mut game.state, pos + Point::new(6, 0), 2);
+        game.state.spatial_table.reset(&game.state.spawning_pool);
+        game.state.light = LightMap::new(&game.state);
+        game.state.scheduler.current = Some(player);
+        game.update_fov();
+
+        let memory = game.state.spawning_pool.get::<components::MapMemory>(player).unwrap();
+        let visible = |x| memory.is_visible(pos.x + x, pos.y);
+        assert!(visible(0));
+        assert!(!visible(1));
+        assert!(!visible(3));
+        assert!(visible(5));
+        assert!(visible(6));
+        assert!(!memory.is_explored(pos.x + 1, pos.y));
+    }
+}
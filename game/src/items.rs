@@ -0,0 +1,189 @@
+use geo::colors;
+use std::convert::TryFrom;
+use yaml_rust::Yaml;
+
+use spawning_pool::{EntityId};
+use geo::*;
+use crate::components;
+use crate::data::{DataError, Entry, load_file, entries};
+
+#[derive(Debug)]
+pub struct ItemData {
+    pub name: String,
+    pub glyph: char,
+    pub color: colors::Color,
+    pub kind: components::ItemKind,
+    pub equip: Option<components::EquipmentSlot>,
+    pub statistics_bonus: Option<components::StatisticsBonus>,
+    pub on_use: Option<components::OnUseCallback>,
+    pub weights: Vec<(u32, u32)>
+}
+
+impl ItemData {
+    pub fn weight(&self, level: u32) -> u32 {
+        self.weights
+            .iter()
+            .filter(|(from, _)| *from <= level)
+            .last()
+            .map(|(_, weight)| *weight)
+            .unwrap_or(0)
+    }
+}
+
+const KIND_NAMES: [(&str, components::ItemKind); 3] = [
+    ("scroll", components::ItemKind::Scroll),
+    ("potion", components::ItemKind::Potion),
+    ("equipment", components::ItemKind::Equipment)
+];
+
+const SLOT_NAMES: [(&str, components::EquipmentSlot); 3] = [
+    ("left hand", components::EquipmentSlot::LeftHand),
+    ("right hand", components::EquipmentSlot::RightHand),
+    ("head", components::EquipmentSlot::Head)
+];
+
+pub fn load_items(file: &str) -> Result<Vec<ItemData>, DataError> {
+    let base = load_file(file)?;
+    let mut items = vec![];
+    for entry in entries(file, &base)? {
+        let equip = if entry.has("slot") {
+            Some(entry.one_of("slot", &SLOT_NAMES)?)
+        } else {
+            None
+        };
+        let statistics_bonus = match entry.yaml["bonus"] {
+            Yaml::BadValue => None,
+            Yaml::Hash(_) => {
+                let bonus = Entry{file, name: entry.name.clone(), yaml: &entry.yaml["bonus"]};
+                Some(components::StatisticsBonus{
+                    strength: bonus.opt_i32("strength", 0)?,
+                    defense: bonus.opt_i32("defense", 0)?
+                })
+            },
+            _ => return Err(entry.error("bonus", "expected a map of strength and defense".to_string()))
+        };
+        let on_use = entry.opt_str("spell")?.map(|spell| components::OnUseCallback::Spell(spell.to_string()));
+        items.push(ItemData{
+            name: entry.str("name")?.to_string(),
+            glyph: entry.glyph("glyph")?,
+            color: entry.color("color")?,
+            kind: entry.one_of("kind", &KIND_NAMES)?,
+            equip,
+            statistics_bonus,
+            on_use,
+            weights: get_weights(&entry)?
+        });
+    }
+    Ok(items)
+}
+
+// Weights map the first level an item appears on to its weight from then on.
+fn get_weights(entry: &Entry) -> Result<Vec<(u32, u32)>, DataError> {
+    let mut weights = vec![];
+    let number = |value: &Yaml| value.as_i64().and_then(|value| u32::try_from(value).ok());
+    match entry.yaml["weight"] {
+        Yaml::BadValue => {},
+        Yaml::Hash(ref hash) => {
+            for (level, weight) in hash.iter() {
+                match (number(level), number(weight)) {
+                    (Some(level), Some(weight)) => weights.push((level, weight)),
+                    _ => return Err(entry.error("weight", "expected positive numbers for levels and weights".to_string()))
+                }
+            }
+        },
+        _ => return Err(entry.error("weight", "expected a map of level to weight".to_string()))
+    }
+    weights.sort_by_key(|(level, _)| *level);
+    Ok(weights)
+}
+
+pub fn create_item(data: &ItemData, pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
+    let item = spawning_pool.spawn_entity();
+    spawning_pool.set(item, components::Visual{
+        always_display: false,
+        glyph: data.glyph,
+        color: data.color
+    });
+    spawning_pool.set(item, components::Physics{
+        coord: pos
+    });
+    spawning_pool.set(item, components::Flags{
+        block_sight: false,
+        solid: false
+    });
+    spawning_pool.set(item, components::Information{
+        faction: components::Faction::Neutral,
+        name: data.name.to_string()
+    });
+    spawning_pool.set(item, components::Item{
+        on_use: data.on_use.clone(),
+        equip: data.equip,
+        kind: data.kind.clone(),
+        statistics_bonus: data.statistics_bonus.clone()
+    });
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::items::*;
+    use crate::testing;
+    use crate::spells::SpellEffect;
+    use crate::data::{GameData, TEST_DATA};
+
+    #[test]
+    fn test_loot_weights_hold_until_the_next_level() {
+        let file = testing::data_file("items", r#"
+Base:
+  - Scroll:
+      name: "scroll"
+      glyph: "?"
+      color: [1, 2, 3]
+      kind: "scroll"
+      weight:
+        4: 2
+        2: 1
+        7: 0
+  - Potion:
+      name: "potion"
+      glyph: "!"
+      color: "pink"
+      kind: "potion"
+"#);
+        let items = load_items(&file).unwrap();
+        let weights: Vec<u32> = (1..9).map(|level| items[0].weight(level)).collect();
+        assert_eq!(weights, vec![0, 1, 1, 2, 2, 2, 0, 0]);
+        assert_eq!(items[1].weight(1), 0);
+        assert_eq!(items[1].weight(10), 0);
+
+        let file = testing::data_file("bad_weights", r#"
+Base:
+  - Scroll:
+      name: "scroll"
+      glyph: "?"
+      color: [1, 2, 3]
+      kind: "scroll"
+      weight:
+        1: -1
+"#);
+        let err = load_items(&file).unwrap_err();
+        assert_eq!(err.entry, Some("Scroll".to_string()));
+        assert_eq!(err.field, Some("weight".to_string()));
+    }
+
+    #[test]
+    fn test_shipped_light_scroll() {
+        let data = GameData::load_from(TEST_DATA).unwrap();
+        let scroll = data.item("scroll of light").unwrap();
+        assert_eq!(scroll.kind, components::ItemKind::Scroll);
+        let spell = match scroll.on_use {
+            Some(components::OnUseCallback::Spell(ref spell)) => data.spell(spell).unwrap(),
+            _ => panic!("the scroll of light casts no spell")
+        };
+        assert!(spell.effects.iter().any(|effect| match *effect {
+            SpellEffect::Light(radius) => radius > 0,
+            _ => false
+        }));
+        assert_eq!((scroll.weight(1), scroll.weight(2), scroll.weight(3)), (1, 1, 3));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::actions::Action;
+use crate::rng::Seed;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub time: i32,
+    pub actions: Vec<Action>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Journal {
+    pub seed: Seed,
+    pub entries: Vec<JournalEntry>
+}
+
+impl Journal {
+    pub fn new(seed: Seed) -> Journal {
+        Journal {
+            seed,
+            entries: vec![]
+        }
+    }
+
+    pub fn record(&mut self, time: i32, actions: &[Action]) {
+        self.entries.push(JournalEntry {
+            time,
+            actions: actions.to_vec()
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
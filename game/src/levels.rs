@@ -0,0 +1,212 @@
+use std::convert::TryFrom;
+use rand::Rng;
+use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
+use yaml_rust::Yaml;
+
+use map_generator::{Map as GeneratedMap, Tile, bsp, tower, roomsy, corridor, caves};
+use crate::data::{DataError, Entry, load_file, entries};
+use crate::map::Palette;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Generator {
+    Corridor,
+    Caves,
+    Roomsy,
+    Bsp,
+    Tower
+}
+
+impl Generator {
+    pub fn generate<T: Rng>(self, width: i32, height: i32, min_size: i32, rng: &mut T) -> GeneratedMap {
+        match self {
+            Generator::Corridor => corridor::generate(width, height, min_size, rng),
+            Generator::Caves => caves::generate(width, height, min_size, rng),
+            Generator::Roomsy => roomsy::generate(width, height, min_size, rng),
+            Generator::Bsp => bsp::generate(width, height, min_size, rng),
+            Generator::Tower => tower::generate(width, height, min_size, rng)
+        }
+    }
+}
+
+const GENERATOR_NAMES: [(&str, Generator); 5] = [
+    ("corridor", Generator::Corridor),
+    ("caves", Generator::Caves),
+    ("roomsy", Generator::Roomsy),
+    ("bsp", Generator::Bsp),
+    ("tower", Generator::Tower)
+];
+
+const TERRAIN_NAMES: [(&str, Tile); 5] = [
+    ("water", Tile::Water),
+    ("deep water", Tile::DeepWater),
+    ("lava", Tile::Lava),
+    ("chasm", Tile::Chasm),
+    ("rubble", Tile::Rubble)
+];
+
+// How the levels from `depth` down to the next configured depth are built.
+#[derive(Debug)]
+pub struct LevelData {
+    pub name: String,
+    pub depth: u32,
+    pub generators: Vec<(Generator, u32)>,
+    pub min_size: i32,
+    pub monsters: Vec<String>,
+    pub monster_chance: f32,
+    pub item_density: f32,
+    pub palette: Palette,
+    pub ambient_light: f32,
+    pub terrain: Vec<(Tile, u32)>,
+    pub last: bool
+}
+
+impl LevelData {
+    pub fn generate<T: Rng>(&self, width: i32, height: i32, rng: &mut T) -> GeneratedMap {
+        self.pick_generator(rng).generate(width, height, self.min_size, rng)
+    }
+
+    pub fn pick_generator<T: Rng>(&self, rng: &mut T) -> Generator {
+        let chances = &mut self.generators
+            .iter()
+            .map(|(generator, weight)| Weighted {
+                weight: *weight,
+                item: *generator
+            })
+            .collect::<Vec<_>>();
+        WeightedChoice::new(chances).ind_sample(rng)
+    }
+
+    // An empty monster list lets every creature of the depth appear.
+    pub fn allows_monster(&self, name: &str) -> bool {
+        self.monsters.is_empty() || self.monsters.iter().any(|monster| monster == name)
+    }
+}
+
+pub fn load_levels(file: &str) -> Result<Vec<LevelData>, DataError> {
+    let base = load_file(file)?;
+    let mut levels = vec![];
+    for entry in entries(file, &base)? {
+        let depth = entry.i32("depth")?;
+        if depth < 1 {
+            return Err(entry.error("depth", "must be at least 1".to_string()));
+        }
+        let monster_chance = chance(&entry, "monster chance", 0.6)?;
+        let item_density = chance(&entry, "item density", 0.2)?;
+        if monster_chance + item_density > 1.0 {
+            return Err(entry.error("item density", "monster chance and item density add up to more than 1".to_string()));
+        }
+        let min_size = entry.opt_i32("min size", 6)?;
+        if min_size < 3 {
+            return Err(entry.error("min size", "must be at least 3".to_string()));
+        }
+        let default = Palette::default();
+        let palette = Palette {
+            wall: (
+                if entry.has("wall color") { entry.color("wall color")? } else { default.wall.0 },
+                if entry.has("wall background") { entry.color("wall background")? } else { default.wall.1 }
+            ),
+            floor: (
+                if entry.has("floor color") { entry.color("floor color")? } else { default.floor.0 },
+                if entry.has("floor background") { entry.color("floor background")? } else { default.floor.1 }
+            )
+        };
+        let ambient_light = chance(&entry, "ambient light", 1.0)?;
+        let last = entry.opt_bool("last", false)?;
+        let terrain = get_terrain(&entry)?;
+        if last && terrain.iter().any(|(tile, _)| *tile == Tile::Chasm) {
+            return Err(entry.error("terrain", "there is no level below the last one for a chasm to drop into".to_string()));
+        }
+        levels.push(LevelData {
+            name: entry.name.clone(),
+            depth: depth as u32,
+            generators: get_generators(&entry)?,
+            min_size,
+            monsters: get_monsters(&entry)?,
+            monster_chance,
+            item_density,
+            palette,
+            ambient_light,
+            terrain,
+            last
+        });
+    }
+    Ok(levels)
+}
+
+fn chance(entry: &Entry, field: &str, default: f32) -> Result<f32, DataError> {
+    let value = if entry.has(field) { entry.f32(field)? } else { default };
+    if value < 0.0 || value > 1.0 {
+        return Err(entry.error(field, "must be between 0 and 1".to_string()));
+    }
+    Ok(value)
+}
+
+// Generators map a generator name to how often it is picked.
+fn get_generators(entry: &Entry) -> Result<Vec<(Generator, u32)>, DataError> {
+    let hash = match entry.yaml["generators"] {
+        Yaml::Hash(ref hash) => hash,
+        Yaml::BadValue => return Err(entry.error("generators", "missing".to_string())),
+        _ => return Err(entry.error("generators", "expected a map of generator name to weight".to_string()))
+    };
+    let mut generators = vec![];
+    for (name, weight) in hash.iter() {
+        let name = name.as_str()
+            .ok_or_else(|| entry.error("generators", "expected a map of generator name to weight".to_string()))?;
+        let generator = GENERATOR_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, generator)| *generator)
+            .ok_or_else(|| {
+                let names: Vec<&str> = GENERATOR_NAMES.iter().map(|(name, _)| *name).collect();
+                entry.error("generators", format!("unknown generator \"{}\", expected one of: {}", name, names.join(", ")))
+            })?;
+        match weight.as_i64().and_then(|weight| u32::try_from(weight).ok()) {
+            Some(weight) if weight > 0 => generators.push((generator, weight)),
+            _ => return Err(entry.error("generators", format!("weight of \"{}\" must be above zero", name)))
+        }
+    }
+    if generators.is_empty() {
+        return Err(entry.error("generators", "needs at least one generator".to_string()));
+    }
+    Ok(generators)
+}
+
+// Terrain maps a terrain name to the number of pools, or tiles of rubble.
+fn get_terrain(entry: &Entry) -> Result<Vec<(Tile, u32)>, DataError> {
+    let hash = match entry.yaml["terrain"] {
+        Yaml::Hash(ref hash) => hash,
+        Yaml::BadValue => return Ok(vec![]),
+        _ => return Err(entry.error("terrain", "expected a map of terrain name to count".to_string()))
+    };
+    let mut terrain = vec![];
+    for (name, count) in hash.iter() {
+        let name = name.as_str()
+            .ok_or_else(|| entry.error("terrain", "expected a map of terrain name to count".to_string()))?;
+        let tile = TERRAIN_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, tile)| *tile)
+            .ok_or_else(|| {
+                let names: Vec<&str> = TERRAIN_NAMES.iter().map(|(name, _)| *name).collect();
+                entry.error("terrain", format!("unknown terrain \"{}\", expected one of: {}", name, names.join(", ")))
+            })?;
+        match count.as_i64().and_then(|count| u32::try_from(count).ok()) {
+            Some(count) => terrain.push((tile, count)),
+            _ => return Err(entry.error("terrain", format!("count of \"{}\" must not be negative", name)))
+        }
+    }
+    Ok(terrain)
+}
+
+fn get_monsters(entry: &Entry) -> Result<Vec<String>, DataError> {
+    match entry.yaml["monsters"] {
+        Yaml::BadValue => Ok(vec![]),
+        Yaml::Array(ref names) => names
+            .iter()
+            .map(|name| name.as_str()
+                .map(|name| name.to_string())
+                .ok_or_else(|| entry.error("monsters", "expected a list of creature names".to_string())))
+            .collect(),
+        _ => Err(entry.error("monsters", "expected a list of creature names".to_string()))
+    }
+}
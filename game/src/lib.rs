@@ -0,0 +1,49 @@
+#![feature(uniform_paths)]
+//! Rendering agnostic game core. Nothing in here opens a window, so games can be
+//! driven from tests and bots as well as from the tcod frontend in `main.rs`.
+
+extern crate fnv;
+extern crate yaml_rust;
+extern crate rand;
+extern crate inflector;
+
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+extern crate serde_json;
+
+#[macro_use] extern crate spawning_pool;
+
+extern crate map_generator;
+extern crate geo;
+
+pub mod messages;
+pub mod events;
+pub mod statistics;
+pub mod spells;
+pub mod save;
+pub mod journal;
+pub mod path;
+pub mod utils;
+pub mod rng;
+pub mod consts;
+pub mod spatial;
+pub mod map;
+pub mod lighting;
+pub mod noise;
+pub mod navigation;
+pub mod dungeon;
+pub mod scheduler;
+pub mod systems;
+pub mod components;
+pub mod renderer;
+pub mod game;
+pub mod ai;
+pub mod actions;
+pub mod rules;
+pub mod creatures;
+pub mod spawn;
+pub mod levels;
+pub mod items;
+pub mod data;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,176 @@
+use geo::colors::{self, Color};
+
+use geo::{Point, field_of_view};
+use crate::components;
+use crate::map::{Map, TileType};
+use crate::spatial::SpatialTable;
+use crate::game::GameState;
+
+// Tiles darker than this can't be made out, even when in line of sight.
+pub const MIN_LIGHT: f32 = 0.2;
+// How dark the colors of a barely lit tile get.
+const MIN_SHADE: f32 = 0.25;
+const LAVA_RADIUS: i32 = 3;
+const LAVA_COLOR: Color = colors::ORANGE;
+
+// Amount of red, green and blue light on a tile, from 0 to 1 each.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Illumination {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32
+}
+
+impl Illumination {
+    pub fn new(level: f32) -> Illumination {
+        Illumination { r: level, g: level, b: level }
+    }
+
+    fn add(&mut self, color: Color, strength: f32) {
+        self.r = (self.r + color.r as f32 / 255.0 * strength).min(1.0);
+        self.g = (self.g + color.g as f32 / 255.0 * strength).min(1.0);
+        self.b = (self.b + color.b as f32 / 255.0 * strength).min(1.0);
+    }
+
+    pub fn level(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    pub fn is_lit(&self) -> bool {
+        self.level() >= MIN_LIGHT
+    }
+
+    // Fully lit tiles keep their colors, darker ones fade and take on the
+    // color of the light.
+    pub fn tint(&self, color: Color) -> Color {
+        let shade = |c: u8, light: f32| (c as f32 * (MIN_SHADE + (1.0 - MIN_SHADE) * light)) as u8;
+        Color {
+            r: shade(color.r, self.r),
+            g: shade(color.g, self.g),
+            b: shade(color.b, self.b)
+        }
+    }
+}
+
+pub fn is_transparent(map: &Map, spatial_table: &SpatialTable, pos: Point) -> bool {
+    map.in_bounds(pos.x, pos.y)
+        && !map.get_cell(pos.x, pos.y).blocks_sight
+        && !spatial_table.get(pos).map_or(false, |cell| cell.opaque)
+}
+
+// The light on every tile of the level: the ambient light of the map, plus
+// whatever reaches the tile from entities carrying a light and from lava.
+// Rebuilt with the spatial table once an action is done, everything else
+// only reads it.
+#[derive(Default)]
+pub struct LightMap {
+    width: i32,
+    tiles: Vec<Illumination>
+}
+
+impl LightMap {
+    pub fn new(state: &GameState) -> LightMap {
+        let map = &state.map;
+        let mut light_map = LightMap {
+            width: map.dimensions.x,
+            tiles: vec![Illumination::new(map.ambient_light); map.cells.len()]
+        };
+        let mut sources: Vec<(Point, i32, Color)> = state.spawning_pool
+            .get_all::<components::Light>()
+            .iter()
+            .filter_map(|&(id, light)| {
+                let physics = state.spawning_pool.get::<components::Physics>(id)?;
+                Some((physics.coord, light.radius, light.color))
+            })
+            .collect();
+        for (i, cell) in map.cells.iter().enumerate() {
+            if cell.tile_type == TileType::Lava {
+                let pos = Point::new(i as i32 % map.dimensions.x, i as i32 / map.dimensions.x);
+                sources.push((pos, LAVA_RADIUS, LAVA_COLOR));
+            }
+        }
+        for (origin, radius, color) in sources {
+            let tiles = &mut light_map.tiles;
+            let transparent = |pos| is_transparent(map, &state.spatial_table, pos);
+            field_of_view(origin, radius, transparent, |pos| {
+                if map.in_bounds(pos.x, pos.y) {
+                    let strength = 1.0 - origin.distance(pos) / (radius + 1) as f32;
+                    tiles[(pos.x + pos.y * map.dimensions.x) as usize].add(color, strength);
+                }
+            });
+        }
+        light_map
+    }
+
+    // Light maps aren't saved, a loaded game needs a new one.
+    pub fn is_stale(&self, state: &GameState) -> bool {
+        self.width != state.map.dimensions.x || self.tiles.len() != state.map.cells.len()
+    }
+
+    // Without a light map, before the first turn, everything is lit.
+    pub fn get(&self, pos: Point) -> Illumination {
+        if pos.x < 0 || pos.x >= self.width || pos.y < 0 {
+            return Illumination::new(1.0);
+        }
+        self.tiles
+            .get((pos.x + pos.y * self.width) as usize)
+            .cloned()
+            .unwrap_or_else(|| Illumination::new(1.0))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::lighting::*;
+    use crate::testing;
+
+    fn assert_level(light: &LightMap, pos: Point, level: f32) {
+        let actual = light.get(pos).level();
+        assert!((actual - level).abs() < 0.001, "light at {:?} is {}, expected {}", pos, actual, level);
+    }
+
+    #[test]
+    fn test_ambient_light() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        testing::empty_level(&mut game.state, 0.5);
+        let light = LightMap::new(&game.state);
+        assert_eq!(light.get(Point::new(3, 4)), Illumination::new(0.5));
+        assert!(light.get(Point::new(3, 4)).is_lit());
+
+        testing::empty_level(&mut game.state, 0.1);
+        assert!(!LightMap::new(&game.state).get(Point::new(3, 4)).is_lit());
+    }
+
+    #[test]
+    fn test_light_fades_with_distance() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        testing::empty_level(&mut game.state, 0.0);
+        let origin = Point::new(10, 10);
+        testing::add_light(&mut game.state, origin, 4);
+        let light = LightMap::new(&game.state);
+        assert_level(&light, origin + Point::new(1, 0), 0.8);
+        assert_level(&light, origin + Point::new(0, 2), 0.6);
+        assert_level(&light, origin + Point::new(-4, 0), 0.2);
+        assert_level(&light, origin + Point::new(5, 0), 0.0);
+    }
+
+    #[test]
+    fn test_walls_block_light() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        testing::empty_level(&mut game.state, 0.0);
+        let origin = Point::new(10, 10);
+        testing::add_light(&mut game.state, origin, 4);
+        testing::set_tile(&mut game.state, origin + Point::new(1, 0), TileType::Wall);
+        let light = LightMap::new(&game.state);
+        assert_level(&light, origin + Point::new(2, 0), 0.0);
+        assert_level(&light, origin + Point::new(-2, 0), 0.6);
+    }
+
+    #[test]
+    fn test_dim_tiles_are_not_lit() {
+        assert!(Illumination::new(MIN_LIGHT).is_lit());
+        assert!(!Illumination::new(MIN_LIGHT - 0.01).is_lit());
+        assert!(Illumination{r: MIN_LIGHT, g: 0.0, b: 0.0}.is_lit());
+    }
+}
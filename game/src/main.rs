@@ -1,44 +1,21 @@
  #![feature(uniform_paths)]
  #![feature(drain_filter)]
-use std::rc::Rc;
-use std::cell::RefCell;
 
-extern crate fnv;
 extern crate time;
-extern crate yaml_rust;
 extern crate tcod;
-extern crate rand;
-extern crate inflector;
-
-extern crate serde;
-#[macro_use] extern crate serde_derive;
-extern crate serde_json;
+extern crate geo;
+extern crate wily;
 
+use std::rc::Rc;
 use tcod::console::*;
 
-#[macro_use] extern crate spawning_pool;
+use wily::{actions, components, consts, game, map, save, spatial, utils, rng, journal, events, data};
+use wily::rng::Seed;
+use journal::Journal;
+use data::GameData;
 
-extern crate map_generator;
-extern crate geo;
-
-pub mod messages;
-pub mod spells;
-pub mod screens;
-pub mod save;
-pub mod path;
-pub mod utils;
-pub mod consts;
-pub mod spatial;
-pub mod map;
-pub mod scheduler;
-pub mod systems;
-pub mod components;
-pub mod render;
-pub mod game;
-pub mod ai;
-pub mod actions;
-pub mod rules;
-pub mod creatures;
+mod screens;
+mod render;
 
 use game::*;
 use geo::*;
@@ -47,6 +24,7 @@ use render::*;
 use map::*;
 use spatial::*;
 use actions::*;
+use events::GameEvent;
 
 fn main() {
     let root = Root::initializer()
@@ -68,16 +46,61 @@ fn main() {
         status_animations: Default::default()
     };
 
-    run_game(&mut tcod);
+    let data = match GameData::load() {
+        Ok(data) => Rc::new(data),
+        Err(err) => {
+            eprintln!("Could not load game data: {}", err);
+            return;
+        }
+    };
+
+    if let Some(path) = arg_value("--replay") {
+        match save::load_replay(&path) {
+            Ok(journal) => run_replay(&mut tcod, journal, data),
+            Err(err) => eprintln!("Could not load replay {}: {}", path, err)
+        }
+    } else {
+        run_game(&mut tcod, seed_from_args(), data);
+    }
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == name)?;
+    args.get(index + 1).cloned()
+}
+
+fn seed_from_args() -> Option<Seed> {
+    match rng::parse_seed(&arg_value("--seed")?) {
+        Ok(seed) => Some(seed),
+        Err(err) => {
+            eprintln!("Invalid seed, {}", err);
+            None
+        }
+    }
+}
+
+fn new_game(seed: Option<Seed>, data: &Rc<GameData>) -> Game {
+    let seed = seed.unwrap_or_else(rng::random_seed);
+    println!("seed: {}", rng::format_seed(&seed));
+    Game::new(seed, data.clone())
 }
 
 /*
 */
 
+// The journal is written when a game ends, is left or is swapped for
+// another one, not after every action. Replays never write it back.
+fn save_replay(game: &Game) {
+    if !game.state.journal.is_empty() {
+        let _ = save::save_replay(&game.state.journal);
+    }
+}
+
 
-fn run_game(tcod: &mut Tcod) {
+fn run_game(tcod: &mut Tcod, seed: Option<Seed>, data: Rc<GameData>) {
     let mut manager = screens::ScreenManager::new();
-    let mut game = create_new_game();
+    let mut game = new_game(seed, &data);
 
     manager.add(Box::new(screens::main_menu::MainMenuScreen::new()));
     let mut t_0 = time::precise_time_ns();
@@ -93,44 +116,37 @@ fn run_game(tcod: &mut Tcod) {
         let mut actions = vec![];
 
         manager.handle_input(&mut game.state);
-        manager.tick(&mut game.state, tcod, &mut actions);
+        manager.tick(&mut game.state, &mut actions);
 
         if actions.iter().any(|a| a.command == Command::CreateGame) {
-            game = create_new_game();
+            save_replay(&game);
+            game = new_game(seed, &data);
         }
         if actions.iter().any(|a| a.command == Command::LoadGame) {
-            game.state = screens::main_menu::load_game();
-            game.state.spatial_table.dirty = true;
-        }
-
-        if game.state.spatial_table.dirty {
-            game.state.spatial_table.dirty = false;
-            update_fov_map(&mut game.fov, &game.state.map, &game.state.spatial_table);
+            save_replay(&game);
+            game.state = screens::main_menu::load_game(data.clone());
         }
 
-        let mut animations = vec![];
         let tick_result = if game.current_action.is_none() || !actions.is_empty() {
-            game.game_tick(actions, &mut animations)
+            game.game_tick(actions, tcod)
         } else {
             TickResult::Wait(WaitResult::Wait)
         };
-
-        for animation in animations {
-            tcod.add_animation(animation);
+        let events = game.take_events();
+        let player_died = events.iter().any(|event| match *event {
+            GameEvent::EntityDied{entity, ..} => entity == game.state.player,
+            _ => false
+        });
+        if player_died {
+            save_replay(&game);
         }
+        manager.on_events(&events, &game.state);
 
         manager.post_tick(&game.state);
-        manager.render(t_delta, &mut game.state, &game.fov, tcod);
+        manager.render(t_delta, &mut game.state, tcod);
         manager.add_screens(&mut game.state);
         manager.clear_screens(&mut game.state);
 
-        if let Some(stats) = game.state.spawning_pool.get_mut::<components::Stats>(game.state.player) {
-            if stats.points > 0 {
-                manager.add_screen(Rc::new(RefCell::new(Box::new(screens::LevelUpScreen::new()))));
-                stats.points -= 1;
-            }
-        }
-
         match tick_result {
             TickResult::Passed => {
                 game.state.spawning_pool.cleanup_removed();
@@ -211,39 +227,41 @@ fn run_game(tcod: &mut Tcod) {
         }
 
     }
+    save_replay(&game);
 }
 
-pub fn update_fov_map(fov: &mut tcod::map::Map, map: &Map, spatial_table: &SpatialTable) {
-    for x in 0..spatial_table.width {
-        for y in 0..spatial_table.height {
-            let (mut blocks_sight, mut blocks_movement) = (map.get_cell(x, y).blocks_sight, map.get_cell(x, y).blocks_movement);
-            if let Some(spatial_cell) = spatial_table.get(Point::new(x, y)) {
-                if spatial_cell.solid {
-                    blocks_movement = true;
-                }
-                if spatial_cell.opaque {
-                    blocks_sight = true;
-                }
-            }
-            fov.set(x, y, !blocks_sight, !blocks_movement);
-        }
-    }
-}
+fn run_replay(tcod: &mut Tcod, journal: Journal, data: Rc<GameData>) {
+    let mut manager = screens::ScreenManager::new();
+    let mut game = Game::new(journal.seed, data);
 
-fn create_new_game() -> Game {
-    let mut state = GameState::new();
-    state.new_level();
-
-    let fov = tcod::map::Map::new(state.map.dimensions.x, state.map.dimensions.y);
-
-    Game {
-        state,
-        fov,
-        tick_time: 0,
-        current_action: None,
-        action_queue: vec![],
-        reaction_queue: vec![],
-        rejection_queue: vec![],
-        systems: systems::DurationSystem::new()
+    manager.add(Box::new(screens::ReplayScreen::new(journal)));
+    let mut t_0 = time::precise_time_ns();
+    let mut t_1;
+    let mut t_delta: f64;
+    while !tcod.root.window_closed() && !manager.screens.is_empty() {
+        t_1 = time::precise_time_ns();
+        t_delta = (t_1 - t_0) as f64 / 1_000_000.0;
+        t_0 = t_1;
+        let mut actions = vec![];
+
+        manager.handle_input(&mut game.state);
+        manager.tick(&mut game.state, &mut actions);
+
+        // target and level up choices are part of the journal, so no
+        // screens are opened for them while replaying
+        let tick_result = if game.current_action.is_none() || !actions.is_empty() {
+            game.game_tick(actions, tcod)
+        } else {
+            TickResult::Wait(WaitResult::Wait)
+        };
+        game.take_events();
+
+        manager.post_tick(&game.state);
+        manager.render(t_delta, &mut game.state, tcod);
+        manager.clear_screens(&mut game.state);
+
+        if let TickResult::Passed = tick_result {
+            game.state.spawning_pool.cleanup_removed();
+        }
     }
 }
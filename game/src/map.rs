@@ -1,23 +1,63 @@
-use tcod;
-use tcod::colors;
+use geo::colors;
 
 use rand;
 use rand::*;
 use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
 
-use crate::spells;
 use crate::spatial::*;
-use map_generator::{Map as GeneratedMap, bsp, tower, corridor};
+use map_generator::{Map as GeneratedMap, Feature, Tile, vaults, terrain};
 use crate::components;
+use crate::actions;
 use geo::*;
 use spawning_pool::{EntityId};
 use crate::creatures::*;
+use crate::items::*;
+use crate::data::GameData;
+use crate::levels::LevelData;
+use crate::spawn::{self, RoomDifficulty};
 use crate::scheduler::{Scheduler};
 
 #[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
-    Floor
+    Floor,
+    Water,
+    DeepWater,
+    Lava,
+    Chasm,
+    Rubble
+}
+
+impl TileType {
+    // Doors and stairs are entities standing on the floor.
+    fn from_tile(tile: Tile) -> TileType {
+        match tile {
+            Tile::Wall => TileType::Wall,
+            Tile::Floor | Tile::Door | Tile::Stairs => TileType::Floor,
+            Tile::Water => TileType::Water,
+            Tile::DeepWater => TileType::DeepWater,
+            Tile::Lava => TileType::Lava,
+            Tile::Chasm => TileType::Chasm,
+            Tile::Rubble => TileType::Rubble
+        }
+    }
+
+    // Ground nobody would step onto unless they meant to: deep water only
+    // lets swimmers through, lava burns and chasms drop to the next level.
+    pub fn is_hazard(self) -> bool {
+        match self {
+            TileType::DeepWater | TileType::Lava | TileType::Chasm => true,
+            TileType::Wall | TileType::Floor | TileType::Water | TileType::Rubble => false
+        }
+    }
+
+    // Time it takes to step onto the tile.
+    pub fn walk_time(self) -> i32 {
+        match self {
+            TileType::Water | TileType::DeepWater | TileType::Rubble => 100,
+            TileType::Wall | TileType::Floor | TileType::Lava | TileType::Chasm => 50
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -28,10 +68,31 @@ pub struct Cell {
 }
 
 impl Cell {
-    pub fn get_render_info(&self) -> (char, tcod::colors::Color, tcod::colors::Color) {
+    pub fn get_render_info(&self, palette: &Palette) -> (char, colors::Color, colors::Color) {
         match self.tile_type {
-            TileType::Wall => ('#', colors::DARK_GREY, tcod::colors::Color{r: 0, g: 20, b: 35}),
-            TileType::Floor => ('.', colors::DARK_GREY, tcod::colors::Color{r: 0, g: 10, b: 20})
+            TileType::Wall => ('#', palette.wall.0, palette.wall.1),
+            TileType::Floor => ('.', palette.floor.0, palette.floor.1),
+            TileType::Water => ('~', colors::LIGHT_BLUE, colors::DARKER_BLUE),
+            TileType::DeepWater => ('~', colors::BLUE, colors::DARKEST_BLUE),
+            TileType::Lava => ('~', colors::ORANGE, colors::DARKER_RED),
+            TileType::Chasm => (':', colors::DARKEST_GREY, colors::BLACK),
+            TileType::Rubble => ('%', palette.wall.0, palette.floor.1)
+        }
+    }
+}
+
+// Foreground and background colors of the tiles, set per level.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Palette {
+    pub wall: (colors::Color, colors::Color),
+    pub floor: (colors::Color, colors::Color)
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette {
+            wall: (colors::DARK_GREY, colors::Color{r: 0, g: 20, b: 35}),
+            floor: (colors::DARK_GREY, colors::Color{r: 0, g: 10, b: 20})
         }
     }
 }
@@ -40,17 +101,23 @@ impl Cell {
 pub struct Map {
     pub dimensions: Point,
     pub cells: Vec<Cell>,
+    #[serde(default)]
+    pub palette: Palette,
+    // Light on every tile before any light sources, from 0 to 1.
+    #[serde(default = "full_light")]
+    pub ambient_light: f32
+}
+
+fn full_light() -> f32 {
+    1.0
 }
 
 impl Map {
-    pub fn new(m: &GeneratedMap) -> Map {
+    pub fn new(m: &GeneratedMap, palette: Palette, ambient_light: f32) -> Map {
         let mut cells = vec![];
 
-        for v in &m.data {
-            let tile_type = match v {
-                0 => TileType::Wall,
-                _ => TileType::Floor
-            };
+        for tile in &m.data {
+            let tile_type = TileType::from_tile(*tile);
             cells.push(Cell{
                 tile_type,
                 blocks_movement: tile_type == TileType::Wall,
@@ -60,7 +127,9 @@ impl Map {
 
         Map {
             dimensions: (m.width, m.height).into(),
-            cells
+            cells,
+            palette,
+            ambient_light
         }
     }
 
@@ -74,15 +143,21 @@ impl Map {
         x >= 0 && x < self.dimensions.x && y >= 0 && y < self.dimensions.y
     }
 
+    // Any open ground, water, lava and chasms included.
     pub fn is_floor(&self, position: Point) -> bool {
-        self.get_cell(position.x, position.y).tile_type == TileType::Floor
+        self.get_cell(position.x, position.y).tile_type != TileType::Wall
+    }
+
+    pub fn is_walkable(&self, position: Point) -> bool {
+        let cell = self.get_cell(position.x, position.y);
+        !cell.blocks_movement && !cell.tile_type.is_hazard()
     }
 }
 
 pub fn can_walk(position: Point, grid: &SpatialTable, map: &Map) -> bool {
     match grid.get(position) {
         Some(cell) => {
-            !cell.solid && map.is_floor(position)
+            !cell.solid && map.is_walkable(position)
         },
         None => {
             false
@@ -90,59 +165,76 @@ pub fn can_walk(position: Point, grid: &SpatialTable, map: &Map) -> bool {
     }
 }
 
+pub fn is_closed_door(position: Point, grid: &SpatialTable, spawning_pool: &components::SpawningPool) -> bool {
+    match grid.get(position) {
+        Some(cell) => cell.entities.iter().any(|entity| {
+            spawning_pool.get::<components::Door>(*entity).map_or(false, |door| !door.opened)
+        }),
+        None => false
+    }
+}
+
 pub fn empty_map(width: i32, height: i32) -> Map {
     Map {
         dimensions: Point::new(width, height),
-        cells: vec![]
+        cells: vec![],
+        palette: Palette::default(),
+        ambient_light: full_light()
     }
 }
 
-pub fn create_map(level: u32, player: EntityId, width: i32, height: i32, spawning_pool: &mut components::SpawningPool, scheduler: &mut Scheduler, seed: Option<[u32; 4]>) -> Map {
-    let creatures = load_creatures();
-    let mut rng: XorShiftRng = if let Some(seed) = seed {
-        SeedableRng::from_seed(seed)
-    } else {
-        rand::weak_rng()
-    };
+pub fn create_map<T: Rng>(level: u32, player: EntityId, width: i32, height: i32, data: &GameData, spawning_pool: &mut components::SpawningPool, scheduler: &mut Scheduler, rng: &mut T) -> Map {
+    let items = &data.items;
+    let config = data.level(level);
 
-    let generated = corridor::generate(width, height, 6, &mut rng);
-    let map = Map::new(&generated);
+    let mut generated = config.generate(width, height, rng);
+    vaults::stamp_vaults(&mut generated, &data.vaults, 1, rng);
+    for (tile, count) in &config.terrain {
+        terrain::add_terrain(&mut generated, *tile, *count, rng);
+    }
+    let map = Map::new(&generated, config.palette.clone(), config.ambient_light);
 
     spawning_pool.set(player, components::Physics{coord: generated.rooms[0].center()});
-    if level == 5 {
+    if level > 1 {
+        add_up_stairs(generated.rooms[0].center(), spawning_pool);
+    }
+    if data.is_final_level(level) {
         add_portal(generated.stairs.unwrap(), spawning_pool);
     } else {
         add_down_stairs(generated.stairs.unwrap(), spawning_pool);
     }
     let mut experience_index = rng.gen_range(3, generated.rooms.len() - 1) as usize;
-    add_experience_potion(generated.rooms[experience_index].center(), spawning_pool);
+    add_named_item("potion of experience", generated.rooms[experience_index].center(), items, spawning_pool);
     experience_index -= 1;
     for (idx, room) in generated.rooms.iter().skip(1).enumerate() {
         if experience_index == idx {
-            let _ = add_monsters(level + 2, room, &creatures, scheduler, width, height, spawning_pool, &mut rng);
+            let _ = add_monsters(level + 2, room, &map, config, data, scheduler, width, height, spawning_pool, rng);
         } else {
             let p = rng.gen::<f32>();
-            if p < 0.6 {
-                let difficulty = add_monsters(level, room, &creatures, scheduler, width, height, spawning_pool, &mut rng);
+            if p < config.monster_chance {
+                let difficulty = add_monsters(level, room, &map, config, data, scheduler, width, height, spawning_pool, rng);
                 if idx != experience_index {
                     match difficulty {
                         RoomDifficulty::Normal | RoomDifficulty::Difficult => {
                             if rng.gen::<f32>() > 0.4 {
-                                let entity = add_item(room.center(), spawning_pool, &mut rng);
-                                scheduler.schedule_entity(entity, 0, spawning_pool);
+                                if let Some(entity) = add_item(level, room.center(), items, spawning_pool, rng) {
+                                    scheduler.schedule_entity(entity, 0, spawning_pool);
+                                }
                             }
                         },
                         RoomDifficulty::Easy => {
                             if rng.gen::<f32>() > 0.8 {
-                                let entity = add_item(room.center(), spawning_pool, &mut rng);
-                                scheduler.schedule_entity(entity, 0, spawning_pool);
+                                if let Some(entity) = add_item(level, room.center(), items, spawning_pool, rng) {
+                                    scheduler.schedule_entity(entity, 0, spawning_pool);
+                                }
                             }
                         }
                     };
                 }
-            } else if p < 0.8 {
-                let entity = add_item(room.center(), spawning_pool, &mut rng);
-                scheduler.schedule_entity(entity, 0, spawning_pool);
+            } else if p < config.monster_chance + config.item_density {
+                if let Some(entity) = add_item(level, room.center(), items, spawning_pool, rng) {
+                    scheduler.schedule_entity(entity, 0, spawning_pool);
+                }
             }
         }
     }
@@ -151,17 +243,29 @@ pub fn create_map(level: u32, player: EntityId, width: i32, height: i32, spawnin
         add_door(*door, spawning_pool);
     }
 
+    for (pos, feature) in &generated.features {
+        add_feature(level, *pos, *feature, config, data, scheduler, width, height, spawning_pool, rng);
+    }
+
     map
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
-enum RoomDifficulty {
-    Easy,
-    Normal,
-    Difficult
+fn add_feature<T: Rng>(level: u32, pos: Point, feature: Feature, config: &LevelData, data: &GameData, scheduler: &mut Scheduler, width: i32, height: i32, spawning_pool: &mut components::SpawningPool, rng: &mut T) {
+    let entity = match feature {
+        Feature::Monster => spawn::pick_creature(level, config, data, rng)
+            .map(|creature| create_creature(creature, pos, width, height, spawning_pool)),
+        Feature::Item => add_item(level, pos, &data.items, spawning_pool, rng),
+        Feature::Rune => {
+            actions::write_rune_at("stun", pos, components::Faction::Neutral, spawning_pool);
+            None
+        }
+    };
+    if let Some(entity) = entity {
+        scheduler.schedule_entity(entity, 0, spawning_pool);
+    }
 }
 
-fn add_monsters<T: Rng>(level: u32, room: &Rect, creatures: &Vec<CreatureData>, scheduler: &mut Scheduler, width: i32, height: i32, spawning_pool: &mut components::SpawningPool, rng: &mut T) -> RoomDifficulty {
+fn add_monsters<T: Rng>(level: u32, room: &Rect, map: &Map, config: &LevelData, data: &GameData, scheduler: &mut Scheduler, width: i32, height: i32, spawning_pool: &mut components::SpawningPool, rng: &mut T) -> RoomDifficulty {
     let chances = &mut [
         Weighted {
             weight: 7,
@@ -179,51 +283,20 @@ fn add_monsters<T: Rng>(level: u32, room: &Rect, creatures: &Vec<CreatureData>,
 
     let chooser = WeightedChoice::new(chances);
     let choice = chooser.ind_sample(rng);
-
-    match choice {
-        RoomDifficulty::Easy => {
-            add_creature(&creatures[2], room, width, height, scheduler, spawning_pool, rng);
-            if level >= 2 {
-                add_creature(&creatures[0], room, width, height, scheduler, spawning_pool, rng);
-            }
-            if level >= 3 {
-                add_creature(&creatures[0], room, width, height, scheduler, spawning_pool, rng);
-            }
-        },
-        RoomDifficulty::Normal => {
-            add_creature(&creatures[1], room, width, height, scheduler, spawning_pool, rng);
-            add_creature(&creatures[0], room, width, height, scheduler, spawning_pool, rng);
-            if level >= 2 {
-                add_creature(&creatures[0], room, width, height, scheduler, spawning_pool, rng);
-            }
-            if level >= 3 {
-                add_creature(&creatures[1], room, width, height, scheduler, spawning_pool, rng);
-            }
-        },
-        RoomDifficulty::Difficult => {
-            add_creature(&creatures[0], room, width, height, scheduler, spawning_pool, rng);
-            add_creature(&creatures[1], room, width, height, scheduler, spawning_pool, rng);
-            add_creature(&creatures[2], room, width, height, scheduler, spawning_pool, rng);
-            if level >= 2 {
-                add_creature(&creatures[0], room, width, height, scheduler, spawning_pool, rng);
-            }
-            if level >= 3 {
-                add_creature(&creatures[1], room, width, height, scheduler, spawning_pool, rng);
-                add_creature(&creatures[2], room, width, height, scheduler, spawning_pool, rng);
-            }
-        }
+    for creature in spawn::generate_group(level, config, spawn::budget(level, choice), data, rng) {
+        add_creature(creature, room, map, width, height, scheduler, spawning_pool, rng);
     }
     choice
 }
 
-fn add_creature<T: Rng>(creature: &CreatureData, room: &Rect, width: i32, height: i32, scheduler: &mut Scheduler, spawning_pool: &mut components::SpawningPool, rng: &mut T) {
-    if let Some(point) = get_empty_spot(room, spawning_pool, rng) {
+fn add_creature<T: Rng>(creature: &CreatureData, room: &Rect, map: &Map, width: i32, height: i32, scheduler: &mut Scheduler, spawning_pool: &mut components::SpawningPool, rng: &mut T) {
+    if let Some(point) = get_empty_spot(room, map, spawning_pool, rng) {
         let creature = create_creature(creature, point, width, height, spawning_pool);
         scheduler.schedule_entity(creature, 0, spawning_pool);
     }
 }
 
-fn get_empty_spot<T: Rng>(room: &Rect, spawning_pool: &mut components::SpawningPool, rng: &mut T) -> Option<Point> {
+fn get_empty_spot<T: Rng>(room: &Rect, map: &Map, spawning_pool: &mut components::SpawningPool, rng: &mut T) -> Option<Point> {
     let mut iter = 0;
 
     while iter < 10 {
@@ -231,7 +304,7 @@ fn get_empty_spot<T: Rng>(room: &Rect, spawning_pool: &mut components::SpawningP
         let x = room.x1 + rng.gen_range(0, room.width);
         let y = room.y1 + rng.gen_range(0, room.height);
         let point = Point::new(x, y);
-        if !spawning_pool.get_all::<components::Physics>().iter().any(|(_, phys)| phys.coord == point) {
+        if map.is_walkable(point) && !spawning_pool.get_all::<components::Physics>().iter().any(|(_, phys)| phys.coord == point) {
             return Some(point);
         }
     }
@@ -255,6 +328,17 @@ fn add_down_stairs(pos: Point, spawning_pool: &mut components::SpawningPool) ->
     spawning_pool.set(stairs, components::Physics{coord: (pos.x, pos.y).into()});
     spawning_pool.set(stairs, components::Information{faction: components::Faction::Neutral, name: "down stairs".to_string()});
     spawning_pool.set(stairs, components::Flags{block_sight: false, solid: false});
+    spawning_pool.set(stairs, components::Stairs{direction: components::StairsDirection::Down});
+    stairs
+}
+
+fn add_up_stairs(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
+    let stairs = spawning_pool.spawn_entity();
+    spawning_pool.set(stairs, components::Visual{always_display: true, glyph: '>', color: colors::WHITE});
+    spawning_pool.set(stairs, components::Physics{coord: (pos.x, pos.y).into()});
+    spawning_pool.set(stairs, components::Information{faction: components::Faction::Neutral, name: "up stairs".to_string()});
+    spawning_pool.set(stairs, components::Flags{block_sight: false, solid: false});
+    spawning_pool.set(stairs, components::Stairs{direction: components::StairsDirection::Up});
     stairs
 }
 
@@ -268,174 +352,25 @@ fn add_portal(pos: Point, spawning_pool: &mut components::SpawningPool) -> Entit
 }
 
 
-fn add_item<T: Rng>(pos: Point, spawning_pool: &mut components::SpawningPool, rng: &mut T) -> EntityId {
-    let chances = &mut [
-        Weighted {
-            weight: 5,
-            item: "healing"
-        },
-        Weighted {
-            weight: 1,
-            item: "scroll"
-        },
-        Weighted {
-            weight: 2,
-            item: "confuse"
-        },
-        Weighted {
-            weight: 1,
-            item: "sword"
-        },
-        Weighted {
-            weight: 1,
-            item: "shield"
-        },
-        Weighted {
-            weight: 500,
-            item: "magic_missile"
-        },
-        Weighted {
-            weight: 3,
-            item: "frost"
-        }
-    ];
-
-    let choice = WeightedChoice::new(chances);
-
-    match choice.ind_sample(rng) {
-        "magic_missile" => add_magic_missile_scroll(pos, spawning_pool),
-        "frost" => add_frost_scroll(pos, spawning_pool),
-        "healing" => add_healing_potion(pos, spawning_pool),
-        "scroll" => add_lightning_scroll(pos, spawning_pool),
-        "confuse" => add_confusion_scroll(pos, spawning_pool),
-        "sword" => add_sword(pos, spawning_pool),
-        "shield" => add_shield(pos, spawning_pool),
-        _ => panic!()
-    }
-}
-
-fn add_sword(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '/', color: colors::LIGHT_CYAN});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "sword".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: None,
-        equip: Some(components::EquipmentSlot::RightHand),
-        kind: components::ItemKind::Equipment,
-        statistics_bonus: Some(components::StatisticsBonus{
-            strength: 5,
-            defense: 0
-        })
-    });
-    item
-}
-
-fn add_magic_missile_scroll(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '?', color: tcod::colors::Color{r: 250, g: 50, b: 150}});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "scroll of magic missile".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: Some(components::OnUseCallback::Spell(spells::Spells::MagicMissile)),
-        equip: None,
-        kind: components::ItemKind::Scroll,
-        statistics_bonus: None
-    });
-    item
-}
-
-fn add_frost_scroll(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '?', color: tcod::colors::Color{r: 50, g: 150, b: 150}});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "scroll of frost".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: Some(components::OnUseCallback::Spell(spells::Spells::RayOfFrost)),
-        equip: None,
-        kind: components::ItemKind::Scroll,
-        statistics_bonus: None
-    });
-    item
-}
-
-fn add_confusion_scroll(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '?', color: tcod::colors::Color{r: 130, g: 50, b: 130}});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "scroll of confusion".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: Some(components::OnUseCallback::Spell(spells::Spells::Confusion)),
-        equip: None,
-        kind: components::ItemKind::Scroll,
-        statistics_bonus: None
-    });
-    item
-}
-
-fn add_lightning_scroll(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '?', color: tcod::colors::Color{r: 0, g: 100, b: 180}});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "scroll of lightning".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: Some(components::OnUseCallback::Spell(spells::Spells::LightningStrike)),
-        equip: None,
-        kind: components::ItemKind::Scroll,
-        statistics_bonus: None
-    });
-    item
-}
-
-fn add_shield(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: ')', color: colors::LIGHTEST_CYAN});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "buckler".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: None,
-        equip: Some(components::EquipmentSlot::LeftHand),
-        kind: components::ItemKind::Equipment,
-        statistics_bonus: Some(components::StatisticsBonus{
-            strength: 0,
-            defense: 3
+fn add_item<T: Rng>(level: u32, pos: Point, items: &[ItemData], spawning_pool: &mut components::SpawningPool, rng: &mut T) -> Option<EntityId> {
+    let chances = &mut items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.weight(level) > 0)
+        .map(|(index, item)| Weighted {
+            weight: item.weight(level),
+            item: index
         })
-    });
-    item
-}
+        .collect::<Vec<_>>();
+    if chances.is_empty() {
+        return None;
+    }
 
-fn add_healing_potion(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '!', color: colors::PINK});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "potion of healing".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: Some(components::OnUseCallback::Spell(spells::Spells::Heal)),
-        equip: None,
-        kind: components::ItemKind::Potion,
-        statistics_bonus: None
-    });
-    item
+    let choice = WeightedChoice::new(chances);
+    Some(create_item(&items[choice.ind_sample(rng)], pos, spawning_pool))
 }
 
-fn add_experience_potion(pos: Point, spawning_pool: &mut components::SpawningPool) -> EntityId {
-    let item = spawning_pool.spawn_entity();
-    spawning_pool.set(item, components::Visual{always_display: false, glyph: '!', color: colors::LIGHTEST_GREEN});
-    spawning_pool.set(item, components::Physics{coord: pos});
-    spawning_pool.set(item, components::Flags{block_sight: false, solid: false});
-    spawning_pool.set(item, components::Information{faction: components::Faction::Neutral, name: "potion of experience".to_string()});
-    spawning_pool.set(item, components::Item{
-        on_use: Some(components::OnUseCallback::Spell(spells::Spells::Experience)),
-        equip: None,
-        kind: components::ItemKind::Potion,
-        statistics_bonus: None
-    });
-    item
+fn add_named_item(name: &str, pos: Point, items: &[ItemData], spawning_pool: &mut components::SpawningPool) -> Option<EntityId> {
+    let data = items.iter().find(|item| item.name == name)?;
+    Some(create_item(data, pos, spawning_pool))
 }
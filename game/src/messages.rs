@@ -1,6 +1,14 @@
-use tcod::colors::Color;
-use tcod::colors;
+use inflector::Inflector;
+use geo::colors::Color;
+use geo::colors;
+use spawning_pool::EntityId;
+
 use crate::consts::*;
+use crate::utils;
+use crate::game::GameState;
+use crate::events::GameEvent;
+use crate::components::{self, Effect, Awareness};
+use crate::actions::{ActionTarget, LevelUpChoice};
 
 pub type Messages = Vec<(String, Color)>;
 
@@ -31,3 +39,124 @@ impl MessageLog for Messages {
         }
     }
 }
+
+pub fn event_message(event: &GameEvent, state: &GameState) -> Option<(MessageLevel, String)> {
+    let name = |entity: EntityId| utils::get_entity_name(entity, &state.spawning_pool);
+    let optional_name = |entity: Option<EntityId>| match entity {
+        Some(entity) => name(entity),
+        None => "Unknown".to_string()
+    };
+    let seen_by_player = |entity: EntityId| {
+        match (utils::get_position(entity, &state.spawning_pool), state.spawning_pool.get::<components::MapMemory>(state.player)) {
+            (Some(pos), Some(memory)) => memory.is_visible(pos.x, pos.y),
+            _ => false
+        }
+    };
+    match *event {
+        GameEvent::EntityDamaged{attacker, target, damage} => {
+            let level = if target == state.player {
+                MessageLevel::Important
+            } else {
+                MessageLevel::Info
+            };
+            match attacker {
+                Some(attacker) => Some((level, format!("The {} attacked the {} for {}", name(attacker), name(target), damage))),
+                None => Some((level, format!("The {} takes {} damage", name(target), damage)))
+            }
+        },
+        GameEvent::EntityHealed{healer, target, amount} => {
+            let healer_name = optional_name(healer);
+            let target_name = name(target);
+            if healer_name == target_name {
+                Some((MessageLevel::Info, format!("{} healed for {}", target_name.to_sentence_case(), amount)))
+            } else {
+                Some((MessageLevel::Info, format!("{} healed {} for {}", healer_name.to_sentence_case(), target_name, amount)))
+            }
+        },
+        GameEvent::EntityDied{entity, ref name} => {
+            if entity == state.player {
+                Some((MessageLevel::Important, format!("The {} has died!", name)))
+            } else {
+                Some((MessageLevel::Info, format!("The {} has died!", name)))
+            }
+        },
+        GameEvent::EntityFell{entity, ref name} => {
+            if entity == state.player {
+                Some((MessageLevel::Important, format!("The {} falls into the chasm!", name)))
+            } else {
+                Some((MessageLevel::Info, format!("The {} falls into the chasm", name)))
+            }
+        },
+        GameEvent::EntityStunned{entity} if entity == state.player => {
+            Some((MessageLevel::Info, "The player is stunned".to_string()))
+        },
+        GameEvent::EffectApplied{target, effect: Effect::Burning, ..} => {
+            let level = if target == state.player {
+                MessageLevel::Important
+            } else {
+                MessageLevel::Info
+            };
+            Some((level, format!("The {} catches fire!", name(target))))
+        },
+        GameEvent::EffectApplied{actor, target, effect: Effect::Confuse} => {
+            if actor == Some(state.player) {
+                Some((MessageLevel::Important, format!("The {} is confused!", name(target))))
+            } else {
+                Some((MessageLevel::Info, format!("The {} is confused!", name(target))))
+            }
+        },
+        GameEvent::EffectExpired{entity, effect} => {
+            Some((MessageLevel::Info, format!("The {} is no longer {}", name(entity), effect)))
+        },
+        GameEvent::ItemPickedUp{entity, item} => {
+            Some((MessageLevel::Info, format!("{} picked up {}", name(entity).to_sentence_case(), name(item))))
+        },
+        GameEvent::ItemUsed{entity, item} => {
+            Some((MessageLevel::Info, format!("{} uses {}", name(entity).to_sentence_case(), name(item))))
+        },
+        GameEvent::ItemEquipped{entity, item} => {
+            Some((MessageLevel::Info, format!("{} equips {}", name(entity).to_sentence_case(), name(item))))
+        },
+        GameEvent::ItemUnequipped{entity, item} => {
+            Some((MessageLevel::Info, format!("{} takes off the {}", name(entity).to_sentence_case(), name(item))))
+        },
+        GameEvent::SpellCast{caster, target, ref spell} => {
+            let target_name = match target {
+                Some(ActionTarget::Entity(target)) => Some(name(target)),
+                Some(_) => Some("Unknown".to_string()),
+                None => None
+            };
+            let msg = match (caster, target_name) {
+                (Some(caster), Some(target)) => format!("The {} is casting {} on {} ", name(caster), spell.name, target),
+                (Some(caster), None) => format!("The {} is casting {}", name(caster), spell.name),
+                (None, Some(target)) => format!("The {} is {}", target, spell.name),
+                (None, None) => format!("{} is cast", spell.name)
+            };
+            Some((MessageLevel::Spell, msg))
+        },
+        GameEvent::RuneWritten{entity, ref spell, ..} => {
+            let spell = state.data.spell(spell)?;
+            Some((MessageLevel::Spell, format!("The {} carves a {} rune on the floor", name(entity), spell.name)))
+        },
+        GameEvent::RuneBlocked{..} => {
+            Some((MessageLevel::Info, "There is already a rune there".to_string()))
+        },
+        GameEvent::LevelledUp{ref choice, ..} => {
+            match choice {
+                LevelUpChoice::Strength => Some((MessageLevel::Important, "The player grows stronger".to_string())),
+                LevelUpChoice::Defense => Some((MessageLevel::Important, "The player's skin thickens".to_string()))
+            }
+        },
+        GameEvent::LevelEntered{level} if level > 1 => {
+            Some((MessageLevel::Important, "The player's wounds heal and his body grows stronger".to_string()))
+        },
+        GameEvent::AwarenessChanged{entity, awareness} if seen_by_player(entity) => {
+            match awareness {
+                Awareness::Suspicious => Some((MessageLevel::Info, format!("The {} wakes up", name(entity)))),
+                Awareness::Hunting => Some((MessageLevel::Info, format!("The {} notices the player", name(entity)))),
+                Awareness::Unaware => None
+            }
+        },
+        _ => None
+    }
+}
@@ -0,0 +1,118 @@
+use geo::{Point, DijkstraMap};
+use crate::components;
+use crate::game::{GameState, get_entity_position};
+use crate::path::terrain_cost;
+
+// Dijkstra maps shared by every creature, rebuilt once per turn after the
+// player has acted. They cost the terrain and doors only, creatures are
+// stepped around when following a map.
+#[derive(Default)]
+pub struct Navigation {
+    pub to_player: DijkstraMap,
+    pub from_player: DijkstraMap,
+    pub to_stairs: DijkstraMap,
+    pub to_items: DijkstraMap,
+    items: Vec<Point>
+}
+
+impl Navigation {
+    pub fn new(state: &GameState) -> Navigation {
+        let map = &state.map;
+        let (width, height) = (map.dimensions.x, map.dimensions.y);
+        let cost = |pos: Point| terrain_cost(pos, state, false);
+        let player: Vec<Point> = get_entity_position(state.player, state).into_iter().collect();
+        let stairs: Vec<Point> = state.spawning_pool
+            .get_all::<components::Stairs>()
+            .iter()
+            .filter_map(|&(id, _)| get_entity_position(id, state))
+            .collect();
+        let items = item_positions(state);
+        let to_player = DijkstraMap::new(width, height, &player, cost);
+        Navigation {
+            from_player: to_player.flee(cost),
+            to_player,
+            to_stairs: DijkstraMap::new(width, height, &stairs, cost),
+            to_items: DijkstraMap::new(width, height, &items, cost),
+            items
+        }
+    }
+
+    // The maps lead to where the player and the items were when they were
+    // built.
+    pub fn is_stale(&self, state: &GameState) -> bool {
+        let moved = match get_entity_position(state.player, state) {
+            Some(position) => self.to_player.get(position) != Some(0),
+            None => false
+        };
+        moved || self.items != item_positions(state)
+    }
+}
+
+// items in an inventory have no position
+fn item_positions(state: &GameState) -> Vec<Point> {
+    state.spawning_pool
+        .get_all::<components::Item>()
+        .iter()
+        .filter_map(|&(id, _)| get_entity_position(id, state))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::navigation::*;
+    use crate::testing;
+
+    const START: Point = Point{x: 10, y: 10};
+
+    fn add_item(state: &mut GameState, coord: Point) -> spawning_pool::EntityId {
+        let data = state.data.clone();
+        crate::items::create_item(data.item("sword").unwrap(), coord, &mut state.spawning_pool)
+    }
+
+    fn place(state: &mut GameState, entity: spawning_pool::EntityId, coord: Point) {
+        state.spawning_pool.set(entity, components::Physics{coord});
+        state.spatial_table.reset(&state.spawning_pool);
+    }
+
+    #[test]
+    fn test_maps_lead_to_the_player_stairs_and_items() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let stairs = game.state.spawning_pool.get_all::<components::Stairs>()[0].0;
+        testing::empty_level(&mut game.state, 1.0);
+        place(&mut game.state, player, START);
+        place(&mut game.state, stairs, START + Point::new(6, 0));
+        add_item(&mut game.state, START + Point::new(0, 4));
+
+        let navigation = Navigation::new(&game.state);
+        assert_eq!(navigation.to_player.get(START), Some(0));
+        assert_eq!(navigation.to_player.get(START + Point::new(-3, 3)), Some(3));
+        assert_eq!(navigation.to_stairs.get(START + Point::new(6, 0)), Some(0));
+        assert_eq!(navigation.to_stairs.get(START + Point::new(2, 3)), Some(4));
+        assert_eq!(navigation.to_items.get(START), Some(4));
+        assert!(navigation.from_player.get(START) > navigation.from_player.get(START + Point::new(5, 0)));
+
+        let step = navigation.to_items.downhill(START + Point::new(3, 4), |_| true).unwrap();
+        assert_eq!(navigation.to_items.get(step), Some(2));
+    }
+
+    #[test]
+    fn test_maps_go_stale_when_the_player_or_items_move() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        testing::empty_level(&mut game.state, 1.0);
+        place(&mut game.state, player, START);
+        let item = add_item(&mut game.state, START + Point::new(0, 4));
+
+        assert!(Navigation::default().is_stale(&game.state));
+        let navigation = Navigation::new(&game.state);
+        assert!(!navigation.is_stale(&game.state));
+
+        place(&mut game.state, player, START + Point::new(1, 0));
+        assert!(navigation.is_stale(&game.state));
+        let navigation = Navigation::new(&game.state);
+
+        game.state.spawning_pool.remove::<components::Physics>(item);
+        assert!(navigation.is_stale(&game.state));
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use fnv::FnvHashMap;
+use spawning_pool::EntityId;
+
+use geo::*;
+use crate::actions::{Action, Command};
+use crate::components::{self, Awareness};
+use crate::events::GameEvent;
+use crate::game::GameState;
+use crate::map::is_closed_door;
+
+// How many steps away the commands can be heard.
+const WALK_NOISE: i32 = 3;
+const DOOR_NOISE: i32 = 6;
+const SPELL_NOISE: i32 = 6;
+const FIGHT_NOISE: i32 = 8;
+
+pub fn noise_radius(command: &Command) -> Option<i32> {
+    match *command {
+        Command::WalkDirection{..} => Some(WALK_NOISE),
+        Command::OpenDoor{..} => Some(DOOR_NOISE),
+        Command::CastSpell{..} => Some(SPELL_NOISE),
+        Command::AttackEntity{..} => Some(FIGHT_NOISE),
+        _ => None
+    }
+}
+
+fn blocks_noise(pos: Point, state: &GameState) -> bool {
+    if !state.map.in_bounds(pos.x, pos.y) || state.map.get_cell(pos.x, pos.y).blocks_movement {
+        return true;
+    }
+    is_closed_door(pos, &state.spatial_table, &state.spawning_pool)
+}
+
+// Steps it takes a sound to reach every tile within `radius` of `origin`,
+// going around walls and closed doors.
+pub fn propagate(origin: Point, radius: i32, state: &GameState) -> FnvHashMap<Point, i32> {
+    let mut reached = FnvHashMap::default();
+    let mut queue = VecDeque::new();
+    reached.insert(origin, 0);
+    queue.push_back(origin);
+    while let Some(pos) = queue.pop_front() {
+        let steps = reached[&pos];
+        if steps >= radius {
+            continue;
+        }
+        for next in get_neigbours(pos.x, pos.y, false) {
+            if !reached.contains_key(&next) && !blocks_noise(next, state) {
+                reached.insert(next, steps + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+pub fn set_awareness(entity: EntityId, awareness: Awareness, state: &mut GameState) {
+    if let Some(memory) = state.spawning_pool.get_mut::<components::AiMemory>(entity) {
+        if memory.awareness != awareness {
+            memory.awareness = awareness;
+            state.events.push(GameEvent::AwarenessChanged{entity, awareness});
+        }
+    }
+}
+
+// Creatures of other factions that hear the action wake up and go look for
+// where it came from. Perception adds to how far they hear, sleeping halves
+// it. Hunters hearing the player know where to find them again.
+pub fn make_noise(action: &Action, state: &mut GameState) {
+    use components::*;
+    let actor = match action.actor {
+        Some(actor) => actor,
+        None => return
+    };
+    let radius = match noise_radius(&action.command) {
+        Some(radius) => radius,
+        None => return
+    };
+    let origin = match state.spawning_pool.get::<Physics>(actor) {
+        Some(physics) => physics.coord,
+        None => return
+    };
+    let faction = state.spawning_pool.get::<Information>(actor).map(|info| info.faction);
+    let listeners: Vec<(EntityId, Point, i32)> = state.spawning_pool
+        .get_all::<AiMemory>()
+        .iter()
+        .filter(|&&(id, _)| id != actor && state.spawning_pool.get::<Information>(id).map(|info| info.faction) != faction)
+        .filter_map(|&(id, memory)| {
+            let pos = state.spawning_pool.get::<Physics>(id)?.coord;
+            let range = radius + state.spawning_pool.get::<Perception>(id).map_or(0, |perception| perception.value);
+            let range = if memory.awareness == Awareness::Unaware { range / 2 } else { range };
+            Some((id, pos, range))
+        })
+        .collect();
+    let max_range = match listeners.iter().map(|&(_, _, range)| range).max() {
+        Some(range) => range,
+        None => return
+    };
+    let reached = propagate(origin, max_range, state);
+    for (id, pos, range) in listeners {
+        if !reached.get(&pos).map_or(false, |steps| *steps <= range) {
+            continue;
+        }
+        let awareness = match state.spawning_pool.get_mut::<AiMemory>(id) {
+            Some(memory) => {
+                if memory.awareness == Awareness::Hunting {
+                    if actor == state.player {
+                        memory.player_position = Some(origin);
+                    }
+                } else {
+                    memory.noise_position = Some(origin);
+                }
+                memory.awareness
+            },
+            None => continue
+        };
+        if awareness == Awareness::Unaware {
+            set_awareness(id, Awareness::Suspicious, state);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::noise::*;
+    use crate::actions::{perform_action, ActionTarget};
+    use crate::map::TileType;
+    use crate::testing;
+
+    const ORIGIN: Point = Point{x: 10, y: 10};
+
+    fn add_door(state: &mut GameState, coord: Point) -> EntityId {
+        let door = state.spawning_pool.spawn_entity();
+        state.spawning_pool.set(door, components::Physics{coord});
+        state.spawning_pool.set(door, components::Door{opened: false});
+        state.spawning_pool.set(door, components::Flags{block_sight: true, solid: true});
+        state.spatial_table.reset(&state.spawning_pool);
+        door
+    }
+
+    // The first monster, `distance` steps east of the player walking at
+    // ORIGIN, and what it remembers after hearing the steps.
+    fn hear(distance: i32, perception: i32, awareness: Awareness) -> components::AiMemory {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let monster = testing::first_monster(&game.state);
+        testing::empty_level(&mut game.state, 1.0);
+        game.state.spawning_pool.set(player, components::Physics{coord: ORIGIN});
+        game.state.spawning_pool.set(monster, components::Physics{coord: ORIGIN + Point::new(distance, 0)});
+        game.state.spawning_pool.set(monster, components::Perception{value: perception});
+        game.state.spawning_pool.get_mut::<components::AiMemory>(monster).unwrap().awareness = awareness;
+        game.state.spatial_table.reset(&game.state.spawning_pool);
+
+        let walk = Action::new(Some(player), None, Command::WalkDirection{dir: Point::new(0, 1)});
+        make_noise(&walk, &mut game.state);
+        game.state.spawning_pool.get::<components::AiMemory>(monster).unwrap().clone()
+    }
+
+    #[test]
+    fn test_walls_and_closed_doors_block_noise() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        testing::empty_level(&mut game.state, 1.0);
+        let target = ORIGIN + Point::new(3, 0);
+        assert_eq!(propagate(ORIGIN, 5, &game.state).get(&target), Some(&3));
+        assert_eq!(propagate(ORIGIN, 2, &game.state).get(&target), None);
+
+        for y in 0..game.state.map.dimensions.y {
+            testing::set_tile(&mut game.state, Point::new(12, y), TileType::Wall);
+        }
+        assert_eq!(propagate(ORIGIN, 5, &game.state).get(&target), None);
+
+        let doorway = Point::new(12, ORIGIN.y);
+        testing::set_tile(&mut game.state, doorway, TileType::Floor);
+        let door = add_door(&mut game.state, doorway);
+        assert_eq!(propagate(ORIGIN, 5, &game.state).get(&target), None);
+
+        game.state.spawning_pool.set(door, components::Door{opened: true});
+        assert_eq!(propagate(ORIGIN, 5, &game.state).get(&target), Some(&3));
+    }
+
+    #[test]
+    fn test_perception_adds_range_and_sleep_halves_it() {
+        let heard = |memory: components::AiMemory| memory.noise_position == Some(ORIGIN);
+        assert!(heard(hear(1, 0, Awareness::Unaware)));
+        assert!(!heard(hear(2, 0, Awareness::Unaware)));
+        assert!(heard(hear(2, 2, Awareness::Unaware)));
+        assert!(heard(hear(3, 0, Awareness::Suspicious)));
+        assert!(!heard(hear(4, 0, Awareness::Suspicious)));
+        assert!(heard(hear(4, 1, Awareness::Suspicious)));
+    }
+
+    #[test]
+    fn test_noise_wakes_sleepers_and_guides_hunters() {
+        assert_eq!(hear(1, 0, Awareness::Unaware).awareness, Awareness::Suspicious);
+        assert_eq!(hear(2, 0, Awareness::Unaware).awareness, Awareness::Unaware);
+        assert_eq!(hear(1, 0, Awareness::Suspicious).awareness, Awareness::Suspicious);
+
+        let hunter = hear(3, 0, Awareness::Hunting);
+        assert_eq!(hunter.awareness, Awareness::Hunting);
+        assert_eq!(hunter.player_position, Some(ORIGIN));
+        assert_eq!(hunter.noise_position, None);
+    }
+
+    #[test]
+    fn test_getting_hurt_starts_the_hunt() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let monster = testing::first_monster(&game.state);
+        let hit = Action::new(None, Some(ActionTarget::Entity(monster)), Command::TakeDamage{damage: 1});
+        perform_action(&hit, &mut game.state);
+        let memory = game.state.spawning_pool.get::<components::AiMemory>(monster).unwrap();
+        assert_eq!(memory.awareness, Awareness::Hunting);
+        assert!(game.state.events.iter().any(|event| match *event {
+            GameEvent::AwarenessChanged{entity, awareness: Awareness::Hunting} => entity == monster,
+            _ => false
+        }));
+    }
+
+    #[test]
+    fn test_old_saves_keep_monsters_hunting() {
+        let saved = r#"{"player_position":null,"path_memory":{"previous_position":null,"goal":null,"path":null}}"#;
+        let memory: components::AiMemory = ::serde_json::from_str(saved).unwrap();
+        assert_eq!(memory.awareness, Awareness::Hunting);
+        assert_eq!(components::AiMemory::new().awareness, Awareness::Unaware);
+    }
+}
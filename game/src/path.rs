@@ -1,9 +1,11 @@
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
+use std::fmt;
+use spawning_pool::EntityId;
 
-use crate::spatial::*;
 use geo::*;
+use crate::game::GameState;
 use crate::map::*;
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -25,62 +27,226 @@ impl PartialOrd for State {
     }
 }
 
-pub fn path(start: Point, goal: Point, grid: &SpatialTable, map: &Map) -> Option<Vec<Point>> {
-    if start == (-1, -1) || goal == (-1, -1) {
-        return None;
+// Costs are in steps over plain floor.
+const DOOR_COST: i32 = 2;
+const CROWD_COST: i32 = 4;
+const RUNE_COST: i32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    OutOfBounds(Point),
+    AtGoal,
+    NoPath
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PathError::OutOfBounds(pos) => write!(f, "{} is outside the map", pos),
+            PathError::AtGoal => write!(f, "already at the goal"),
+            PathError::NoPath => write!(f, "the goal can't be reached")
+        }
+    }
+}
+
+// Cheapest path from `start` to `goal`, with `cost` telling what stepping
+// onto a tile costs, or none for tiles that can't be entered. The goal
+// itself can always be entered. The path is reversed, popping it gives the
+// next step.
+pub fn find_path<F>(start: Point, goal: Point, map: &Map, cost: F) -> Result<Vec<Point>, PathError>
+    where F: Fn(Point) -> Option<i32>
+{
+    for pos in &[start, goal] {
+        if !map.in_bounds(pos.x, pos.y) {
+            return Err(PathError::OutOfBounds(*pos));
+        }
+    }
+    if start == goal {
+        return Err(PathError::AtGoal);
     }
     let mut frontier = BinaryHeap::new();
     let mut from: HashMap<Point, Point> = Default::default();
     let mut cost_so_far: HashMap<Point, i32> = Default::default();
 
     frontier.push(State{priority: 0, position: start});
-    from.insert(start, start);
     cost_so_far.insert(start, 0);
 
-    let mut found = false;
     while let Some(State{position, .. }) = frontier.pop() {
-        if found || position == goal {
+        if position == goal {
             break;
         }
-        let point_cost = match cost_so_far.get(&position) {
-            Some(p) => *p,
-            None => unreachable!()
-        };
+        let point_cost = cost_so_far[&position];
 
         for neighbour in get_neigbours(position.x, position.y, false) {
-            if neighbour == goal {
-                from.insert(neighbour, position);
-                found = true;
-                break;
+            if !map.in_bounds(neighbour.x, neighbour.y) {
+                continue;
             }
-            if can_walk(neighbour, grid, map) {
-                let new_cost = point_cost + 1;
+            let step_cost = if neighbour == goal { Some(1) } else { cost(neighbour) };
+            if let Some(step_cost) = step_cost {
+                let new_cost = point_cost + step_cost.max(1);
                 if !cost_so_far.contains_key(&neighbour) || new_cost < cost_so_far[&neighbour] {
                     cost_so_far.insert(neighbour, new_cost);
-                    frontier.push(State{priority: heuristic(neighbour, goal), position: neighbour});
+                    frontier.push(State{priority: new_cost + heuristic(neighbour, goal), position: neighbour});
                     from.insert(neighbour, position);
                 }
             }
         }
     }
 
-    match from.get(&goal) {
-        Some(_) => {
-            let mut path = vec![goal];
-            let mut current = goal;
-            while let Some(p) = from.get(&current) {
-                if *p == start {
-                    break;
+    if !from.contains_key(&goal) {
+        return Err(PathError::NoPath);
+    }
+    let mut path = vec![goal];
+    let mut current = goal;
+    while let Some(p) = from.get(&current) {
+        if *p == start {
+            break;
+        }
+        path.push(*p);
+        current = *p;
+    }
+    Ok(path)
+}
+
+// What stepping onto a tile costs anyone, ignoring what stands on it apart
+// from doors, which take a turn to open. Only swimmers go in deep water.
+pub fn terrain_cost(position: Point, state: &GameState, swimmer: bool) -> Option<i32> {
+    let map = &state.map;
+    if !map.in_bounds(position.x, position.y) {
+        return None;
+    }
+    let tile_type = map.get_cell(position.x, position.y).tile_type;
+    if !map.is_walkable(position) && !(swimmer && tile_type == TileType::DeepWater) {
+        return None;
+    }
+    let cost = tile_type.walk_time() / TileType::Floor.walk_time();
+    if is_closed_door(position, &state.spatial_table, &state.spawning_pool) {
+        Some(cost + DOOR_COST)
+    } else {
+        Some(cost)
+    }
+}
+
+// Cost of the way for `actor`: terrain, plus runes on tiles it has seen and
+// creatures standing in the way, which may have moved on by then. Anything
+// else solid can't be walked through, items and stairs are walked over.
+pub fn walk_cost(actor: EntityId, state: &GameState) -> impl Fn(Point) -> Option<i32> + '_ {
+    use crate::components::*;
+    let swimmer = state.spawning_pool.get::<Swimmer>(actor).is_some();
+    let faction = state.spawning_pool.get::<Information>(actor).map(|info| info.faction);
+    let memory = state.spawning_pool.get::<MapMemory>(actor);
+    move |position: Point| {
+        let mut cost = terrain_cost(position, state, swimmer)?;
+        let cell = match state.spatial_table.get(position) {
+            Some(cell) => cell,
+            None => return Some(cost)
+        };
+        for entity in cell.entities.iter().filter(|entity| **entity != actor) {
+            if state.spawning_pool.get::<Trigger>(*entity).is_some() {
+                let own = faction.is_some() && state.spawning_pool.get::<Information>(*entity).map(|info| info.faction) == faction;
+                if own || memory.map_or(false, |memory| memory.is_explored(position.x, position.y)) {
+                    cost += RUNE_COST;
                 }
-                path.push(*p);
-                current = *p;
-            };
-            Some(path)
-        },
-        None => None
+                continue;
+            }
+            let solid = state.spawning_pool.get::<Flags>(*entity).map_or(false, |flags| flags.solid);
+            if !solid || state.spawning_pool.get::<Door>(*entity).is_some() {
+                continue;
+            }
+            if state.spawning_pool.get::<Stats>(*entity).is_some() {
+                cost += CROWD_COST;
+            } else {
+                return None;
+            }
+        }
+        Some(cost)
     }
 }
 
+// Steps left with diagonal moves allowed, never more than the real path.
 fn heuristic(a: Point, b: Point) -> i32 {
-    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+    a.tile_distance(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::*;
+    use crate::actions;
+    use crate::testing;
+    use crate::components::{self, Faction};
+    use crate::game::{Game, get_entity_position};
+
+    // A game with its monsters and items cleared from around the player,
+    // and an empty floor tile next to them.
+    fn new_game() -> (Game, Point, Point) {
+        let mut game = testing::new_game([1, 1, 2, 3]);
+        let player = game.state.player;
+        let start = get_entity_position(player, &game.state).unwrap();
+        let next = get_neigbours(start.x, start.y, false)
+            .into_iter()
+            .find(|pos| can_walk(*pos, &game.state.spatial_table, &game.state.map)
+                && terrain_cost(*pos, &game.state, false) == Some(1)
+                && game.state.spatial_table.get(*pos).map_or(true, |cell| cell.entities.is_empty()))
+            .unwrap();
+        (game, start, next)
+    }
+
+    fn add_entity(game: &mut Game, pos: Point, solid: bool, creature: bool) {
+        let entity = game.state.spawning_pool.spawn_entity();
+        game.state.spawning_pool.set(entity, components::Physics{coord: pos});
+        game.state.spawning_pool.set(entity, components::Flags{block_sight: false, solid});
+        if creature {
+            game.state.spawning_pool.set(entity, components::Stats::new(1, 1, 1));
+        }
+        game.state.spatial_table.reset(&game.state.spawning_pool);
+    }
+
+    #[test]
+    fn test_walk_cost_of_what_stands_on_a_tile() {
+        let (mut game, _, next) = new_game();
+        let player = game.state.player;
+        assert_eq!(walk_cost(player, &game.state)(next), Some(1));
+
+        add_entity(&mut game, next, false, false);
+        assert_eq!(walk_cost(player, &game.state)(next), Some(1));
+
+        add_entity(&mut game, next, true, true);
+        assert_eq!(walk_cost(player, &game.state)(next), Some(1 + CROWD_COST));
+
+        add_entity(&mut game, next, true, false);
+        assert_eq!(walk_cost(player, &game.state)(next), None);
+    }
+
+    #[test]
+    fn test_walk_cost_of_runes() {
+        let (mut game, _, next) = new_game();
+        let player = game.state.player;
+        actions::write_rune_at("stun", next, Faction::Enemy, &mut game.state.spawning_pool);
+        game.state.spatial_table.reset(&game.state.spawning_pool);
+        game.state.spawning_pool.get_mut::<components::MapMemory>(player).unwrap().reset();
+        assert_eq!(walk_cost(player, &game.state)(next), Some(1));
+
+        // still avoided once out of sight
+        game.state.spawning_pool.get_mut::<components::MapMemory>(player).unwrap().explore(next.x, next.y);
+        assert_eq!(walk_cost(player, &game.state)(next), Some(1 + RUNE_COST));
+
+        // its own are known without seeing them
+        game.state.spawning_pool.get_mut::<components::MapMemory>(player).unwrap().reset();
+        actions::write_rune_at("stun", next, Faction::Player, &mut game.state.spawning_pool);
+        game.state.spatial_table.reset(&game.state.spawning_pool);
+        assert_eq!(walk_cost(player, &game.state)(next), Some(1 + RUNE_COST));
+    }
+
+    #[test]
+    fn test_find_path() {
+        let (game, start, next) = new_game();
+        let cost = walk_cost(game.state.player, &game.state);
+        assert_eq!(find_path(start, next, &game.state.map, &cost), Ok(vec![next]));
+        assert_eq!(find_path(start, start, &game.state.map, &cost), Err(PathError::AtGoal));
+        let outside = Point::new(-1, 0);
+        assert_eq!(find_path(start, outside, &game.state.map, &cost), Err(PathError::OutOfBounds(outside)));
+        assert_eq!(find_path(start, next, &game.state.map, |_| None), Ok(vec![next]));
+        let far = start + Point::new(2, 0);
+        assert_eq!(find_path(start, far, &game.state.map, |_| None), Err(PathError::NoPath));
+    }
 }
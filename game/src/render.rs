@@ -10,6 +10,7 @@ use crate::consts::*;
 use crate::utils;
 use crate::game::*;
 use crate::components;
+use wily::events::{GameEvent, EventListener};
 
 pub struct Tcod {
     pub root: Root,
@@ -31,6 +32,36 @@ impl Tcod {
     }
 }
 
+impl EventListener for Tcod {
+    fn on_event(&mut self, event: &GameEvent, state: &GameState) {
+        match *event {
+            GameEvent::LightningStruck{target} => {
+                if let Some(pos) = utils::get_position(target, &state.spawning_pool) {
+                    if let Some(glyph) = utils::get_glyph(target, &state.spawning_pool) {
+                        self.add_animation(Animation::new(
+                            AnimationAnchor::Position{point: pos},
+                            200.0, // time
+                            Some(2000.0), // duration
+                            vec![None, Some((glyph, colors::LIGHT_SKY))]
+                        ));
+                    }
+                }
+            },
+            GameEvent::EntityDamaged{target, ..} => {
+                if let Some(glyph) = utils::get_glyph(target, &state.spawning_pool) {
+                    self.add_animation(Animation::new(
+                        AnimationAnchor::Entity{entity: target},
+                        0.0, // time
+                        Some(300.0), // duration
+                        vec![Some((glyph, colors::RED))]
+                    ));
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum AnimationAnchor {
     Entity{entity: EntityId},
@@ -127,7 +158,7 @@ fn render_messages(con: &mut Offscreen, game_state: &GameState) {
     for &(ref msg, color) in game_state.messages.iter().rev() {
         let msg_height = con.get_height_rect(0, y - 1, MESSAGES_WIDTH - 2, 0, msg);
         y -= msg_height;
-        con.set_default_foreground(color);
+        con.set_default_foreground(tcod_color(color));
         con.print_rect(1, y, MESSAGES_WIDTH - 2, 0, msg);
         if y <= 1 {
             break;
@@ -140,7 +171,7 @@ struct EntityRendingInfo {
     animate_effect: Option<components::Effect>,
     pos: Point,
     glyph: char,
-    color: colors::Color,
+    color: geo::colors::Color,
     solid: bool,
     always_display: bool,
     visible: bool,
@@ -197,10 +228,12 @@ fn render_entities(con: &mut Offscreen, memory: &components::MapMemory, game_sta
     });
     for draw in to_draw {
         status_animation(draw.id, draw.animate_effect, status_animations);
-        let col = if omnipotent || draw.visible {
-            draw.color
+        let col = if draw.visible {
+            tcod_color(game_state.light.get(draw.pos).tint(draw.color))
+        } else if omnipotent {
+            tcod_color(draw.color)
         } else {
-            shade_color(draw.color)
+            shade_color(tcod_color(draw.color))
         };
         con.set_default_foreground(col);
         con.put_char(draw.pos.x, draw.pos.y, draw.glyph, BackgroundFlag::None);
@@ -229,6 +262,11 @@ fn shade_color(col: colors::Color) -> colors::Color {
     col * colors::DARK_GREY
 }
 
+// The game core keeps its own colors, tcod only sees them when drawing.
+pub fn tcod_color(color: geo::colors::Color) -> colors::Color {
+    colors::Color{r: color.r, g: color.g, b: color.b}
+}
+
 fn render_map(con: &mut Offscreen, memory: &components::MapMemory, game_state: &GameState, omnipotent: bool) {
     con.clear();
     for x in 0..game_state.map.dimensions.x {
@@ -239,11 +277,14 @@ fn render_map(con: &mut Offscreen, memory: &components::MapMemory, game_state: &
             let explored = memory.is_explored(x, y);
 
             if in_view || explored {
-                let (glyph, foreground_color, background_color) = cell.get_render_info();
-                let (foreground, background)= if omnipotent || in_view {
-                    (foreground_color, background_color)
+                let (glyph, foreground_color, background_color) = cell.get_render_info(&game_state.map.palette);
+                let light = game_state.light.get(Point::new(x, y));
+                let (foreground, background)= if memory.is_visible(x, y) {
+                    (tcod_color(light.tint(foreground_color)), tcod_color(light.tint(background_color)))
+                } else if omnipotent {
+                    (tcod_color(foreground_color), tcod_color(background_color))
                 } else {
-                    (shade_color(foreground_color), shade_color(background_color))
+                    (shade_color(tcod_color(foreground_color)), shade_color(tcod_color(background_color)))
                 };
                 con.set_default_foreground(foreground);
                 con.put_char(x, y, glyph, BackgroundFlag::None);
@@ -311,12 +352,16 @@ fn render_stats_panel(panel: &mut Offscreen, stats: &components::Stats, spell_bo
 
     let mut y = 9;
     for spell in &spell_book.spells {
+        let name = match game_state.data.spell(spell) {
+            Some(spell) => spell.name,
+            None => spell.to_string()
+        };
         panel.print_ex(
             1,
             y,
             BackgroundFlag::None,
             TextAlignment::Left,
-            &format!("{} - Carve {}", y - 8, spell)
+            &format!("{} - Carve {}", y - 8, name)
         );
         y += 1;
     }
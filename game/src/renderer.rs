@@ -0,0 +1,11 @@
+use crate::game::*;
+use crate::events::{GameEvent, EventListener};
+
+/// Renderer that throws everything away, for running games without a display.
+/// Frontends pass their own listener to `game_tick` to show the visual side
+/// effects of a tick, the core never draws anything itself.
+pub struct HeadlessRenderer;
+
+impl EventListener for HeadlessRenderer {
+    fn on_event(&mut self, _event: &GameEvent, _state: &GameState) {}
+}
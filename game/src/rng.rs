@@ -0,0 +1,113 @@
+use std::fmt;
+use std::num::ParseIntError;
+
+use rand::{self, Rng, SeedableRng};
+
+pub type Seed = [u32; 4];
+
+// Xorshift128, same algorithm as rand's XorShiftRng, but with its state
+// exposed to serde so a saved game keeps rolling the same numbers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32
+}
+
+impl GameRng {
+    pub fn new(seed: Seed) -> GameRng {
+        let mut rng = GameRng { x: 0, y: 0, z: 0, w: 0 };
+        rng.reseed(seed);
+        rng
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w = self.w ^ (self.w >> 19) ^ (t ^ (t >> 8));
+        self.w
+    }
+}
+
+impl SeedableRng<Seed> for GameRng {
+    fn reseed(&mut self, seed: Seed) {
+        // xorshift gets stuck on an all zero state
+        let seed = if seed.iter().all(|s| *s == 0) {
+            [0x193a_6754, 0xa8a7_d469, 0x9783_0e05, 0x113b_a7bb]
+        } else {
+            seed
+        };
+        self.x = seed[0];
+        self.y = seed[1];
+        self.z = seed[2];
+        self.w = seed[3];
+    }
+
+    fn from_seed(seed: Seed) -> GameRng {
+        GameRng::new(seed)
+    }
+}
+
+pub fn random_seed() -> Seed {
+    let mut rng = rand::thread_rng();
+    [rng.gen(), rng.gen(), rng.gen(), rng.gen()]
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SeedError {
+    WrongLength(usize),
+    NotANumber(ParseIntError)
+}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SeedError::WrongLength(parts) => write!(f, "expected four comma separated numbers, got {}", parts),
+            SeedError::NotANumber(ref err) => write!(f, "expected four comma separated numbers, {}", err)
+        }
+    }
+}
+
+pub fn parse_seed(input: &str) -> Result<Seed, SeedError> {
+    let parts: Vec<&str> = input.split(',').collect();
+    if parts.len() != 4 {
+        return Err(SeedError::WrongLength(parts.len()));
+    }
+    let mut seed = [0; 4];
+    for (s, part) in seed.iter_mut().zip(parts) {
+        *s = part.trim().parse().map_err(SeedError::NotANumber)?;
+    }
+    Ok(seed)
+}
+
+pub fn format_seed(seed: &Seed) -> String {
+    format!("{},{},{},{}", seed[0], seed[1], seed[2], seed[3])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::*;
+
+    #[test]
+    fn test_seeds_are_four_numbers() {
+        assert_eq!(parse_seed("1,2,3,4"), Ok([1, 2, 3, 4]));
+        assert_eq!(parse_seed(" 1, 2 ,3,4294967295 "), Ok([1, 2, 3, 4294967295]));
+        assert_eq!(parse_seed("1,2,3"), Err(SeedError::WrongLength(3)));
+        assert_eq!(parse_seed("1,2,3,4,5"), Err(SeedError::WrongLength(5)));
+        assert_eq!(parse_seed(""), Err(SeedError::WrongLength(1)));
+        assert!(match parse_seed("1,2,three,4") { Err(SeedError::NotANumber(_)) => true, _ => false });
+        assert!(match parse_seed("1,2,-3,4") { Err(SeedError::NotANumber(_)) => true, _ => false });
+    }
+
+    #[test]
+    fn test_formatted_seeds_parse_back() {
+        let seed = [0, 17, 4294967295, 123456];
+        assert_eq!(format_seed(&seed), "0,17,4294967295,123456");
+        assert_eq!(parse_seed(&format_seed(&seed)), Ok(seed));
+    }
+}
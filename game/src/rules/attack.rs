@@ -5,6 +5,8 @@ use crate::game::*;
 use crate::actions::*;
 use crate::components;
 
+const SNEAK_ATTACK_MULTIPLIER: i32 = 2;
+
 pub fn attack(action: &mut Action, state: &GameState, _rejected_actions: &mut Vec<Action>, reaction_actions: &mut Vec<Action>) -> ActionStatus {
     if let Command::AttackEntity{bonus_strength, bonus_defense} = action.command {
         if let Some(actor) = action.actor {
@@ -21,7 +23,10 @@ pub fn attack(action: &mut Action, state: &GameState, _rejected_actions: &mut Ve
                 Some(stats) => stats.defense,
                 None => 0 
             };
-            let attack_strength = bonus_strength + strength;
+            // sleeping targets don't see the blow coming
+            let sneak_attack = state.spawning_pool.get::<components::AiMemory>(target_id)
+                .map_or(false, |memory| memory.awareness == components::Awareness::Unaware);
+            let attack_strength = bonus_strength + if sneak_attack { strength * SNEAK_ATTACK_MULTIPLIER } else { strength };
 
             let damage = max(1, attack_strength - target_defense);
 
@@ -1,5 +1,5 @@
 use crate::rules::definitions::*;
-use crate::map::*;
+use crate::map::TileType;
 use crate::actions::*;
 use crate::game::*;
 use crate::components;
@@ -24,7 +24,10 @@ pub fn collision(action: &mut Action, game_state: &GameState, rejected_actions:
 
                 let new_pos = pos + dir;
                 let cell = game_state.map.get_cell(new_pos.x, new_pos.y);
-                if cell.tile_type == TileType::Wall {
+                if cell.blocks_movement {
+                    return ActionStatus::Reject;
+                }
+                if cell.tile_type == TileType::DeepWater && game_state.spawning_pool.get::<components::Swimmer>(actor).is_none() {
                     return ActionStatus::Reject;
                 }
 
@@ -77,3 +80,35 @@ pub fn collision(action: &mut Action, game_state: &GameState, rejected_actions:
         _ => ActionStatus::Accept
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use geo::Point;
+    use crate::rules::collision::*;
+    use crate::map;
+    use crate::testing;
+
+    fn walk(actor: spawning_pool::EntityId, dir: Point, state: &GameState) -> ActionStatus {
+        let mut action = Action::new(Some(actor), None, Command::WalkDirection{dir});
+        collision(&mut action, state, &mut vec![], &mut vec![])
+    }
+
+    #[test]
+    fn test_only_swimmers_enter_deep_water() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let pos = get_entity_position(player, &game.state).unwrap();
+        let dir = [Point::new(1, 0), Point::new(-1, 0), Point::new(0, 1), Point::new(0, -1)]
+            .iter()
+            .cloned()
+            .find(|dir| map::can_walk(pos + *dir, &game.state.spatial_table, &game.state.map))
+            .unwrap();
+        assert_eq!(walk(player, dir, &game.state), ActionStatus::Accept);
+
+        testing::set_tile(&mut game.state, pos + dir, TileType::DeepWater);
+        assert_eq!(walk(player, dir, &game.state), ActionStatus::Reject);
+
+        game.state.spawning_pool.set(player, components::Swimmer);
+        assert_eq!(walk(player, dir, &game.state), ActionStatus::Accept);
+    }
+}
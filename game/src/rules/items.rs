@@ -2,7 +2,6 @@ use crate::utils;
 use crate::actions::*;
 use crate::game::*;
 use crate::components;
-use crate::spells;
 
 use crate::rules::definitions::*;
 
@@ -19,6 +18,10 @@ pub fn use_item(action: &mut Action, game_state: &GameState, _rejected_actions:
                 if let Some(on_use) = get_callback(action, &game_state.spawning_pool) {
                     match on_use {
                         components::OnUseCallback::Spell(spell) => {
+                            let spell = match game_state.data.spell(&spell) {
+                                Some(spell) => spell,
+                                None => return ActionStatus::Reject
+                            };
                             reaction_actions.push(Action::new(
                                 action.actor,
                                 None,
@@ -27,7 +30,7 @@ pub fn use_item(action: &mut Action, game_state: &GameState, _rejected_actions:
                             let mut act = Action::new(
                                 action.actor,
                                 None,
-                                Command::CastSpell{spell: spells::Spell::create(spell)}
+                                Command::CastSpell{spell}
                             );
                             act.set_time = Some(50);
                             if item.kind == components::ItemKind::Potion {
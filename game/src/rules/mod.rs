@@ -1,5 +1,5 @@
 use crate::actions::*;
-use crate::*;
+use crate::game::*;
 
 mod items;
 mod spells;
@@ -7,32 +7,26 @@ mod definitions;
 mod collision;
 mod attack;
 mod trigger;
+mod rule_set;
 
 pub use self::definitions::*;
+pub use self::rule_set::*;
 
-pub fn apply_rules(action: &mut Action, game_state: &GameState, rejected_actions: &mut Vec<Action>, reaction_actions: &mut Vec<Action>) -> ActionStatus {
-    let rules = [
-        spells::validate_spell,
-        items::use_item,
-        items::apply_equipment_bonus,
-        collision::collision,
-        spells::cast_spell,
-        spells::lightning_strike,
-        attack::attack,
-        attack::take_damage,
-        trigger::trigger
-    ];
-    if action.command == Command::Abort {
-        return ActionStatus::Reject;
-    }
-    let mut action_status = ActionStatus::Accept;  
-    for rule in &rules {
-        action_status = rule(action, game_state, rejected_actions, reaction_actions);
-        if action_status == ActionStatus::Reject {
-            break;
-        }
-    }
-    action_status
+pub fn standard_rules() -> RuleSet {
+    let mut rules = RuleSet::new();
+    rules.register("abort", 1000, &[CommandKind::Abort], abort);
+    rules.register("validate_spell", 100, &[CommandKind::CastSpell], spells::validate_spell);
+    rules.register("use_item", 90, &[CommandKind::UseItem], items::use_item);
+    rules.register("equipment_bonus", 80, &[CommandKind::AttackEntity], items::apply_equipment_bonus);
+    rules.register("collision", 70, &[CommandKind::WalkDirection], collision::collision);
+    rules.register("cast_spell", 60, &[CommandKind::CastSpell], spells::cast_spell);
+    rules.register("lightning_strike", 50, &[CommandKind::LightningStrike], spells::lightning_strike);
+    rules.register("attack", 40, &[CommandKind::AttackEntity], attack::attack);
+    rules.register("take_damage", 30, &[CommandKind::TakeDamage], attack::take_damage);
+    rules.register("trigger", 20, &[CommandKind::WalkDirection], trigger::trigger);
+    rules
 }
 
-
+fn abort(_action: &mut Action, _game_state: &GameState, _rejected_actions: &mut Vec<Action>, _reaction_actions: &mut Vec<Action>) -> ActionStatus {
+    ActionStatus::Reject
+}
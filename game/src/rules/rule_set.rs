@@ -0,0 +1,195 @@
+use crate::rules::definitions::*;
+use crate::actions::*;
+use crate::game::*;
+
+pub type RuleFn = fn(&mut Action, &GameState, &mut Vec<Action>, &mut Vec<Action>) -> ActionStatus;
+
+pub struct Rule {
+    pub name: String,
+    pub priority: i32,
+    pub commands: Vec<CommandKind>,
+    pub enabled: bool,
+    rule: RuleFn
+}
+
+impl Rule {
+    // a rule registered without any commands is run for every action
+    pub fn applies_to(&self, command: &Command) -> bool {
+        self.commands.is_empty() || self.commands.contains(&command.kind())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub rule: String,
+    pub action: Action
+}
+
+#[derive(Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    last_rejection: Option<Rejection>
+}
+
+impl RuleSet {
+    pub fn new() -> RuleSet {
+        RuleSet {
+            rules: vec![],
+            last_rejection: None
+        }
+    }
+
+    pub fn register(&mut self, name: &str, priority: i32, commands: &[CommandKind], rule: RuleFn) {
+        self.rules.retain(|r| r.name != name);
+        let index = self.rules
+            .iter()
+            .position(|r| r.priority < priority)
+            .unwrap_or_else(|| self.rules.len());
+        self.rules.insert(index, Rule {
+            name: name.to_string(),
+            priority,
+            commands: commands.to_vec(),
+            enabled: true,
+            rule
+        });
+    }
+
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let count = self.rules.len();
+        self.rules.retain(|r| r.name != name);
+        count != self.rules.len()
+    }
+
+    pub fn enable(&mut self, name: &str) -> bool {
+        self.set_enabled(name, true)
+    }
+
+    pub fn disable(&mut self, name: &str) -> bool {
+        self.set_enabled(name, false)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.rules.iter().any(|r| r.name == name && r.enabled)
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn last_rejection(&self) -> Option<&Rejection> {
+        self.last_rejection.as_ref()
+    }
+
+    pub fn apply(&mut self, action: &mut Action, game_state: &GameState, rejected_actions: &mut Vec<Action>, reaction_actions: &mut Vec<Action>) -> ActionStatus {
+        for rule in &self.rules {
+            if !rule.enabled || !rule.applies_to(&action.command) {
+                continue;
+            }
+            if (rule.rule)(action, game_state, rejected_actions, reaction_actions) == ActionStatus::Reject {
+                self.last_rejection = Some(Rejection {
+                    rule: rule.name.clone(),
+                    action: action.clone()
+                });
+                return ActionStatus::Reject;
+            }
+        }
+        ActionStatus::Accept
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.rules.iter_mut().find(|r| r.name == name) {
+            Some(rule) => {
+                rule.enabled = enabled;
+                true
+            },
+            None => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rules::*;
+    use crate::testing;
+    use crate::events::GameEvent;
+    use crate::renderer::HeadlessRenderer;
+
+    fn accept(_action: &mut Action, _game_state: &GameState, _rejected_actions: &mut Vec<Action>, _reaction_actions: &mut Vec<Action>) -> ActionStatus {
+        ActionStatus::Accept
+    }
+
+    fn reject(_action: &mut Action, _game_state: &GameState, _rejected_actions: &mut Vec<Action>, _reaction_actions: &mut Vec<Action>) -> ActionStatus {
+        ActionStatus::Reject
+    }
+
+    fn names(rules: &RuleSet) -> Vec<&str> {
+        rules.rules().iter().map(|rule| rule.name.as_str()).collect()
+    }
+
+    fn apply(rules: &mut RuleSet, command: Command, state: &GameState) -> ActionStatus {
+        let mut action = Action::new(Some(state.player), None, command);
+        rules.apply(&mut action, state, &mut vec![], &mut vec![])
+    }
+
+    #[test]
+    fn test_rules_run_by_priority() {
+        let mut rules = RuleSet::new();
+        rules.register("low", 10, &[], accept);
+        rules.register("high", 30, &[], accept);
+        rules.register("middle", 20, &[], accept);
+        assert_eq!(names(&rules), vec!["high", "middle", "low"]);
+
+        // registering a name again replaces the rule
+        rules.register("low", 40, &[], accept);
+        assert_eq!(names(&rules), vec!["low", "high", "middle"]);
+        assert!(rules.unregister("middle"));
+        assert!(!rules.unregister("middle"));
+        assert_eq!(names(&rules), vec!["low", "high"]);
+    }
+
+    #[test]
+    fn test_rules_are_enabled_by_name() {
+        let game = testing::new_game([1, 2, 3, 4]);
+        let mut rules = RuleSet::new();
+        rules.register("no_waiting", 10, &[CommandKind::Wait], reject);
+        assert!(rules.is_enabled("no_waiting"));
+        assert_eq!(apply(&mut rules, Command::Wait, &game.state), ActionStatus::Reject);
+        assert_eq!(apply(&mut rules, Command::Win, &game.state), ActionStatus::Accept);
+
+        assert!(rules.disable("no_waiting"));
+        assert!(!rules.is_enabled("no_waiting"));
+        assert_eq!(apply(&mut rules, Command::Wait, &game.state), ActionStatus::Accept);
+        assert!(rules.enable("no_waiting"));
+        assert_eq!(apply(&mut rules, Command::Wait, &game.state), ActionStatus::Reject);
+        assert!(!rules.disable("missing"));
+    }
+
+    #[test]
+    fn test_rejections_name_the_rule() {
+        let game = testing::new_game([1, 2, 3, 4]);
+        let mut rules = RuleSet::new();
+        rules.register("first", 20, &[], accept);
+        rules.register("second", 10, &[], reject);
+        rules.register("third", 5, &[], reject);
+        assert!(rules.last_rejection().is_none());
+        assert_eq!(apply(&mut rules, Command::Wait, &game.state), ActionStatus::Reject);
+        let rejection = rules.last_rejection().unwrap();
+        assert_eq!(rejection.rule, "second");
+        assert_eq!(rejection.action.command, Command::Wait);
+    }
+
+    // Rejections reach the event listeners, which the debug view shows.
+    #[test]
+    fn test_rejections_are_sent_as_events() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        game.rules.register("no_waiting", 10, &[CommandKind::Wait], reject);
+        let action = Action::new(Some(game.state.player), None, Command::Wait);
+        game.game_tick(vec![action], &mut HeadlessRenderer);
+        game.game_tick(vec![], &mut HeadlessRenderer);
+        let rejected = game.take_events().into_iter().any(|event| match event {
+            GameEvent::ActionRejected{rule, action} => rule == "no_waiting" && action.command == Command::Wait,
+            _ => false
+        });
+        assert!(rejected);
+    }
+}
@@ -1,4 +1,4 @@
-use tcod::line::{Line};
+use geo::Line;
 use geo::*;
 use spawning_pool::{EntityId};
 use crate::utils;
@@ -115,107 +115,50 @@ fn cast(spell: &Spell, caster: Option<EntityId>, target: Option<ActionTarget>, s
             }
         }
     };
-    if spell_target.is_none() {
-        return false;
-    }
-    match spell.kind {
-        Spells::RayOfFrost => {
-            let targets = match spell_target {
-                Some(SpellTarget::Entities(ids)) => ids,
-                _ => return false
-            };
-            for target in &targets {
-                reaction_actions.push(Action::new(
-                    caster,
-                    Some(ActionTarget::Entity(*target)),
-                    Command::TakeDamage{damage: spell.power}
-                ));
-                reaction_actions.push(Action::new(
-                    caster,
-                    Some(ActionTarget::Entity(*target)),
-                    Command::Slow
-                ));
-            }
-        },
-        Spells::Stun => {
-            let target = match spell_target {
-                Some(SpellTarget::Entity(id)) => id,
-                _ => return false
+    let (targets, position) = match spell_target {
+        Some(SpellTarget::Entity(id)) => (vec![id], get_entity_position(id, state)),
+        Some(SpellTarget::Entities(ids)) => (ids, None),
+        Some(SpellTarget::Position(pos)) => (vec![], Some(pos)),
+        None => return false
+    };
+    let mut reactions = vec![];
+    for target in &targets {
+        for effect in &spell.effects {
+            let command = match *effect {
+                SpellEffect::Damage(damage) => Command::TakeDamage{damage},
+                SpellEffect::Lightning(damage) => Command::LightningStrike{damage},
+                SpellEffect::Heal(amount) => Command::Heal{amount},
+                SpellEffect::Slow => Command::Slow,
+                SpellEffect::Stun => Command::Stun,
+                SpellEffect::Confuse => Command::Confuse,
+                SpellEffect::GainPoint => Command::GainPoint,
+                SpellEffect::Fog | SpellEffect::Light(_) => continue
             };
-            reaction_actions.push(Action::new(
+            reactions.push(Action::new(
                 caster,
-                Some(ActionTarget::Entity(target)),
-                Command::Stun
+                Some(ActionTarget::Entity(*target)),
+                command
             ));
-        },
-        Spells::Fog => {
-            let target = match spell_target {
-                Some(SpellTarget::Position(pos)) => pos,
-                _ => return false
+        }
+    }
+    if let Some(pos) = position {
+        for effect in &spell.effects {
+            let command = match *effect {
+                SpellEffect::Fog => Command::SpawnFog{pos},
+                SpellEffect::Light(radius) => Command::SpawnLight{pos, radius},
+                _ => continue
             };
-            reaction_actions.push(Action::new(
+            reactions.push(Action::new(
                 caster,
                 None,
-                Command::SpawnFog{pos: target}
-            ));
-        },
-        Spells::MagicMissile => {
-            let target = match spell_target {
-                Some(SpellTarget::Entity(id)) => id,
-                _ => return false
-            };
-            reaction_actions.push(Action::new(
-                caster,
-                Some(ActionTarget::Entity(target)),
-                Command::TakeDamage{damage: spell.power}
-            ));
-        },
-        Spells::LightningStrike => {
-            let target = match spell_target {
-                Some(SpellTarget::Entity(id)) => id,
-                _ => return false
-            };
-            reaction_actions.push(Action::new(
-                caster,
-                Some(ActionTarget::Entity(target)),
-                Command::LightningStrike{damage: spell.power}
-            ));
-        },
-        Spells::Confusion => {
-            let target = match spell_target {
-                Some(SpellTarget::Entity(id)) => id,
-                _ => return false
-            };
-            reaction_actions.push(Action::new(
-                caster,
-                Some(ActionTarget::Entity(target)),
-                Command::Confuse
-            ));
-        },
-        Spells::Heal => {
-            let target = match spell_target {
-                Some(SpellTarget::Entity(id)) => id,
-                _ => return false
-            };
-            reaction_actions.push(Action::new(
-                caster,
-                Some(ActionTarget::Entity(target)),
-                Command::Heal{amount: spell.power}
-            ));
-        },
-        Spells::Experience => {
-            let target = match spell_target {
-                Some(SpellTarget::Entity(id)) => id,
-                _ => return false
-            };
-            reaction_actions.push(Action::new(
-                caster,
-                Some(ActionTarget::Entity(target)),
-                Command::GainPoint
+                command
             ));
         }
     }
-    return true;
+    // reactions are taken from the back, so the first effect goes last
+    let cast = !reactions.is_empty();
+    reaction_actions.extend(reactions.into_iter().rev());
+    cast
 }
 
 fn get_closest_target(caster: EntityId, state: &GameState) -> Option<EntityId> {
@@ -283,3 +226,35 @@ fn get_ray_targets(caster: EntityId, end: Point, state: &GameState) -> Vec<Entit
     }
     entities
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rules::spells::*;
+    use crate::testing;
+    use crate::events::GameEvent;
+    use crate::renderer::HeadlessRenderer;
+
+    #[test]
+    fn test_effects_are_applied_in_order() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let spell = Spell {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            power: 1,
+            range: 1,
+            target: SpellTargetType::Entity,
+            targeting: SpellTargeting::Caster,
+            effects: vec![SpellEffect::Confuse, SpellEffect::Damage(1), SpellEffect::Slow]
+        };
+        let action = Action::new(Some(player), Some(ActionTarget::Entity(player)), Command::CastSpell{spell});
+        game.game_tick(vec![action], &mut HeadlessRenderer);
+        let effects: Vec<&str> = game.take_events().iter().filter_map(|event| match *event {
+            GameEvent::EffectApplied{effect: components::Effect::Confuse, ..} => Some("confuse"),
+            GameEvent::EntityDamaged{..} => Some("damage"),
+            GameEvent::EffectApplied{effect: components::Effect::Slow, ..} => Some("slow"),
+            _ => None
+        }).collect();
+        assert_eq!(effects, vec!["confuse", "damage", "slow"]);
+    }
+}
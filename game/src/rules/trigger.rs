@@ -32,7 +32,10 @@ pub fn trigger(action: &mut Action, state: &GameState, _rejected_actions: &mut V
                             if let Some(ref on_trigger) = trigger.on_trigger {
                                 match on_trigger {
                                     components::OnTriggerCallback::Spell(spell) => {
-                                        let spl = spells::Spell::create(*spell);
+                                        let spl = match state.data.spell(spell) {
+                                            Some(spl) => spl,
+                                            None => continue
+                                        };
                                         let target = match spl.target {
                                             spells::SpellTargetType::Spot => {
                                                 if let Some(pos) = get_entity_position(actor, &state) {
@@ -1,9 +1,12 @@
 use serde_json;
 
+use std::rc::Rc;
 use std::io::{self, Read, Write};
 use std::fs::File;
 
 use crate::game::*;
+use crate::journal::Journal;
+use crate::data::GameData;
 
 pub fn save_game(game_state: &GameState) -> Result<(), io::Error> {
     let save_data = serde_json::to_string(&game_state).unwrap();
@@ -12,11 +15,26 @@ pub fn save_game(game_state: &GameState) -> Result<(), io::Error> {
     Ok(())
 }
 
-pub fn load_game() -> Result<GameState, io::Error> {
-    let mut data = String::new();
+pub fn load_game(data: Rc<GameData>) -> Result<GameState, io::Error> {
+    let mut save_data = String::new();
     let mut file = File::open("savegame")?;
-    file.read_to_string(&mut data)?;
-    let state: GameState = serde_json::from_str(&data)?;
+    file.read_to_string(&mut save_data)?;
+    let mut state: GameState = serde_json::from_str(&save_data)?;
+    state.data = data;
     Ok(state)
+}
+
+pub fn save_replay(journal: &Journal) -> Result<(), io::Error> {
+    let replay_data = serde_json::to_string(&journal).unwrap();
+    let mut file = File::create("replay")?;
+    file.write_all(replay_data.as_bytes())?;
+    Ok(())
+}
 
+pub fn load_replay(path: &str) -> Result<Journal, io::Error> {
+    let mut data = String::new();
+    let mut file = File::open(path)?;
+    file.read_to_string(&mut data)?;
+    let journal: Journal = serde_json::from_str(&data)?;
+    Ok(journal)
 }
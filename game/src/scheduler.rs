@@ -96,6 +96,27 @@ impl Scheduler {
         }
     }
 
+    // Moves the clock to `time`, keeping everyone's place in the queue.
+    pub fn shift_time(&mut self, time: i32) {
+        let elapsed = time - self.time;
+        let entries: Vec<ScheduledEntity> = self.heap
+            .drain()
+            .map(|mut se| {
+                se.time += elapsed;
+                se
+            })
+            .collect();
+        self.heap.extend(entries);
+        self.time = time;
+    }
+
+    // Everyone waiting for a turn with when they get it, in turn order.
+    pub fn queue(&self) -> Vec<(EntityId, i32)> {
+        let mut entries: Vec<&ScheduledEntity> = self.heap.iter().collect();
+        entries.sort_by_key(|se| (se.time, se.entry_id));
+        entries.iter().map(|se| (se.entity, se.time)).collect()
+    }
+
     pub fn get_current(&self) -> EntityId {
         self.current.unwrap()
     }
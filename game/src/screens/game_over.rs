@@ -1,7 +1,6 @@
-use tcod::console::*;
-use tcod::colors;
 use tcod::input::{KeyCode};
 use crate::screens::*;
+use crate::screens::utils::{get_summary};
 
 pub struct GameOverScreen { 
     exit: bool,
@@ -24,22 +23,12 @@ impl Screen for GameOverScreen {
         vec![]
     }
 
-    fn render(&mut self, _delta: f64, _sate: &mut GameState, _fov: &tcod::map::Map, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
-        let mut root = Offscreen::new(14, 5);
-        root.set_default_foreground(colors::WHITE);
-        root.print_rect_ex(
-            2,
-            2,
-            14,
-            5,
-            BackgroundFlag::None,
-            TextAlignment::Left,
-            "Game Over!"
-        );
+    fn render(&mut self, _delta: f64, state: &mut GameState, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+        let root = get_summary("Game Over!", &state.statistics);
         (ScreenResult::PassThrough, Some(ModularWindow{screen: root, alpha: 1.0, pos: ModularWindowPosition::Center}))
     }
 
-    fn tick(&mut self, _state: &mut GameState, _tcod: &mut render::Tcod, _actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, _state: &mut GameState, _actions: &mut Vec<Action>) -> ScreenResult {
         ScreenResult::PassThrough
     }
 
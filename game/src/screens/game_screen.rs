@@ -1,7 +1,8 @@
+use tcod::console::*;
+use tcod::colors;
 use tcod::input::{KeyCode};
 use std::rc::Rc;
 use std::cell::RefCell;
-use crate::spells;
 
 use crate::utils;
 use crate::components;
@@ -29,6 +30,7 @@ pub struct GameScreen {
     stats: Option<components::Stats>,
     spell_book: Option<components::SpellBook>,
     map_memory: Option<components::MapMemory>,
+    rejection: Option<String>,
     screens: Vec<ScreenPointer>,
     input_command: Option<InputCommand>
 }
@@ -44,6 +46,7 @@ impl GameScreen {
             stats: None,
             map_memory: None,
             spell_book: None,
+            rejection: None,
             input_command: None,
         }
     }
@@ -68,11 +71,19 @@ impl Screen for GameScreen {
         self.screens.drain(..).collect()
     }
 
-    fn render(&mut self, delta: f64, state: &mut GameState, _fov: &tcod::map::Map, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, delta: f64, state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         if let Some(ref stats) = self.stats {
             if let Some(ref memory) = self.map_memory {
                 if let Some(ref spell_book) = self.spell_book {
-                    let screen = render(tcod, &stats, &memory, &spell_book, state, self.omnipotent, delta);
+                    let mut screen = render(tcod, &stats, &memory, &spell_book, state, self.omnipotent, delta);
+                    // with everything shown, also show why the last thing
+                    // the player tried didn't happen
+                    if self.omnipotent {
+                        if let Some(ref rejection) = self.rejection {
+                            screen.set_default_foreground(colors::LIGHT_YELLOW);
+                            screen.print_ex(0, MAP_HEIGHT - 1, BackgroundFlag::None, TextAlignment::Left, rejection);
+                        }
+                    }
                     return (ScreenResult::Stop, Some(ModularWindow{screen, alpha: 1.0, pos: ModularWindowPosition::Position{point: (0, 0).into()}}));
                 }
             }
@@ -80,7 +91,7 @@ impl Screen for GameScreen {
         (ScreenResult::Stop, None)
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         match self.input_command {
             Some(InputCommand::GameCommand{ref command}) => {
                 actions.push(Action::new(
@@ -90,13 +101,13 @@ impl Screen for GameScreen {
                 ));
             },
             Some(InputCommand::SelfHeal) => {
-                actions.push(Action::new(
-                    Some(state.player),
-                    Some(ActionTarget::Entity(state.player)),
-                    Command::CastSpell{
-                        spell: spells::Spell::create(spells::Spells::Heal)
-                    }
-                ));
+                if let Some(spell) = state.data.spell("heal") {
+                    actions.push(Action::new(
+                        Some(state.player),
+                        Some(ActionTarget::Entity(state.player)),
+                        Command::CastSpell{spell}
+                    ));
+                }
             }
             Some(InputCommand::Quit) => {
                 self.exit = true;
@@ -168,6 +179,18 @@ impl Screen for GameScreen {
         }
     }
 
+    fn on_event(&mut self, event: &GameEvent, state: &GameState) {
+        match *event {
+            GameEvent::PointGained{entity} if entity == state.player => {
+                self.screens.push(Rc::new(RefCell::new(Box::new(LevelUpScreen::new()))));
+            },
+            GameEvent::ActionRejected{ref rule, ref action} if action.actor == Some(state.player) => {
+                self.rejection = Some(format!("{:?} rejected by {}", action.command.kind(), rule));
+            },
+            _ => {}
+        }
+    }
+
     fn handle_input(&mut self, input: &Input, _state: &mut GameState) -> ScreenResult {
         self.input_command = match input.key {
             Key { code: KeyCode::Escape, .. } => Some(InputCommand::Quit),  // exit game
@@ -224,7 +247,7 @@ impl Screen for GameScreen {
                     if let Some(num) = num {
                         if (num as usize) <= spell_book.spells.len() {
                              Some(InputCommand::GameCommand{command: Command::WriteRune{
-                                spell: spell_book.spells[(num - 1) as usize]
+                                spell: spell_book.spells[(num - 1) as usize].clone()
                             }})
                         } else {
                             None
@@ -249,24 +272,25 @@ fn tile_interaction(state: &GameState, actions: &mut Vec<Action>) {
     if let Some(pos) = utils::get_position(state.player, &state.spawning_pool) {
         if let Some(spatial_cell) = state.spatial_table.get(pos) {
             for entity in &spatial_cell.entities {
-                let glyph = utils::get_glyph(*entity, &state.spawning_pool);
-                if let Some(glyph) = glyph {
-                    if glyph == '<' {
-                        trigger_stair_interaction(state, actions);
-                    } else if glyph == 'X' {
-                        trigger_portal_interaction(state, actions);
-                    }
+                if let Some(stairs) = state.spawning_pool.get::<components::Stairs>(*entity) {
+                    trigger_stair_interaction(stairs.direction, state, actions);
+                } else if utils::get_glyph(*entity, &state.spawning_pool) == Some('X') {
+                    trigger_portal_interaction(state, actions);
                 }
             }
         }
     }
 }
 
-fn trigger_stair_interaction(state: &GameState, actions: &mut Vec<Action>) {
+fn trigger_stair_interaction(direction: components::StairsDirection, state: &GameState, actions: &mut Vec<Action>) {
+    let command = match direction {
+        components::StairsDirection::Down => Command::DescendStairs,
+        components::StairsDirection::Up => Command::AscendStairs
+    };
     actions.push(Action::new(
         Some(state.player),
         None,
-        Command::DescendStairs
+        command
     ));
 }
 
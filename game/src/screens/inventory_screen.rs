@@ -44,7 +44,7 @@ impl Screen for InventoryScreen {
         self.screens.drain(..).collect()
     }
 
-    fn render(&mut self, _delta: f64, state: &mut GameState, _fov: &tcod::map::Map, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, state: &mut GameState, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         let equipped: HashMap<EntityId, components::EquipmentSlot> = match state.spawning_pool.get::<components::Equipment>(state.player) {
             Some(equipment) => {
                 let mut tmp: HashMap<EntityId, components::EquipmentSlot> = Default::default();
@@ -93,7 +93,7 @@ impl Screen for InventoryScreen {
         (ScreenResult::PassThrough, Some(ModularWindow{screen: root, alpha: 0.7, pos: ModularWindowPosition::Center}))
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         if let Some(selected) = self.selected {
             match self.action {
                 InventoryAction::UseItem => {
@@ -31,7 +31,7 @@ impl Screen for LevelUpScreen {
         self.screens.drain(..).collect()
     }
 
-    fn render(&mut self, _delta: f64, _state: &mut GameState, _fov: &tcod::map::Map, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, _state: &mut GameState, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         let menu = get_menu(&["(s) Strength", "(d) Defense"]);
         let width = menu.width();
         let height = menu.height();
@@ -52,7 +52,7 @@ impl Screen for LevelUpScreen {
         (ScreenResult::PassThrough, Some(ModularWindow{screen: root, alpha: 0.7, pos: ModularWindowPosition::Center}))
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         match self.choice {
             Some(LevelUpChoice::Strength) => {
                 actions.push(Action::new(
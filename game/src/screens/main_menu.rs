@@ -1,3 +1,4 @@
+use std::rc::Rc;
 use tcod::console::*;
 use crate::components;
 use tcod::colors;
@@ -5,6 +6,7 @@ use tcod::input::{KeyCode};
 use crate::screens::*;
 use crate::screens::utils::{get_menu};
 use crate::save;
+use crate::data::GameData;
 
 pub struct MainMenuScreen { 
     exit: bool,
@@ -56,7 +58,7 @@ impl Screen for MainMenuScreen {
         }
     }
 
-    fn render(&mut self, _delta: f64, _sate: &mut GameState, _fov: &tcod::map::Map, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, _sate: &mut GameState, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         let mut root = Offscreen::new(SCREEN_WIDTH, SCREEN_HEIGHT);
         root.set_default_foreground(colors::WHITE);
         root.print_rect_ex(
@@ -77,7 +79,7 @@ impl Screen for MainMenuScreen {
         (ScreenResult::Stop, Some(ModularWindow{screen: root, alpha: 1.0, pos: ModularWindowPosition::Position{point: (0, 0).into()}}))
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         self.alive = if let Some(stats) = state.spawning_pool.get::<components::Stats>(state.player) {
             stats.health > 0
         } else {
@@ -132,8 +134,8 @@ fn save_game(state: &GameState) {
     let _ = save::save_game(&state);
 }
 
-pub fn load_game() -> GameState {
-    if let Ok(state) = save::load_game() {
+pub fn load_game(data: Rc<GameData>) -> GameState {
+    if let Ok(state) = save::load_game(data) {
         state
     } else {
         panic!("Could not load game");
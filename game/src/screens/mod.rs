@@ -10,6 +10,7 @@ use tcod::colors;
 use geo::*;
 use crate::consts::*;
 use crate::actions::*;
+use crate::events::GameEvent;
 use crate::*;
 
 pub mod game_screen;
@@ -20,6 +21,7 @@ mod win;
 mod utils;
 mod target;
 mod level_up;
+mod replay;
 mod spell_entity_target;
 mod spell_position_target;
 mod spell_ray_target;
@@ -34,6 +36,7 @@ pub use self::spell_position_target::SpellPositionTargetScreen;
 pub use self::spell_ray_target::SpellRayTargetScreen;
 pub use self::spell_projectile_target::SpellProjectileTargetScreen;
 pub use self::level_up::LevelUpScreen;
+pub use self::replay::ReplayScreen;
 
 pub struct Input {
     key: Key,
@@ -88,7 +91,7 @@ impl ScreenManager {
         }
     }
 
-    pub fn render(&mut self, delta: f64, state: &mut GameState, fov: &tcod::map::Map, tcod: &mut render::Tcod) {
+    pub fn render(&mut self, delta: f64, state: &mut GameState, tcod: &mut render::Tcod) {
         tcod.time += delta;
         // aim to keep rendering speed at 60 fps
         if tcod.time < tcod.prev_time + 16.7 {
@@ -97,7 +100,7 @@ impl ScreenManager {
         tcod.prev_time = tcod.time;
         let mut windows = vec![];
         for screen in &mut self.screens {
-            let (res, window) = screen.borrow_mut().render(delta, state, fov, tcod);
+            let (res, window) = screen.borrow_mut().render(delta, state, tcod);
             if let Some(window) = window {
                 windows.push(window);
             }
@@ -124,9 +127,9 @@ impl ScreenManager {
         tcod.root.flush();
     }
 
-    pub fn tick(&mut self, state: &mut GameState, tcod: &mut render::Tcod, actions: &mut Vec<Action>) {
+    pub fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) {
         for screen in &mut self.screens {
-            let res = screen.borrow_mut().tick(state, tcod, actions);
+            let res = screen.borrow_mut().tick(state, actions);
             if res == ScreenResult::Stop {
                 break;
             }
@@ -139,6 +142,14 @@ impl ScreenManager {
         }
     }
 
+    pub fn on_events(&mut self, events: &[GameEvent], state: &GameState) {
+        for event in events {
+            for screen in &mut self.screens {
+                screen.borrow_mut().on_event(event, state);
+            }
+        }
+    }
+
     pub fn handle_input(&mut self, state: &mut GameState) {
         let mut input = Input {
             key: Default::default(),
@@ -172,11 +183,12 @@ pub type ScreenPointer = Rc<RefCell<Box<Screen>>>;
 pub trait Screen {
     fn should_discard(&self, state: &mut GameState) -> bool;
     fn new_screens(&mut self, state: &mut GameState) -> Vec<ScreenPointer>;
-    fn render(&mut self, delta: f64, state: &mut GameState, fov: &tcod::map::Map, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>);
-    fn tick(&mut self, state: &mut GameState, tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult;
+    fn render(&mut self, delta: f64, state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>);
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult;
     fn handle_input(&mut self, input: &Input, state: &mut GameState) -> ScreenResult;
     fn add_callback(&mut self, _callback: Box<Fn()>) {}
     fn set_creator(&mut self, _screen: ScreenPointer) {}
     fn post_tick(&mut self, _state: &GameState) {}
+    fn on_event(&mut self, _event: &GameEvent, _state: &GameState) {}
     fn close(&mut self) { }
 }
@@ -0,0 +1,124 @@
+use tcod::console::*;
+use tcod::colors;
+use tcod::input::{KeyCode};
+
+use crate::journal::Journal;
+use crate::screens::*;
+use crate::screens::game_screen::GameScreen;
+
+const REPLAY_DELAYS: [f64; 5] = [25.0, 50.0, 100.0, 200.0, 400.0];
+
+pub struct ReplayScreen {
+    exit: bool,
+    paused: bool,
+    step: bool,
+    speed: usize,
+    elapsed: f64,
+    index: usize,
+    desync: Option<(i32, i32)>,
+    journal: Journal,
+    game_screen: GameScreen
+}
+
+impl ReplayScreen {
+    pub fn new(journal: Journal) -> Self {
+        ReplayScreen {
+            exit: false,
+            paused: false,
+            step: false,
+            speed: 2,
+            elapsed: 0.0,
+            index: 0,
+            desync: None,
+            journal,
+            game_screen: GameScreen::new()
+        }
+    }
+
+    fn status(&self) -> String {
+        if let Some((recorded, time)) = self.desync {
+            return format!("Replay {}/{} - out of sync, recorded at {} but the game is at {} - (esc) quit", self.index, self.journal.len(), recorded, time);
+        }
+        let state = if self.index >= self.journal.len() {
+            "done"
+        } else if self.paused {
+            "paused"
+        } else {
+            "playing"
+        };
+        format!("Replay {}/{} - {} - (space) pause (s) step (+/-) speed (esc) quit", self.index, self.journal.len(), state)
+    }
+}
+
+impl Screen for ReplayScreen {
+    fn should_discard(&self, _state: &mut GameState) -> bool {
+        self.exit
+    }
+
+    fn new_screens(&mut self, _state: &mut GameState) -> Vec<ScreenPointer> {
+        vec![]
+    }
+
+    fn render(&mut self, delta: f64, state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+        self.elapsed += delta;
+        let (res, mut window) = self.game_screen.render(delta, state, tcod);
+        if let Some(ref mut window) = window {
+            let color = if self.desync.is_some() { colors::LIGHT_RED } else { colors::LIGHT_YELLOW };
+            window.screen.set_default_foreground(color);
+            window.screen.print_ex(0, 0, BackgroundFlag::None, TextAlignment::Left, self.status());
+        }
+        (res, window)
+    }
+
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
+        if self.desync.is_some() || self.index >= self.journal.len() || state.scheduler.current != Some(state.player) {
+            return ScreenResult::Stop;
+        }
+        if self.paused && !self.step {
+            return ScreenResult::Stop;
+        }
+        if !self.step && self.elapsed < REPLAY_DELAYS[self.speed] {
+            return ScreenResult::Stop;
+        }
+        self.step = false;
+        self.elapsed = 0.0;
+
+        // the rest of the journal means nothing to a game that went its own
+        // way, so the replay stops there
+        let entry = &self.journal.entries[self.index];
+        if entry.time != state.scheduler.time {
+            self.desync = Some((entry.time, state.scheduler.time));
+            return ScreenResult::Stop;
+        }
+        actions.extend(entry.actions.iter().cloned());
+        self.index += 1;
+        ScreenResult::Stop
+    }
+
+    fn post_tick(&mut self, state: &GameState) {
+        self.game_screen.post_tick(state);
+    }
+
+    fn handle_input(&mut self, input: &Input, _state: &mut GameState) -> ScreenResult {
+        match input.key {
+            Key { code: KeyCode::Escape, .. } => {
+                self.exit = true;
+            },
+            Key { code: KeyCode::Text, printable: ' ', .. } => {
+                self.paused = !self.paused;
+            },
+            Key { code: KeyCode::Text, printable: 's', .. } => {
+                self.paused = true;
+                self.step = true;
+            },
+            Key { code: KeyCode::Text, printable: '+', .. } => {
+                self.speed = self.speed.saturating_sub(1);
+            },
+            Key { code: KeyCode::Text, printable: '-', .. } => {
+                self.speed = (self.speed + 1).min(REPLAY_DELAYS.len() - 1);
+            },
+            _ => {}
+        };
+        ScreenResult::Stop
+    }
+}
@@ -66,7 +66,7 @@ impl Screen for SpellEntityTargetScreen {
         vec![]
     }
 
-    fn render(&mut self, _delta: f64, _state: &mut GameState, _fov: &tcod::map::Map, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, _state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         if let Some(target) = self.target {
             tcod.add_animation(render::Animation::new(
                 render::AnimationAnchor::Position{point: target},
@@ -78,7 +78,7 @@ impl Screen for SpellEntityTargetScreen {
         (ScreenResult::PassThrough, None)
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         if let Some(selected) = self.selected {
             (self.callback)(selected, state, actions);
         }
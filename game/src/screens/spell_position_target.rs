@@ -43,7 +43,7 @@ impl Screen for SpellPositionTargetScreen {
         vec![]
     }
 
-    fn render(&mut self, _delta: f64, _state: &mut GameState, _fov: &tcod::map::Map, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, _state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         let color = if self.valid {
             tcod::colors::LIGHT_CYAN
         } else {
@@ -58,7 +58,7 @@ impl Screen for SpellPositionTargetScreen {
         (ScreenResult::PassThrough, None)
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         if let Some(memory) = state.spawning_pool.get::<components::MapMemory>(state.player) {
             self.valid = self.position_is_valid(memory, &state.map);
         } else {
@@ -88,7 +88,7 @@ impl Screen for SpellProjectileTargetScreen {
         vec![]
     }
 
-    fn render(&mut self, _delta: f64, _state: &mut GameState, _fov: &tcod::map::Map, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, _state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         for (pos, valid) in &self.ray {
             let color = if *valid {
                 tcod::colors::LIGHT_CYAN
@@ -105,7 +105,7 @@ impl Screen for SpellProjectileTargetScreen {
         (ScreenResult::PassThrough, None)
     }
 
-    fn tick(&mut self, state: &mut GameState, _tcod: &mut render::Tcod, actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, state: &mut GameState, actions: &mut Vec<Action>) -> ScreenResult {
         if self.ray.is_empty() {
             self.update_ray(state);
         }
@@ -57,7 +57,7 @@ impl Screen for TargetScreen {
         vec![]
     }
 
-    fn render(&mut self, _delta: f64, state: &mut GameState, _fov: &tcod::map::Map, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+    fn render(&mut self, _delta: f64, state: &mut GameState, tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
         if let Some(target_id) = self.target_id {
             let description = utils::describe_entity(target_id, &state.spawning_pool);
             let mut root = Offscreen::new(description.len() as i32 + 2, 3);
@@ -84,7 +84,7 @@ impl Screen for TargetScreen {
         }
     }
 
-    fn tick(&mut self, _state: &mut GameState, _tcod: &mut render::Tcod, _actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, _state: &mut GameState, _actions: &mut Vec<Action>) -> ScreenResult {
         ScreenResult::Stop
     }
 
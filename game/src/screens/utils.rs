@@ -1,6 +1,7 @@
 use std::cmp::{max};
 use tcod::console::*;
 use tcod::colors;
+use wily::statistics::Statistics;
 
 pub fn get_menu<T: AsRef<str>>(
     options: &[T]
@@ -31,3 +32,20 @@ pub fn get_menu<T: AsRef<str>>(
 
     window
 }
+
+// A window with a title and how the game went below it.
+pub fn get_summary(title: &str, statistics: &Statistics) -> Offscreen {
+    let lines = statistics.summary();
+    let width = lines.iter().map(|line| line.len() as i32).max().unwrap_or(0).max(title.len() as i32) + 4;
+    let height = lines.len() as i32 + 5;
+    let mut window = Offscreen::new(width, height);
+
+    window.set_default_foreground(colors::WHITE);
+    window.print_ex(2, 1, BackgroundFlag::None, TextAlignment::Left, title);
+    window.set_default_foreground(colors::LIGHT_GREY);
+    for (offset, line) in lines.iter().enumerate() {
+        window.print_ex(2, offset as i32 + 3, BackgroundFlag::None, TextAlignment::Left, line);
+    }
+
+    window
+}
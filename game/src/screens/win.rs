@@ -1,6 +1,5 @@
-use tcod::console::*;
-use tcod::colors;
 use crate::screens::*;
+use crate::screens::utils::{get_summary};
 
 pub type WinScreenCallback = FnMut(&mut Box<Screen>);
 
@@ -42,22 +41,12 @@ impl Screen for WinScreen {
         vec![]
     }
 
-    fn render(&mut self, _delta: f64, _sate: &mut GameState, _fov: &tcod::map::Map, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
-        let mut root = Offscreen::new(19, 5);
-        root.set_default_foreground(colors::WHITE);
-        root.print_rect_ex(
-            3,
-            2,
-            19,
-            5,
-            BackgroundFlag::None,
-            TextAlignment::Left,
-            "You have won!"
-        );
+    fn render(&mut self, _delta: f64, state: &mut GameState, _tcod: &mut render::Tcod) -> (ScreenResult, Option<ModularWindow>) {
+        let root = get_summary("You have won!", &state.statistics);
         (ScreenResult::PassThrough, Some(ModularWindow{screen: root, alpha: 1.0, pos: ModularWindowPosition::Center}))
     }
 
-    fn tick(&mut self, _state: &mut GameState, _tcod: &mut render::Tcod, _actions: &mut Vec<Action>) -> ScreenResult {
+    fn tick(&mut self, _state: &mut GameState, _actions: &mut Vec<Action>) -> ScreenResult {
         if self.exit {
             self.leave();
         }
@@ -1,5 +1,5 @@
 use spawning_pool::{EntityId};
-use std::collections::HashSet;
+use fnv::FnvHashSet;
 use std::cmp::{min, max};
 
 use crate::components;
@@ -7,7 +7,7 @@ use geo::*;
 
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct SpatialCell {
-    pub entities: HashSet<EntityId>,
+    pub entities: FnvHashSet<EntityId>,
     pub solid: bool,
     pub solid_count: i32,
     pub opaque: bool,
@@ -18,7 +18,6 @@ pub struct SpatialCell {
 pub struct SpatialTable {
     pub width: i32,
     pub height: i32,
-    pub dirty: bool,
     pub cells: Vec<SpatialCell>,
 }
 
@@ -31,7 +30,6 @@ impl SpatialTable {
         SpatialTable {
             width,
             height,
-            dirty: true,
             cells,
         }
     }
@@ -51,7 +49,6 @@ impl SpatialTable {
             let pos = physics.coord;
             self.cells[(pos.x + (pos.y * self.width)) as usize].add_entity(id, spawning_pool);
         }
-        self.dirty = true;
     }
 
     pub fn get<P: Into<Point>>(&self, pos: P) -> Option<&SpatialCell> {
@@ -111,7 +108,7 @@ impl SpatialTable {
 impl SpatialCell {
     pub fn new() -> Self {
         SpatialCell {
-            entities: HashSet::new(),
+            entities: FnvHashSet::default(),
             solid_count: 0,
             solid: false,
             opaque_count: 0,
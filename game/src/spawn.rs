@@ -0,0 +1,155 @@
+use rand::Rng;
+
+use crate::creatures::CreatureData;
+use crate::data::GameData;
+use crate::levels::LevelData;
+
+const MAX_GROUP_SIZE: usize = 6;
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RoomDifficulty {
+    Easy,
+    Normal,
+    Difficult
+}
+
+// The total cost of the creatures a room may hold. Deeper levels get a
+// larger budget on top of the room difficulty.
+pub fn budget(level: u32, difficulty: RoomDifficulty) -> u32 {
+    let base = match difficulty {
+        RoomDifficulty::Easy => 2,
+        RoomDifficulty::Normal => 3,
+        RoomDifficulty::Difficult => 5
+    };
+    base + level.saturating_sub(1)
+}
+
+// Builds a group of creatures for a room. The first pick may be a leader, in
+// which case the rest of the budget goes to its minions, otherwise the group
+// is filled from every creature that can appear on the level. A group never
+// holds more than one spell caster.
+pub fn generate_group<'a, T: Rng>(level: u32, config: &LevelData, budget: u32, data: &'a GameData, rng: &mut T) -> Vec<&'a CreatureData> {
+    let mut group: Vec<&CreatureData> = vec![];
+    let mut budget = budget;
+
+    let candidates: Vec<(&CreatureData, u32)> = data.creatures
+        .iter()
+        .filter(|creature| config.allows_monster(&creature.name))
+        .map(|creature| (creature, creature.spawn_weight(level)))
+        .filter(|(_, weight)| *weight > 0)
+        .collect();
+
+    let first = match pick(&candidates, budget, false, rng) {
+        Some(creature) => creature,
+        None => return group
+    };
+    budget -= first.cost;
+    group.push(first);
+
+    // minions follow their leader regardless of the depth they usually
+    // appear at, but only the leader is picked from the level candidates
+    let followers: Vec<(&CreatureData, u32)> = if first.minions.is_empty() {
+        candidates
+            .into_iter()
+            .filter(|(creature, _)| creature.minions.is_empty())
+            .collect()
+    } else {
+        first.minions
+            .iter()
+            .filter_map(|name| data.creature(name))
+            .map(|creature| (creature, creature.weight.max(1)))
+            .collect()
+    };
+
+    while group.len() < MAX_GROUP_SIZE {
+        let has_caster = group.iter().any(|creature| creature.is_caster());
+        match pick(&followers, budget, has_caster, rng) {
+            Some(creature) => {
+                budget -= creature.cost;
+                group.push(creature);
+            },
+            None => break
+        }
+    }
+    group
+}
+
+// A single creature for the level, used where a group would not fit.
+// Leaders are left out since they come with minions.
+pub fn pick_creature<'a, T: Rng>(level: u32, config: &LevelData, data: &'a GameData, rng: &mut T) -> Option<&'a CreatureData> {
+    let candidates: Vec<(&CreatureData, u32)> = data.creatures
+        .iter()
+        .filter(|creature| creature.minions.is_empty() && config.allows_monster(&creature.name))
+        .map(|creature| (creature, creature.spawn_weight(level)))
+        .filter(|(_, weight)| *weight > 0)
+        .collect();
+    pick(&candidates, u32::max_value(), false, rng)
+}
+
+fn pick<'a, T: Rng>(candidates: &[(&'a CreatureData, u32)], budget: u32, exclude_casters: bool, rng: &mut T) -> Option<&'a CreatureData> {
+    let affordable: Vec<(&CreatureData, u32)> = candidates
+        .iter()
+        .filter(|(creature, _)| creature.cost <= budget && !(exclude_casters && creature.is_caster()))
+        .cloned()
+        .collect();
+    let total: u32 = affordable.iter().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = rng.gen_range(0, total);
+    for (creature, weight) in affordable {
+        if roll < weight {
+            return Some(creature);
+        }
+        roll -= weight;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spawn::*;
+    use crate::data::TEST_DATA;
+    use crate::rng::GameRng;
+
+    const DIFFICULTIES: [RoomDifficulty; 3] = [RoomDifficulty::Easy, RoomDifficulty::Normal, RoomDifficulty::Difficult];
+
+    #[test]
+    fn test_groups_stay_within_their_rules() {
+        let data = GameData::load_from(TEST_DATA).unwrap();
+        let mut led_groups = 0;
+        for seed in 1..200 {
+            let mut rng = GameRng::new([seed, 2, 3, 4]);
+            for level in 1..6 {
+                for difficulty in DIFFICULTIES.iter() {
+                    let budget = budget(level, *difficulty);
+                    let group = generate_group(level, data.level(level), budget, &data, &mut rng);
+                    assert!(!group.is_empty());
+                    assert!(group.len() <= MAX_GROUP_SIZE);
+                    assert!(group.iter().map(|creature| creature.cost).sum::<u32>() <= budget);
+                    assert!(group.iter().filter(|creature| creature.is_caster()).count() <= 1);
+                    assert!(group[0].spawn_weight(level) > 0);
+
+                    let leader = group[0];
+                    for creature in &group[1..] {
+                        assert!(creature.minions.is_empty());
+                        if !leader.minions.is_empty() {
+                            assert!(leader.minions.contains(&creature.name));
+                        }
+                    }
+                    if !leader.minions.is_empty() && group.len() > 1 {
+                        led_groups += 1;
+                    }
+                }
+            }
+        }
+        assert!(led_groups > 0);
+    }
+
+    #[test]
+    fn test_no_group_without_a_budget() {
+        let data = GameData::load_from(TEST_DATA).unwrap();
+        let mut rng = GameRng::new([1, 2, 3, 4]);
+        assert!(generate_group(1, data.level(1), 0, &data, &mut rng).is_empty());
+    }
+}
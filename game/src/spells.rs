@@ -1,31 +1,7 @@
-use std::fmt;
+use std::convert::TryFrom;
+use yaml_rust::Yaml;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Spells {
-    LightningStrike,
-    Confusion,
-    MagicMissile,
-    RayOfFrost,
-    Experience,
-    Heal,
-    Fog,
-    Stun
-}
-
-impl fmt::Display for Spells {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Spells::LightningStrike => write!(f, "Lightning Strike"),
-            Spells::Confusion => write!(f, "Confusion"),
-            Spells::MagicMissile => write!(f, "Magic Missile"),
-            Spells::RayOfFrost => write!(f, "Ray of Frost"),
-            Spells::Experience => write!(f, "Experience"),
-            Spells::Heal => write!(f, "Heal"),
-            Spells::Fog => write!(f, "Fog"),
-            Spells::Stun => write!(f, "Stun")
-        }
-    }
-}
+use crate::data::{DataError, load_file, entries};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SpellTargetType {
@@ -43,99 +19,147 @@ pub enum SpellTargeting {
     Caster
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpellEffect {
+    Damage(i32),
+    Lightning(i32),
+    Heal(i32),
+    Slow,
+    Stun,
+    Confuse,
+    Fog,
+    Light(i32),
+    GainPoint
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Spell {
+    pub id: String,
     pub name: String,
-    pub kind: Spells,
     pub power: i32,
     pub range: i32,
     pub target: SpellTargetType,
-    pub targeting: SpellTargeting
+    pub targeting: SpellTargeting,
+    pub effects: Vec<SpellEffect>
 }
 
-impl Spell {
-    pub fn create(spl: Spells) -> Spell {
-        match spl {
-            Spells::Stun => {
-                Spell {
-                    name: "Stun".to_string(),
-                    kind: Spells::Stun,
-                    power: 1,
-                    range: 5,
-                    target: SpellTargetType::Entity,
-                    targeting: SpellTargeting::Select
-                }
-            },
-            Spells::Experience => {
-                Spell {
-                    name: "Experience".to_string(),
-                    kind: Spells::Experience,
-                    power: 1,
-                    range: 10,
-                    target: SpellTargetType::Entity,
-                    targeting: SpellTargeting::Caster
-                }
-            },
-            Spells::RayOfFrost => {
-                Spell {
-                    name: "Ray of Frost".to_string(),
-                    kind: Spells::RayOfFrost,
-                    power: 1,
-                    range: 10,
-                    target: SpellTargetType::Ray,
-                    targeting: SpellTargeting::Select
-                }
-            },
-            Spells::Fog => {
-                Spell {
-                    name: "Fog".to_string(),
-                    kind: Spells::Fog,
-                    power: 0,
-                    range: 5,
-                    target: SpellTargetType::Spot,
-                    targeting: SpellTargeting::Select
-                }
-            },
-            Spells::MagicMissile => {
-                Spell {
-                    name: "Magic Missile".to_string(),
-                    kind: Spells::MagicMissile,
-                    power: 5,
-                    range: 10,
-                    target: SpellTargetType::Projectile,
-                    targeting: SpellTargeting::Select
-                }
-            },
-            Spells::LightningStrike => {
-                Spell {
-                    name: "Lightning Strike".to_string(),
-                    kind: Spells::LightningStrike,
-                    power: 10,
-                    range: 4,
-                    target: SpellTargetType::Entity,
-                    targeting: SpellTargeting::Select
-                }
-            },
-            Spells::Confusion => {
-                Spell {
-                    name: "Confusion".to_string(),
-                    kind: Spells::Confusion,
-                    power: 0,
-                    range: 5,
-                    target: SpellTargetType::Closest,
-                    targeting: SpellTargeting::Closest
-                }
-            },
-            Spells::Heal => {
-                Spell {
-                    name: "Heal".to_string(),
-                    kind: Spells::Heal,
-                    power: 5,
-                    range: 3,
-                    target: SpellTargetType::Entity,
-                    targeting: SpellTargeting::Caster
-                }
-            }
+const TARGET_NAMES: [(&str, SpellTargetType); 5] = [
+    ("entity", SpellTargetType::Entity),
+    ("closest", SpellTargetType::Closest),
+    ("spot", SpellTargetType::Spot),
+    ("projectile", SpellTargetType::Projectile),
+    ("ray", SpellTargetType::Ray)
+];
+
+const TARGETING_NAMES: [(&str, SpellTargeting); 3] = [
+    ("select", SpellTargeting::Select),
+    ("closest", SpellTargeting::Closest),
+    ("caster", SpellTargeting::Caster)
+];
+
+pub fn load_spells(file: &str) -> Result<Vec<Spell>, DataError> {
+    let base = load_file(file)?;
+    let mut spells = vec![];
+    for entry in entries(file, &base)? {
+        let power = entry.opt_i32("power", 0)?;
+        let effects = match entry.yaml["effects"] {
+            Yaml::BadValue => vec![],
+            Yaml::Array(ref effects) => effects
+                .iter()
+                .map(|effect| get_effect(effect, power).map_err(|message| entry.error("effects", message)))
+                .collect::<Result<_, _>>()?,
+            _ => return Err(entry.error("effects", "expected a list of effects".to_string()))
+        };
+        let id = entry.str("id")?;
+        if spells.iter().any(|spell: &Spell| spell.id == id) {
+            return Err(entry.error("id", format!("\"{}\" is used by more than one spell", id)));
         }
+        spells.push(Spell{
+            id: id.to_string(),
+            name: entry.str("name")?.to_string(),
+            power,
+            range: entry.i32("range")?,
+            target: entry.one_of("target", &TARGET_NAMES)?,
+            targeting: entry.one_of("targeting", &TARGETING_NAMES)?,
+            effects
+        });
+    }
+    Ok(spells)
+}
+
+// An effect is either just its name, using the spell power for any amount,
+// or a single entry map of name to amount.
+fn get_effect(effect: &Yaml, power: i32) -> Result<SpellEffect, String> {
+    let (name, amount) = match *effect {
+        Yaml::Hash(ref hash) if hash.len() == 1 => {
+            let (name, amount) = hash.iter().next().unwrap();
+            let name = name.as_str().ok_or_else(|| "effect names must be strings".to_string())?;
+            let amount = amount.as_i64()
+                .and_then(|amount| i32::try_from(amount).ok())
+                .ok_or_else(|| format!("expected a number for {}", name))?;
+            (name, amount)
+        },
+        Yaml::String(ref name) => (name.as_str(), power),
+        _ => return Err("expected an effect name or a map of name to amount".to_string())
+    };
+    let effect = match name {
+        "damage" => SpellEffect::Damage(amount),
+        "lightning" => SpellEffect::Lightning(amount),
+        "heal" => SpellEffect::Heal(amount),
+        "slow" => SpellEffect::Slow,
+        "stun" => SpellEffect::Stun,
+        "confuse" => SpellEffect::Confuse,
+        "fog" => SpellEffect::Fog,
+        "light" => SpellEffect::Light(amount),
+        "gain point" => SpellEffect::GainPoint,
+        _ => return Err(format!("unknown effect \"{}\"", name))
+    };
+    Ok(effect)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spells::*;
+    use crate::testing;
+
+    #[test]
+    fn test_load_spells() {
+        let file = testing::data_file("spells", r#"
+Base:
+  - Frost:
+      id: "frost"
+      name: "Frost"
+      power: 3
+      range: 8
+      target: "projectile"
+      targeting: "select"
+      effects:
+        - damage
+        - slow
+        - heal: 1
+"#);
+        let spells = load_spells(&file).unwrap();
+        assert_eq!(spells.len(), 1);
+        assert_eq!(spells[0].target, SpellTargetType::Projectile);
+        assert_eq!(spells[0].effects, vec![SpellEffect::Damage(3), SpellEffect::Slow, SpellEffect::Heal(1)]);
+
+        let file = testing::data_file("duplicate_spells", r#"
+Base:
+  - Frost:
+      id: "frost"
+      name: "Frost"
+      range: 8
+      target: "projectile"
+      targeting: "select"
+  - Ice:
+      id: "frost"
+      name: "Ice"
+      range: 8
+      target: "projectile"
+      targeting: "select"
+"#);
+        let err = load_spells(&file).unwrap_err();
+        assert_eq!(err.entry, Some("Ice".to_string()));
+        assert_eq!(err.field, Some("id".to_string()));
     }
 }
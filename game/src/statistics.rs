@@ -0,0 +1,61 @@
+use spawning_pool::EntityId;
+
+use crate::events::GameEvent;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Statistics {
+    pub creatures_killed: u32,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub spells_cast: u32,
+    pub items_picked_up: u32,
+    pub items_used: u32,
+    pub doors_opened: u32,
+    pub deepest_level: u32
+}
+
+impl Statistics {
+    pub fn record(&mut self, event: &GameEvent, player: EntityId) {
+        match *event {
+            GameEvent::EntityDamaged{attacker, target, damage} => {
+                if target == player {
+                    self.damage_taken += damage;
+                } else if attacker == Some(player) {
+                    self.damage_dealt += damage;
+                }
+            },
+            GameEvent::EntityDied{entity, ..} if entity != player => {
+                self.creatures_killed += 1;
+            },
+            GameEvent::SpellCast{caster: Some(caster), ..} if caster == player => {
+                self.spells_cast += 1;
+            },
+            GameEvent::ItemPickedUp{entity, ..} if entity == player => {
+                self.items_picked_up += 1;
+            },
+            GameEvent::ItemUsed{entity, ..} if entity == player => {
+                self.items_used += 1;
+            },
+            GameEvent::DoorOpened{entity: Some(entity), ..} if entity == player => {
+                self.doors_opened += 1;
+            },
+            GameEvent::LevelEntered{level} => {
+                self.deepest_level = self.deepest_level.max(level);
+            },
+            _ => {}
+        }
+    }
+
+    pub fn summary(&self) -> Vec<String> {
+        vec![
+            format!("Deepest level: {}", self.deepest_level),
+            format!("Creatures killed: {}", self.creatures_killed),
+            format!("Damage dealt: {}", self.damage_dealt),
+            format!("Damage taken: {}", self.damage_taken),
+            format!("Spells cast: {}", self.spells_cast),
+            format!("Items picked up: {}", self.items_picked_up),
+            format!("Items used: {}", self.items_used),
+            format!("Doors opened: {}", self.doors_opened)
+        ]
+    }
+}
@@ -4,10 +4,9 @@ use rand::Rng;
 
 use spawning_pool::EntityId;
 
-use crate::utils;
-use crate::map;
+use crate::map::{self, TileType};
 
-use crate::messages::*;
+use crate::events::GameEvent;
 use geo::*;
 use crate::actions::*;
 use crate::game::*;
@@ -35,7 +34,7 @@ fn confusion(entity: EntityId, state: &mut GameState) -> Option<Action> {
     let _ = state.spawning_pool.get::<Stats>(entity)?.effects.get(&Effect::Confuse)?;
     let entity_position = get_entity_position(entity, state)?;
     let mut neighbours = get_neigbours(entity_position.x, entity_position.y, false);
-    rand::thread_rng().shuffle(&mut neighbours);
+    state.rng.shuffle(&mut neighbours);
     for n in neighbours {
         if map::can_walk(n, &state.spatial_table, &state.map) {
             let (x, y) = entity_position.direction_to(n);
@@ -52,9 +51,7 @@ fn confusion(entity: EntityId, state: &mut GameState) -> Option<Action> {
 fn stun(entity: EntityId, state: &mut GameState) -> Option<Action> {
     use components::*;
     let _ = state.spawning_pool.get::<Stats>(entity)?.effects.get(&Effect::Stun)?;
-    if entity == state.player {
-        state.messages.log(MessageLevel::Info, "The player is stunned");
-    }
+    state.events.push(GameEvent::EntityStunned{entity});
     return Some(Action::new(
         Some(entity),
         None,
@@ -62,6 +59,48 @@ fn stun(entity: EntityId, state: &mut GameState) -> Option<Action> {
     ));
 }
 
+const LAVA_DAMAGE: i32 = 10;
+const BURN_DAMAGE: i32 = 2;
+const BURN_TIME: i32 = 300;
+
+// What the ground does to an entity ending its turn on it. Lava sets it on
+// fire, and water puts the fire out again.
+pub fn terrain(entity: EntityId, state: &mut GameState) -> Vec<Action> {
+    use components::*;
+
+    let pos = match get_entity_position(entity, state) {
+        Some(pos) => pos,
+        None => return vec![]
+    };
+    let tile_type = state.map.get_cell(pos.x, pos.y).tile_type;
+    let time = state.scheduler.time;
+    let damage = |damage| Action::new(None, Some(ActionTarget::Entity(entity)), Command::TakeDamage{damage});
+
+    if tile_type == TileType::Chasm {
+        return vec![Action::new(Some(entity), None, Command::Fall)];
+    }
+    let stats = match state.spawning_pool.get_mut::<Stats>(entity) {
+        Some(stats) => stats,
+        None => return vec![]
+    };
+    match tile_type {
+        TileType::Lava => {
+            if stats.effects.insert(Effect::Burning, time + BURN_TIME).is_none() {
+                state.events.push(GameEvent::EffectApplied{actor: None, target: entity, effect: Effect::Burning});
+            }
+            vec![damage(LAVA_DAMAGE)]
+        },
+        TileType::Water | TileType::DeepWater => {
+            if stats.effects.remove(&Effect::Burning).is_some() {
+                state.events.push(GameEvent::EffectExpired{entity, effect: Effect::Burning});
+            }
+            vec![]
+        },
+        _ if stats.effects.contains_key(&Effect::Burning) => vec![damage(BURN_DAMAGE)],
+        _ => vec![]
+    }
+}
+
 pub struct DurationSystem {
     last_time: i32
 }
@@ -87,6 +126,7 @@ impl DurationSystem {
         let ids: Vec<EntityId> = state.spawning_pool.get_all::<components::Stats>()
             .iter()
             .map(|(id, _)| *id)
+            .filter(|id| state.spawning_pool.get::<components::Physics>(*id).is_some())
             .collect();
         for id  in ids {
             self.clear_effects(id, state);
@@ -96,7 +136,6 @@ impl DurationSystem {
     fn clear_effects(&self, entity: EntityId, state: &mut GameState) {
         use components::*;
 
-        let name = utils::get_entity_name(entity, &state.spawning_pool);
         let current_time = state.scheduler.time;
         if let Some(stats) = state.spawning_pool.get_mut::<Stats>(entity) {
             let remove: Vec<_> = stats.effects.iter().filter_map(|(e, t)| {
@@ -107,16 +146,18 @@ impl DurationSystem {
                 }
             }).collect();
             for r in remove {
-                state.messages.log(MessageLevel::Info, format!("The {} is longer {}", name, r));
                 stats.effects.remove(&r);
+                state.events.push(GameEvent::EffectExpired{entity, effect: r});
             }
         }
     }
 
     fn duration(&mut self, state: &mut GameState) {
+        // entities on other levels have no physics and are left alone
         let ids: Vec<EntityId> = state.spawning_pool.get_all::<components::Duration>()
             .iter()
             .map(|(id, _)| *id)
+            .filter(|id| state.spawning_pool.get::<components::Physics>(*id).is_some())
             .collect();
         for id  in ids {
             self.apply(id, state);
@@ -139,3 +180,47 @@ impl DurationSystem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::systems::*;
+    use crate::testing;
+
+    fn commands(actions: Vec<Action>) -> Vec<Command> {
+        actions.into_iter().map(|action| action.command).collect()
+    }
+
+    fn is_burning(entity: EntityId, state: &GameState) -> bool {
+        state.spawning_pool.get::<components::Stats>(entity).unwrap().effects.contains_key(&components::Effect::Burning)
+    }
+
+    #[test]
+    fn test_lava_burns_until_water_puts_it_out() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let player = game.state.player;
+        let pos = get_entity_position(player, &game.state).unwrap();
+
+        testing::set_tile(&mut game.state, pos, TileType::Lava);
+        assert_eq!(commands(terrain(player, &mut game.state)), vec![Command::TakeDamage{damage: LAVA_DAMAGE}]);
+        assert!(is_burning(player, &game.state));
+
+        testing::set_tile(&mut game.state, pos, TileType::Floor);
+        assert_eq!(commands(terrain(player, &mut game.state)), vec![Command::TakeDamage{damage: BURN_DAMAGE}]);
+
+        testing::set_tile(&mut game.state, pos, TileType::Water);
+        assert!(terrain(player, &mut game.state).is_empty());
+        assert!(!is_burning(player, &game.state));
+
+        testing::set_tile(&mut game.state, pos, TileType::Floor);
+        assert!(terrain(player, &mut game.state).is_empty());
+    }
+
+    #[test]
+    fn test_chasms_make_entities_fall() {
+        let mut game = testing::new_game([1, 2, 3, 4]);
+        let monster = testing::first_monster(&game.state);
+        let pos = get_entity_position(monster, &game.state).unwrap();
+        testing::set_tile(&mut game.state, pos, TileType::Chasm);
+        assert_eq!(commands(terrain(monster, &mut game.state)), vec![Command::Fall]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::rc::Rc;
+use geo::Point;
+use spawning_pool::EntityId;
+use crate::components::{self, AiMemory};
+use crate::data::{GameData, TEST_DATA};
+use crate::game::{Game, GameState};
+use crate::map::{Cell, Map, Palette, TileType};
+use crate::rng::Seed;
+
+// A new game on the first level, using the shipped data.
+pub fn new_game(seed: Seed) -> Game {
+    let data = Rc::new(GameData::load_from(TEST_DATA).unwrap());
+    Game::new(seed, data)
+}
+
+pub fn first_monster(state: &GameState) -> EntityId {
+    state.spawning_pool
+        .get_all::<AiMemory>()
+        .first()
+        .map(|&(id, _)| id)
+        .unwrap()
+}
+
+// Writes a data file for a loader to read, named after the test using it.
+pub fn data_file(name: &str, contents: &str) -> String {
+    let path = env::temp_dir().join(format!("wily-{}-{}.yaml", process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+pub fn set_tile(state: &mut GameState, pos: Point, tile_type: TileType) {
+    let index = (pos.x + pos.y * state.map.dimensions.x) as usize;
+    state.map.cells[index] = Cell {
+        tile_type,
+        blocks_movement: tile_type == TileType::Wall,
+        blocks_sight: tile_type == TileType::Wall
+    };
+}
+
+// Swaps the level for open floor with nothing on it but the player, who
+// carries no light.
+pub fn empty_level(state: &mut GameState, ambient_light: f32) {
+    let dimensions = state.map.dimensions;
+    let cells = (0..dimensions.x * dimensions.y)
+        .map(|_| Cell{tile_type: TileType::Floor, blocks_movement: false, blocks_sight: false})
+        .collect();
+    state.map = Map{dimensions, cells, palette: Palette::default(), ambient_light};
+    let others: Vec<EntityId> = state.spawning_pool
+        .get_all::<components::Physics>()
+        .iter()
+        .map(|&(id, _)| id)
+        .filter(|id| *id != state.player)
+        .collect();
+    for id in others {
+        state.spawning_pool.remove::<components::Physics>(id);
+    }
+    state.spawning_pool.remove::<components::Light>(state.player);
+    state.spatial_table.reset(&state.spawning_pool);
+}
+
+pub fn add_light(state: &mut GameState, coord: Point, radius: i32) {
+    let light = state.spawning_pool.spawn_entity();
+    state.spawning_pool.set(light, components::Physics{coord});
+    state.spawning_pool.set(light, components::Light{radius, color: geo::colors::WHITE});
+}
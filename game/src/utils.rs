@@ -91,10 +91,24 @@ pub fn describe_entity(entity: EntityId, spawning_pool: &components::SpawningPoo
                 },
                 components::Effect::Confuse => {
                     desc = format!("{}, Confused", desc);
+                },
+                components::Effect::Burning => {
+                    desc = format!("{}, Burning", desc);
                 }
             }
         }
     }
+    if let Some(memory) = spawning_pool.get::<components::AiMemory>(entity) {
+        match memory.awareness {
+            components::Awareness::Unaware => {
+                desc = format!("{}, Asleep", desc);
+            },
+            components::Awareness::Suspicious => {
+                desc = format!("{}, Suspicious", desc);
+            },
+            components::Awareness::Hunting => {}
+        }
+    }
 
     return desc;
 }
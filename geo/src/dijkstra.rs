@@ -0,0 +1,202 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::point::Point;
+use crate::get_neigbours;
+
+const UNREACHABLE: i32 = i32::max_value();
+// Values are kept in tenths of a step, so scaling them for fleeing keeps
+// enough precision to tell neighbouring tiles apart.
+const STEP: i32 = 10;
+
+// Fleeing creatures use the distances to the goals times this, negated, so
+// they run past a goal into open space rather than into a dead end.
+const FLEE_FACTOR: (i32, i32) = (-6, 5);
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct Node {
+    value: i32,
+    position: Point
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Node) -> Ordering {
+        self.value.cmp(&other.value).reverse()
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Node) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Cost of getting from every tile to the closest goal, diagonal steps
+// included. `cost` tells what stepping onto a tile costs, at least 1, or
+// none for tiles that can't be entered. Any number of creatures can share
+// one: stepping downhill leads to a goal.
+#[derive(Clone, Debug, Default)]
+pub struct DijkstraMap {
+    width: i32,
+    height: i32,
+    values: Vec<i32>
+}
+
+impl DijkstraMap {
+    pub fn new<F>(width: i32, height: i32, goals: &[Point], cost: F) -> DijkstraMap
+        where F: Fn(Point) -> Option<i32>
+    {
+        let seeds = goals.iter().map(|goal| (*goal, 0)).collect();
+        DijkstraMap::from_seeds(width, height, seeds, cost)
+    }
+
+    fn from_seeds<F>(width: i32, height: i32, seeds: Vec<(Point, i32)>, cost: F) -> DijkstraMap
+        where F: Fn(Point) -> Option<i32>
+    {
+        let mut map = DijkstraMap {
+            width,
+            height,
+            values: vec![UNREACHABLE; (width * height) as usize]
+        };
+        let mut frontier = BinaryHeap::new();
+        for (position, value) in seeds {
+            if let Some(index) = map.index(position) {
+                if value < map.values[index] {
+                    map.values[index] = value;
+                    frontier.push(Node { value, position });
+                }
+            }
+        }
+        while let Some(Node { value, position }) = frontier.pop() {
+            if value > map.values[map.index(position).unwrap()] {
+                continue;
+            }
+            for neighbour in get_neigbours(position.x, position.y, false) {
+                let index = match map.index(neighbour) {
+                    Some(index) => index,
+                    None => continue
+                };
+                if value + STEP >= map.values[index] {
+                    continue;
+                }
+                if let Some(cost) = cost(neighbour) {
+                    let new_value = value + cost.max(1) * STEP;
+                    if new_value < map.values[index] {
+                        map.values[index] = new_value;
+                        frontier.push(Node { value: new_value, position: neighbour });
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    // A map leading away from the goals of this one.
+    pub fn flee<F>(&self, cost: F) -> DijkstraMap
+        where F: Fn(Point) -> Option<i32>
+    {
+        let seeds = (0..self.values.len())
+            .filter(|i| self.values[*i] != UNREACHABLE)
+            .map(|i| {
+                let position = Point::new(i as i32 % self.width, i as i32 / self.width);
+                (position, self.values[i] * FLEE_FACTOR.0 / FLEE_FACTOR.1)
+            })
+            .collect();
+        DijkstraMap::from_seeds(self.width, self.height, seeds, cost)
+    }
+
+    fn index(&self, position: Point) -> Option<usize> {
+        if position.x < 0 || position.y < 0 || position.x >= self.width || position.y >= self.height {
+            None
+        } else {
+            Some((position.x + position.y * self.width) as usize)
+        }
+    }
+
+    fn value(&self, position: Point) -> Option<i32> {
+        self.index(position)
+            .map(|index| self.values[index])
+            .filter(|value| *value != UNREACHABLE)
+    }
+
+    // Cost of the way to the closest goal, none for tiles that can't reach
+    // one.
+    pub fn get(&self, position: Point) -> Option<i32> {
+        self.value(position).map(|value| value / STEP)
+    }
+
+    // The neighbour closest to a goal that `can_enter` allows, if it is any
+    // closer than `from`. Several creatures following the map spread out
+    // around whoever is in their way.
+    pub fn downhill<F>(&self, from: Point, can_enter: F) -> Option<Point>
+        where F: Fn(Point) -> bool
+    {
+        let mut best = (self.value(from)?, None);
+        for neighbour in get_neigbours(from.x, from.y, false) {
+            if let Some(value) = self.value(neighbour) {
+                if value < best.0 && can_enter(neighbour) {
+                    best = (value, Some(neighbour));
+                }
+            }
+        }
+        best.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dijkstra::*;
+
+    // Walls can't be entered, water takes two steps.
+    fn cost(map: &'static [&'static str]) -> impl Fn(Point) -> Option<i32> {
+        move |p: Point| match map[p.y as usize].as_bytes()[p.x as usize] {
+            b'#' => None,
+            b'~' => Some(2),
+            _ => Some(1)
+        }
+    }
+
+    #[test]
+    fn test_downhill_leads_to_goal_and_flee_away() {
+        let map: &'static [&'static str] = &[
+            "#########",
+            "#.......#",
+            "#.#####.#",
+            "#.#...#.#",
+            "#########"
+        ];
+        let goal = Point::new(1, 3);
+        let towards = DijkstraMap::new(9, 5, &[goal], cost(map));
+        assert_eq!(towards.get(goal), Some(0));
+        assert_eq!(towards.get(Point::new(7, 3)), Some(8));
+        assert_eq!(towards.get(Point::new(4, 3)), None);
+        assert_eq!(towards.get(Point::new(0, 0)), None);
+
+        let mut position = Point::new(7, 3);
+        let mut steps = 0;
+        while let Some(next) = towards.downhill(position, |_| true) {
+            position = next;
+            steps += 1;
+        }
+        assert_eq!((position, steps), (goal, 8));
+        assert_eq!(towards.downhill(Point::new(2, 1), |p| p != Point::new(1, 2)), None);
+
+        let wet: &'static [&'static str] = &[
+            "#######",
+            "#.~~~.#",
+            "#.###.#",
+            "#.....#",
+            "#######"
+        ];
+        let around = DijkstraMap::new(7, 5, &[Point::new(1, 1)], cost(wet));
+        assert_eq!(around.get(Point::new(5, 1)), Some(6));
+        assert_eq!(around.downhill(Point::new(5, 1), |_| true), Some(Point::new(5, 2)));
+
+        let away = towards.flee(cost(map));
+        let mut position = Point::new(2, 1);
+        while let Some(next) = away.downhill(position, |_| true) {
+            position = next;
+        }
+        assert_eq!(position, Point::new(7, 3));
+    }
+}
@@ -0,0 +1,146 @@
+use crate::point::Point;
+
+// Symmetric shadowcasting, as described by Albert Ford. Each of the four
+// quadrants around the origin is scanned row by row, and a tile is only lit
+// when its centre is in view, so whenever a can see b, b can also see a.
+// Slopes are kept as fractions to stay exact.
+#[derive(Clone, Copy)]
+struct Slope {
+    num: i32,
+    den: i32
+}
+
+impl Slope {
+    fn new(num: i32, den: i32) -> Slope {
+        Slope { num, den }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Row {
+    depth: i32,
+    start: Slope,
+    end: Slope
+}
+
+impl Row {
+    fn min_col(&self) -> i32 {
+        // depth * start, rounded with ties going up
+        floor_div(2 * self.depth * self.start.num + self.start.den, 2 * self.start.den)
+    }
+
+    fn max_col(&self) -> i32 {
+        // depth * end, rounded with ties going down
+        -floor_div(-(2 * self.depth * self.end.num - self.end.den), 2 * self.end.den)
+    }
+
+    fn is_symmetric(&self, col: i32) -> bool {
+        col * self.start.den >= self.depth * self.start.num && col * self.end.den <= self.depth * self.end.num
+    }
+
+    fn next(&self) -> Row {
+        Row { depth: self.depth + 1, ..*self }
+    }
+}
+
+fn floor_div(a: i32, b: i32) -> i32 {
+    let d = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) { d - 1 } else { d }
+}
+
+// The slope through the near edge of a tile.
+fn slope(depth: i32, col: i32) -> Slope {
+    Slope::new(2 * col - 1, 2 * depth)
+}
+
+fn transform(origin: Point, quadrant: i32, depth: i32, col: i32) -> Point {
+    match quadrant {
+        0 => Point::new(origin.x + col, origin.y - depth),
+        1 => Point::new(origin.x + depth, origin.y + col),
+        2 => Point::new(origin.x + col, origin.y + depth),
+        _ => Point::new(origin.x - depth, origin.y + col)
+    }
+}
+
+// Calls `reveal` for every tile within `radius` of `origin` that can be seen
+// from it. Walls are revealed, but block the view behind them. Tiles outside
+// of the map are asked for as well, `is_transparent` should return false
+// for them and `reveal` skip them.
+pub fn field_of_view<T, R>(origin: Point, radius: i32, mut is_transparent: T, mut reveal: R)
+    where T: FnMut(Point) -> bool, R: FnMut(Point)
+{
+    reveal(origin);
+    let in_radius = |depth: i32, col: i32| depth * depth + col * col <= radius * radius;
+    for quadrant in 0..4 {
+        let mut rows = vec![Row { depth: 1, start: Slope::new(-1, 1), end: Slope::new(1, 1) }];
+        while let Some(mut row) = rows.pop() {
+            if row.depth > radius {
+                continue;
+            }
+            let mut previous_open = None;
+            for col in row.min_col()..=row.max_col() {
+                let pos = transform(origin, quadrant, row.depth, col);
+                let open = is_transparent(pos);
+                if in_radius(row.depth, col) && (!open || row.is_symmetric(col)) {
+                    reveal(pos);
+                }
+                if previous_open == Some(false) && open {
+                    row.start = slope(row.depth, col);
+                }
+                if previous_open == Some(true) && !open {
+                    let mut next = row.next();
+                    next.end = slope(row.depth, col);
+                    rows.push(next);
+                }
+                previous_open = Some(open);
+            }
+            if previous_open == Some(true) {
+                rows.push(row.next());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::fov::*;
+
+    fn visible(map: &[&str], origin: Point, radius: i32) -> HashSet<Point> {
+        let mut seen = HashSet::new();
+        let is_transparent = |p: Point| {
+            p.y >= 0 && p.x >= 0 && (p.y as usize) < map.len() && (p.x as usize) < map[0].len()
+                && map[p.y as usize].as_bytes()[p.x as usize] != b'#'
+        };
+        field_of_view(origin, radius, is_transparent, |p| { seen.insert(p); });
+        seen
+    }
+
+    #[test]
+    fn test_walls_cast_shadows_symmetrically() {
+        let map = [
+            "##########",
+            "#........#",
+            "#...#....#",
+            "#........#",
+            "#.#......#",
+            "##########"
+        ];
+        let from = Point::new(2, 2);
+        let seen = visible(&map, from, 20);
+        assert!(seen.contains(&Point::new(8, 4)));
+        assert!(seen.contains(&Point::new(4, 2)));
+        assert!(!seen.contains(&Point::new(6, 2)));
+        for y in 1..5 {
+            for x in 1..9 {
+                let to = Point::new(x, y);
+                if map[y as usize].as_bytes()[x as usize] == b'.' {
+                    assert_eq!(seen.contains(&to), visible(&map, to, 20).contains(&from), "{} and {}", from, to);
+                }
+            }
+        }
+        let close = visible(&map, from, 2);
+        assert!(close.contains(&Point::new(4, 2)));
+        assert!(!close.contains(&Point::new(4, 4)));
+    }
+}
@@ -2,9 +2,16 @@
 
 mod point;
 mod rect;
+mod fov;
+mod dijkstra;
+mod line;
+pub mod colors;
 
 pub use crate::point::Point;
 pub use crate::rect::Rect;
+pub use crate::fov::field_of_view;
+pub use crate::dijkstra::DijkstraMap;
+pub use crate::line::Line;
 
 
 pub fn get_neigbours(x: i32, y: i32, only_cardinal: bool) -> Vec<point::Point> {
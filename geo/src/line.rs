@@ -0,0 +1,89 @@
+// Bresenham line between two tiles, stepping the same way as the libtcod
+// lines the game used before. The start is left out, the end is included.
+pub struct Line {
+    x: i32,
+    y: i32,
+    dest_x: i32,
+    dest_y: i32,
+    step_x: i32,
+    step_y: i32,
+    delta_x: i32,
+    delta_y: i32,
+    e: i32
+}
+
+impl Line {
+    pub fn new(start: (i32, i32), end: (i32, i32)) -> Line {
+        let delta_x = end.0 - start.0;
+        let delta_y = end.1 - start.1;
+        let step_x = delta_x.signum();
+        let step_y = delta_y.signum();
+        let e = (step_x * delta_x).max(step_y * delta_y);
+        Line {
+            x: start.0,
+            y: start.1,
+            dest_x: end.0,
+            dest_y: end.1,
+            step_x,
+            step_y,
+            delta_x: delta_x * 2,
+            delta_y: delta_y * 2,
+            e
+        }
+    }
+}
+
+impl Iterator for Line {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<(i32, i32)> {
+        if self.step_x * self.delta_x > self.step_y * self.delta_y {
+            if self.x == self.dest_x {
+                return None;
+            }
+            self.x += self.step_x;
+            self.e -= self.step_y * self.delta_y;
+            if self.e < 0 {
+                self.y += self.step_y;
+                self.e += self.step_x * self.delta_x;
+            }
+        } else {
+            if self.y == self.dest_y {
+                return None;
+            }
+            self.y += self.step_y;
+            self.e -= self.step_x * self.delta_x;
+            if self.e < 0 {
+                self.x += self.step_x;
+                self.e += self.step_y * self.delta_y;
+            }
+        }
+        Some((self.x, self.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line::*;
+
+    #[test]
+    fn test_lines_leave_out_the_start() {
+        assert_eq!(Line::new((1, 1), (4, 1)).collect::<Vec<_>>(), vec![(2, 1), (3, 1), (4, 1)]);
+        assert_eq!(Line::new((1, 1), (1, -1)).collect::<Vec<_>>(), vec![(1, 0), (1, -1)]);
+        assert_eq!(Line::new((0, 0), (-2, -2)).collect::<Vec<_>>(), vec![(-1, -1), (-2, -2)]);
+        assert_eq!(Line::new((3, 3), (3, 3)).count(), 0);
+    }
+
+    #[test]
+    fn test_lines_take_one_step_per_tile() {
+        let line: Vec<(i32, i32)> = Line::new((0, 0), (5, 2)).collect();
+        assert_eq!(line.len(), 5);
+        assert_eq!(line.last(), Some(&(5, 2)));
+        let mut previous = (0, 0);
+        for &(x, y) in &line {
+            assert_eq!(x - previous.0, 1);
+            assert!(y - previous.1 == 0 || y - previous.1 == 1);
+            previous = (x, y);
+        }
+    }
+}
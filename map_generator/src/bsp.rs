@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use rand::{Rng};
 
 use geo::{Rect, Point, get_neigbours};
-use super::map::Map;
+use super::map::{Map, Tile};
 
 #[derive(Debug)]
 pub struct Leaf {
@@ -92,7 +92,7 @@ pub fn carve<T: Rng>(leaf: &Leaf, map: &mut Map, connect: bool, rng: &mut T) ->
     if let Some(ref room) = leaf.room {
         for x in 0..room.width {
             for y in 0..room.height {
-                map.set(x + room.x1, y + room.y1, 1);
+                map.set(x + room.x1, y + room.y1, Tile::Floor);
             }
         }
         map.add_room(room.clone());
@@ -145,20 +145,26 @@ fn carve_tunnel<T: Rng>(x1: i32, y1: i32, x2: i32, y2: i32, map: &mut Map, rng:
 
 fn carve_h_tunnel(x1: i32, y: i32, x2: i32, map: &mut Map) {
     for x in min(x1, x2)..(max(x1, x2) + 1) {
-        map.set(x, y, 1);
+        map.set(x, y, Tile::Floor);
     }
 }
 
 fn carve_v_tunnel(x: i32, y1: i32, y2: i32, map: &mut Map) {
     for y in min(y1, y2)..(max(y1, y2) + 1) {
-        map.set(x, y, 1);
+        map.set(x, y, Tile::Floor);
     }
 }
 
+// Stairs go in the second to last room, away from its walls when it is large
+// enough. Tiny maps may only have a single room to put them in.
 fn add_stairs<T: Rng>(map: &mut Map, rng: &mut T) {
-    let room = map.rooms[map.rooms.len() - 2];
-    let x = rng.gen_range(1, room.width-1);
-    let y = rng.gen_range(1, room.height-1);
+    let room = match map.rooms.len() {
+        0 => return,
+        1 => map.rooms[0],
+        n => map.rooms[n - 2]
+    };
+    let x = if room.width > 2 { rng.gen_range(1, room.width - 1) } else { 0 };
+    let y = if room.height > 2 { rng.gen_range(1, room.height - 1) } else { 0 };
     map.set_stairs((room.x1 + x, room.y1 + y).into());
 }
 
@@ -170,7 +176,7 @@ fn add_doors<T: Rng>(map: &mut Map, rng: &mut T) {
         }
         for x in (room.x1-1)..(room.x2 + 1) {
             for y in (room.y1-1)..(room.y2 + 1) {
-                if map.get(x, y) == Some(0) {
+                if map.get(x, y) == Some(Tile::Wall) {
                     continue;
                 }
                 if x == room.x1-1 
@@ -184,7 +190,7 @@ fn add_doors<T: Rng>(map: &mut Map, rng: &mut T) {
                         continue;
                     }
                     let walls: Vec<&Point> = neighbours.iter()
-                        .filter(|pos| map.in_bounds(pos.x, pos.y) && map.get(pos.x, pos.y) == Some(1))
+                        .filter(|pos| map.in_bounds(pos.x, pos.y) && map.get(pos.x, pos.y).map_or(false, Tile::is_passable))
                         .collect();
                     if walls.len() == 2 {
                         places.insert(Point::new(x, y));
@@ -193,9 +199,11 @@ fn add_doors<T: Rng>(map: &mut Map, rng: &mut T) {
             }
         }
     }
+    // sets iterate in a different order every run
+    let mut places: Vec<Point> = places.into_iter().collect();
+    places.sort_by_key(|pos| (pos.y, pos.x));
     for pos in places {
-        map.set(pos.x, pos.y, 2);
-        map.doors.push(pos);
+        map.add_door(pos);
     }
 }
 
@@ -214,7 +222,7 @@ pub fn generate<T: Rng>(width: i32, height: i32, min_size: i32, rng: &mut T) ->
 #[cfg(test)]
 mod tests {
     use rand::{XorShiftRng, SeedableRng};
-    use bsp::*;
+    use crate::bsp::*;
 
     fn test_leaf_equality(actual: &Leaf, expected: &Leaf) {
         assert_eq!(actual.dim, expected.dim);
@@ -0,0 +1,227 @@
+use rand::{Rng};
+use std::collections::VecDeque;
+
+use geo::{Point, Rect, get_neigbours};
+use super::map::{Map, Tile};
+
+const WALL_CHANCE: f32 = 0.45;
+const SECTOR_WIDTH: i32 = 12;
+const SECTOR_HEIGHT: i32 = 8;
+const MAX_ROOM_SIZE: i32 = 6;
+const MIN_ROOMS: usize = 5;
+const ATTEMPTS: usize = 10;
+
+// Walls in the square around a tile, the tile itself included.
+fn count_walls(map: &Map, x: i32, y: i32, radius: i32) -> i32 {
+    let mut walls = 0;
+    for i in (x - radius)..=(x + radius) {
+        for j in (y - radius)..=(y + radius) {
+            if map.get_or_wall(i, j) != Tile::Floor {
+                walls += 1;
+            }
+        }
+    }
+    walls
+}
+
+fn random_fill<T: Rng>(map: &mut Map, rng: &mut T) {
+    for x in 0..map.width {
+        for y in 0..map.height {
+            let border = x == 0 || y == 0 || x == map.width - 1 || y == map.height - 1;
+            let v = if border || rng.gen::<f32>() < WALL_CHANCE { Tile::Wall } else { Tile::Floor };
+            map.set(x, y, v);
+        }
+    }
+}
+
+// One cellular automata step. While `fill_open` is set, large open areas get
+// a pillar in the middle, which breaks up the early caves.
+fn smooth(map: &Map, fill_open: bool) -> Map {
+    let mut next = map.clone();
+    for x in 1..(map.width - 1) {
+        for y in 1..(map.height - 1) {
+            let walls = count_walls(map, x, y, 1);
+            let wall = walls >= 5 || (fill_open && count_walls(map, x, y, 2) <= 2);
+            next.set(x, y, if wall { Tile::Wall } else { Tile::Floor });
+        }
+    }
+    next
+}
+
+fn flood_fill(map: &Map, start: Point, regions: &mut Vec<i32>, region: i32) -> usize {
+    let mut size = 0;
+    let mut queue = VecDeque::new();
+    regions[(start.y * map.width + start.x) as usize] = region;
+    queue.push_back(start);
+    while let Some(p) = queue.pop_front() {
+        size += 1;
+        for n in get_neigbours(p.x, p.y, true) {
+            if map.get_or_wall(n.x, n.y).is_passable() && regions[(n.y * map.width + n.x) as usize] == 0 {
+                regions[(n.y * map.width + n.x) as usize] = region;
+                queue.push_back(n);
+            }
+        }
+    }
+    size
+}
+
+// Walls up every region but the largest one, so the whole cave can be
+// walked.
+fn keep_largest_region(map: &mut Map) {
+    let mut regions = vec![0; map.data.len()];
+    let mut largest = (0, 0);
+    let mut region = 0;
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if map.get_or_wall(x, y) == Tile::Floor && regions[(y * map.width + x) as usize] == 0 {
+                region += 1;
+                let size = flood_fill(map, Point::new(x, y), &mut regions, region);
+                if size > largest.1 {
+                    largest = (region, size);
+                }
+            }
+        }
+    }
+    for (i, r) in regions.iter().enumerate() {
+        if *r != largest.0 {
+            map.data[i] = Tile::Wall;
+        }
+    }
+}
+
+fn is_floor_rect(map: &Map, rect: &Rect) -> bool {
+    (rect.x1..rect.x2).all(|x| (rect.y1..rect.y2).all(|y| map.get_or_wall(x, y) == Tile::Floor))
+}
+
+fn overlaps_room(map: &Map, rect: &Rect) -> bool {
+    map.rooms.iter().any(|room| room.x1 < rect.x2 && rect.x1 < room.x2 && room.y1 < rect.y2 && rect.y1 < room.y2)
+}
+
+// Grows a rectangle out from a floor tile for as long as it only covers
+// floor and stays clear of the rooms found so far.
+fn grow_room(map: &Map, start: Point) -> Rect {
+    let mut room = Rect::new(start.x, start.y, 1, 1);
+    let mut growing = true;
+    while growing {
+        growing = false;
+        let candidates = [
+            Rect::new(room.x1, room.y1, room.width + 1, room.height),
+            Rect::new(room.x1 - 1, room.y1, room.width + 1, room.height),
+            Rect::new(room.x1, room.y1, room.width, room.height + 1),
+            Rect::new(room.x1, room.y1 - 1, room.width, room.height + 1)
+        ];
+        for candidate in candidates.iter() {
+            if candidate.width <= MAX_ROOM_SIZE && candidate.height <= MAX_ROOM_SIZE && is_floor_rect(map, candidate) && !overlaps_room(map, candidate) {
+                room = *candidate;
+                growing = true;
+            }
+        }
+    }
+    room
+}
+
+// Caves have no rooms of their own, but the game places the player, items and
+// monsters in rooms. Every sector of the map gets a floor only rectangle
+// grown from the floor tile closest to the sector's center.
+fn find_rooms(map: &mut Map) {
+    for sy in 0..(map.height / SECTOR_HEIGHT) {
+        for sx in 0..(map.width / SECTOR_WIDTH) {
+            let sector = Rect::new(sx * SECTOR_WIDTH, sy * SECTOR_HEIGHT, SECTOR_WIDTH, SECTOR_HEIGHT);
+            let center = sector.center();
+            let mut closest: Option<Point> = None;
+            for x in sector.x1..sector.x2 {
+                for y in sector.y1..sector.y2 {
+                    let p = Point::new(x, y);
+                    if map.get_or_wall(x, y) == Tile::Floor && closest.map_or(true, |c| p.tile_distance(center) < c.tile_distance(center)) {
+                        closest = Some(p);
+                    }
+                }
+            }
+            if let Some(start) = closest.filter(|start| !overlaps_room(map, &Rect::new(start.x, start.y, 1, 1))) {
+                let room = grow_room(map, start);
+                if room.width > 1 && room.height > 1 {
+                    map.add_room(room);
+                }
+            }
+        }
+    }
+}
+
+// The floor tile with the longest walk from the start.
+fn place_stairs(map: &mut Map) {
+    let start = map.rooms[0].center();
+    let mut distances = vec![-1; map.data.len()];
+    let mut queue = VecDeque::new();
+    let mut stairs = start;
+    distances[(start.y * map.width + start.x) as usize] = 0;
+    queue.push_back(start);
+    while let Some(p) = queue.pop_front() {
+        let distance = distances[(p.y * map.width + p.x) as usize];
+        if distance > distances[(stairs.y * map.width + stairs.x) as usize] {
+            stairs = p;
+        }
+        for n in get_neigbours(p.x, p.y, true) {
+            if map.get_or_wall(n.x, n.y) == Tile::Floor && distances[(n.y * map.width + n.x) as usize] == -1 {
+                distances[(n.y * map.width + n.x) as usize] = distance + 1;
+                queue.push_back(n);
+            }
+        }
+    }
+    map.set_stairs(stairs);
+}
+
+fn generate_once<T: Rng>(width: i32, height: i32, rng: &mut T) -> Map {
+    let mut map = Map::new(width, height);
+    random_fill(&mut map, rng);
+    for _ in 0..4 {
+        map = smooth(&map, true);
+    }
+    for _ in 0..3 {
+        map = smooth(&map, false);
+    }
+    keep_largest_region(&mut map);
+    find_rooms(&mut map);
+    map
+}
+
+pub fn generate<T: Rng>(width: i32, height: i32, _min_size: i32, rng: &mut T) -> Map {
+    let mut map = generate_once(width, height, rng);
+    let mut attempts = 1;
+    while map.rooms.len() < MIN_ROOMS && attempts < ATTEMPTS {
+        map = generate_once(width, height, rng);
+        attempts += 1;
+    }
+    if !map.rooms.is_empty() {
+        place_stairs(&mut map);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{XorShiftRng, SeedableRng};
+    use crate::caves::*;
+
+    #[test]
+    fn test_caves_are_connected() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([0, 1, 3, 4]);
+        let map = generate(80, 43, 6, &mut rng);
+        let start = map.rooms[0].center();
+        let mut regions = vec![0; map.data.len()];
+        let size = flood_fill(&map, start, &mut regions, 1);
+        assert_eq!(size, map.data.iter().filter(|v| v.is_passable()).count());
+    }
+
+    #[test]
+    fn test_rooms_and_stairs_are_on_floor() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([4, 3, 2, 1]);
+        let map = generate(80, 43, 6, &mut rng);
+        assert!(map.rooms.len() >= MIN_ROOMS);
+        for room in &map.rooms {
+            assert!(is_floor_rect(&map, room));
+        }
+        let stairs = map.stairs.unwrap();
+        assert_eq!(map.get(stairs.x, stairs.y), Some(Tile::Stairs));
+        assert!(stairs != map.rooms[0].center());
+    }
+}
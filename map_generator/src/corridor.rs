@@ -2,7 +2,7 @@ use rand::{Rng};
 use std::collections::{HashSet};
 
 use geo::{Point, Rect, get_neigbours};
-use super::Map;
+use super::map::{Map, Tile};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum SpawnDirection {
@@ -21,7 +21,7 @@ struct Generator<'a, T: Rng + 'a> {
 fn is_possible_connection(map: &Map, point: &Point) -> bool {
     let neighbours = get_neigbours(point.x, point.y, true);
     neighbours.iter().filter(|p| {
-        map.get(p.x, p.y) == Some(1)
+        map.get(p.x, p.y) == Some(Tile::Floor)
     }).count() == 1
 }
 
@@ -37,7 +37,7 @@ impl<'a, T: Rng> Generator<'a, T> {
     fn is_possible_connection(&self, point: &Point) -> bool {
         let neighbours = get_neigbours(point.x, point.y, true);
         neighbours.iter().filter(|p| {
-            self.map.get(p.x, p.y) == Some(1)
+            self.map.get(p.x, p.y) == Some(Tile::Floor)
         }).count() == 1
     }
 
@@ -75,7 +75,7 @@ impl<'a, T: Rng> Generator<'a, T> {
         let mut valid = true;
         for x in (room.x1 - 1)..=room.x2 {
             for y in (room.y1 - 1)..=room.y2 {
-                valid = valid && self.map.get(x, y) == Some(0);
+                valid = valid && self.map.get(x, y) == Some(Tile::Wall);
             }
         }
         valid
@@ -84,7 +84,7 @@ impl<'a, T: Rng> Generator<'a, T> {
     pub fn carve_room(&mut self, room: Rect) {
         for x in room.x1..room.x2 {
             for y in room.y1..room.y2 {
-                self.map.set(x, y, 1);
+                self.map.set(x, y, Tile::Floor);
             }
         }
 
@@ -150,7 +150,7 @@ impl<'a, T: Rng> Generator<'a, T> {
                 stairs = r.center();
             }
         });
-        self.map.stairs = Some(stairs);
+        self.map.set_stairs(stairs);
     }
 
     pub fn add_doors(&mut self) {
@@ -161,26 +161,30 @@ impl<'a, T: Rng> Generator<'a, T> {
                 doors.insert(d.clone());
             });
         });
-        self.map.doors = doors.into_iter().collect::<Vec<_>>();
-
+        // sets iterate in a different order every run
+        let mut doors: Vec<Point> = doors.into_iter().collect();
+        doors.sort_by_key(|pos| (pos.y, pos.x));
+        for door in doors {
+            self.map.add_door(door);
+        }
     }
 }
 
 pub fn get_doors(room: &Rect, map: &Map) -> Vec<Point> {
     let mut doors = vec![];
     for x in room.x1-1..=room.x2 {
-        if map.get(x, room.y1 - 1) == Some(1) {
+        if map.get(x, room.y1 - 1) == Some(Tile::Floor) {
             doors.push((x, room.y1 - 1).into())
         }
-        if map.get(x, room.y2) == Some(1) {
+        if map.get(x, room.y2) == Some(Tile::Floor) {
             doors.push((x, room.y2).into())
         }
     }
     for y in room.y1-1..=room.y2 {
-        if map.get(room.x1 - 1, y) == Some(1) {
+        if map.get(room.x1 - 1, y) == Some(Tile::Floor) {
             doors.push((room.x1 - 1, y).into())
         }
-        if map.get(room.x2, y) == Some(1) {
+        if map.get(room.x2, y) == Some(Tile::Floor) {
             doors.push((room.x2, y).into())
         }
     }
@@ -190,7 +194,15 @@ pub fn get_doors(room: &Rect, map: &Map) -> Vec<Point> {
 pub fn generate<T: Rng>(width: i32, height: i32, _min_size: i32, rng: &mut T) -> Map {
     let map = Map::new(width, height);
     let mut generator = Generator::new(rng, map);
-    generator.carve_room(Rect::new(10, 20, 30, 5));
+    // a long hall to start from, shrunk to fit on small maps
+    let seed_width = (width / 2).min(30);
+    let seed_height = (height / 3).min(5);
+    generator.carve_room(Rect::new(
+        (width - seed_width - 2).min(10),
+        (height - seed_height - 2).min(20),
+        seed_width,
+        seed_height
+    ));
     while let Some((spawn, mut room))= generator.next_room() {
         if generator.can_fit(&room) {
             // growing right
@@ -214,17 +226,17 @@ pub fn generate<T: Rng>(width: i32, height: i32, _min_size: i32, rng: &mut T) ->
                 r.grow(Point::new(0, s))
             });
             generator.carve_room(room);
-            generator.map.set(spawn.x, spawn.y, 1);
+            generator.map.set(spawn.x, spawn.y, Tile::Floor);
             let impossible_connections = generator.clean_connections();
 
             impossible_connections.iter().for_each(|p| {
                 if generator.rng.gen::<f32>() < 0.1 {
                     if get_neigbours(p.x, p.y, true)
                         .iter()
-                        .filter(|p| generator.map.get(p.x, p.y) == Some(1))
+                        .filter(|p| generator.map.get(p.x, p.y) == Some(Tile::Floor))
                         .count() == 2 {
                         if generator.map.get(p.x - 1, p.y) == generator.map.get(p.x + 1, p.y) {
-                            generator.map.set(p.x, p.y, 1);
+                            generator.map.set(p.x, p.y, Tile::Floor);
                         }
                     }
                 }
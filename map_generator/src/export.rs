@@ -0,0 +1,256 @@
+use serde_json::{self, json, Value};
+
+use geo::Point;
+use super::{Map, Feature, Tile};
+
+// Tiles of the Tiled tileset, in the order of their global ids.
+const TILE_SIZE: i32 = 16;
+const WALL_TILE: i32 = 1;
+const FLOOR_TILE: i32 = 2;
+const DOOR_TILE: i32 = 3;
+const STAIRS_TILE: i32 = 4;
+const WATER_TILE: i32 = 5;
+const DEEP_WATER_TILE: i32 = 6;
+const LAVA_TILE: i32 = 7;
+const CHASM_TILE: i32 = 8;
+const RUBBLE_TILE: i32 = 9;
+const TILE_COUNT: i32 = 9;
+const TILESET_IMAGE: &str = "dungeon.png";
+
+fn feature_glyph(feature: Feature) -> char {
+    match feature {
+        Feature::Monster => 'M',
+        Feature::Item => 'I',
+        Feature::Rune => 'R'
+    }
+}
+
+fn feature_name(feature: Feature) -> &'static str {
+    match feature {
+        Feature::Monster => "monster",
+        Feature::Item => "item",
+        Feature::Rune => "rune"
+    }
+}
+
+fn tile(map: &Map, pos: Point) -> i32 {
+    match map.get_or_wall(pos.x, pos.y) {
+        Tile::Wall => WALL_TILE,
+        Tile::Floor => FLOOR_TILE,
+        Tile::Door => DOOR_TILE,
+        Tile::Stairs => STAIRS_TILE,
+        Tile::Water => WATER_TILE,
+        Tile::DeepWater => DEEP_WATER_TILE,
+        Tile::Lava => LAVA_TILE,
+        Tile::Chasm => CHASM_TILE,
+        Tile::Rubble => RUBBLE_TILE
+    }
+}
+
+// Uses the same glyphs as the game and the vault templates.
+pub fn to_ascii(map: &Map) -> String {
+    let mut ascii = String::new();
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let pos = Point::new(x, y);
+            let glyph = match map.features.iter().find(|(p, _)| *p == pos) {
+                Some((_, feature)) => feature_glyph(*feature),
+                None => map.get_or_wall(x, y).glyph()
+            };
+            ascii.push(glyph);
+        }
+        ascii.push('\n');
+    }
+    ascii
+}
+
+pub fn to_json(map: &Map) -> String {
+    serde_json::to_string_pretty(map).unwrap_or_default()
+}
+
+fn tiles(map: &Map) -> Vec<i32> {
+    (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| Point::new(x, y)))
+        .map(|pos| tile(map, pos))
+        .collect()
+}
+
+struct TiledObject {
+    kind: &'static str,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32
+}
+
+impl TiledObject {
+    fn point(kind: &'static str, pos: Point) -> TiledObject {
+        TiledObject {
+            kind,
+            x: pos.x * TILE_SIZE + TILE_SIZE / 2,
+            y: pos.y * TILE_SIZE + TILE_SIZE / 2,
+            width: 0,
+            height: 0
+        }
+    }
+
+    fn is_point(&self) -> bool {
+        self.width == 0
+    }
+}
+
+// Rooms become rectangles, stairs and features points, in pixels as Tiled
+// expects.
+fn object_groups(map: &Map) -> Vec<(&'static str, Vec<TiledObject>)> {
+    let rooms = map.rooms
+        .iter()
+        .map(|room| TiledObject {
+            kind: "room",
+            x: room.x1 * TILE_SIZE,
+            y: room.y1 * TILE_SIZE,
+            width: room.width * TILE_SIZE,
+            height: room.height * TILE_SIZE
+        })
+        .collect();
+    let mut markers: Vec<TiledObject> = map.stairs.iter().map(|pos| TiledObject::point("stairs", *pos)).collect();
+    markers.extend(map.features.iter().map(|(pos, feature)| TiledObject::point(feature_name(*feature), *pos)));
+    vec![("rooms", rooms), ("features", markers)]
+}
+
+pub fn to_tiled_json(map: &Map) -> String {
+    let mut next_id = 1;
+    let mut layers = vec![json!({
+        "type": "tilelayer",
+        "id": 1,
+        "name": "tiles",
+        "x": 0,
+        "y": 0,
+        "width": map.width,
+        "height": map.height,
+        "opacity": 1,
+        "visible": true,
+        "data": tiles(map)
+    })];
+    for (index, (name, objects)) in object_groups(map).into_iter().enumerate() {
+        let objects: Vec<Value> = objects
+            .into_iter()
+            .map(|object| {
+                next_id += 1;
+                json!({
+                    "id": next_id - 1,
+                    "name": "",
+                    "type": object.kind,
+                    "x": object.x,
+                    "y": object.y,
+                    "width": object.width,
+                    "height": object.height,
+                    "point": object.is_point(),
+                    "rotation": 0,
+                    "visible": true
+                })
+            })
+            .collect();
+        layers.push(json!({
+            "type": "objectgroup",
+            "id": index + 2,
+            "name": name,
+            "draworder": "topdown",
+            "x": 0,
+            "y": 0,
+            "opacity": 1,
+            "visible": true,
+            "objects": objects
+        }));
+    }
+    let tiled = json!({
+        "type": "map",
+        "version": "1.2",
+        "orientation": "orthogonal",
+        "renderorder": "right-down",
+        "infinite": false,
+        "width": map.width,
+        "height": map.height,
+        "tilewidth": TILE_SIZE,
+        "tileheight": TILE_SIZE,
+        "nextlayerid": layers.len() + 1,
+        "nextobjectid": next_id,
+        "layers": layers,
+        "tilesets": [{
+            "firstgid": 1,
+            "name": "dungeon",
+            "tilewidth": TILE_SIZE,
+            "tileheight": TILE_SIZE,
+            "tilecount": TILE_COUNT,
+            "columns": TILE_COUNT,
+            "margin": 0,
+            "spacing": 0,
+            "image": TILESET_IMAGE,
+            "imagewidth": TILE_SIZE * TILE_COUNT,
+            "imageheight": TILE_SIZE
+        }]
+    });
+    serde_json::to_string_pretty(&tiled).unwrap_or_default()
+}
+
+pub fn to_tmx(map: &Map) -> String {
+    let mut tmx = String::new();
+    let groups = object_groups(map);
+    let object_count: usize = groups.iter().map(|(_, objects)| objects.len()).sum();
+    tmx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    tmx.push_str(&format!(
+        "<map version=\"1.2\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n",
+        map.width, map.height, TILE_SIZE, TILE_SIZE, groups.len() + 2, object_count + 1
+    ));
+    tmx.push_str(&format!(
+        " <tileset firstgid=\"1\" name=\"dungeon\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">\n  <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n </tileset>\n",
+        TILE_SIZE, TILE_SIZE, TILE_COUNT, TILE_COUNT, TILESET_IMAGE, TILE_SIZE * TILE_COUNT, TILE_SIZE
+    ));
+    tmx.push_str(&format!(" <layer id=\"1\" name=\"tiles\" width=\"{}\" height=\"{}\">\n  <data encoding=\"csv\">\n", map.width, map.height));
+    let rows: Vec<String> = tiles(map)
+        .chunks(map.width as usize)
+        .map(|row| row.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(","))
+        .collect();
+    tmx.push_str(&rows.join(",\n"));
+    tmx.push_str("\n  </data>\n </layer>\n");
+    let mut id = 1;
+    for (index, (name, objects)) in groups.into_iter().enumerate() {
+        tmx.push_str(&format!(" <objectgroup id=\"{}\" name=\"{}\">\n", index + 2, name));
+        for object in objects {
+            if object.is_point() {
+                tmx.push_str(&format!("  <object id=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\">\n   <point/>\n  </object>\n", id, object.kind, object.x, object.y));
+            } else {
+                tmx.push_str(&format!("  <object id=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n", id, object.kind, object.x, object.y, object.width, object.height));
+            }
+            id += 1;
+        }
+        tmx.push_str(" </objectgroup>\n");
+    }
+    tmx.push_str("</map>\n");
+    tmx
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Rect;
+    use crate::export::*;
+
+    #[test]
+    fn test_ascii_and_tiles_agree() {
+        let mut map = Map::new(5, 3);
+        for x in 1..4 {
+            map.set(x, 1, Tile::Floor);
+        }
+        map.add_room(Rect::new(1, 1, 2, 1));
+        map.add_door(Point::new(3, 1));
+        map.set_stairs(Point::new(1, 1));
+        map.features.push((Point::new(2, 1), Feature::Item));
+        assert_eq!(to_ascii(&map), "#####\n#<I+#\n#####\n");
+        assert_eq!(&tiles(&map)[5..10], &[WALL_TILE, STAIRS_TILE, FLOOR_TILE, DOOR_TILE, WALL_TILE]);
+        let tmx = to_tmx(&map);
+        assert!(tmx.contains("1,1,1,1,1,\n1,4,2,3,1,\n1,1,1,1,1\n"));
+        assert!(tmx.contains("<object id=\"1\" type=\"room\" x=\"16\" y=\"16\" width=\"32\" height=\"16\"/>"));
+        let tiled: Value = serde_json::from_str(&to_tiled_json(&map)).unwrap();
+        assert_eq!(tiled["layers"][2]["objects"][1]["type"], "item");
+        assert_eq!(tiled["nextobjectid"], 4);
+    }
+}
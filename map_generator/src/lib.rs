@@ -2,12 +2,20 @@
 
 extern crate rand;
 extern crate geo;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+extern crate serde_json;
 
 pub mod tower;
 pub mod bsp;
 pub mod roomsy;
 pub mod corridor;
+pub mod caves;
+pub mod vaults;
+pub mod validate;
+pub mod export;
+pub mod terrain;
 mod map;
 
 
-pub use crate::map::Map;
+pub use crate::map::{Map, Feature, Tile};
@@ -2,10 +2,236 @@ extern crate rand;
 extern crate geo;
 extern crate map_generator;
 
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
 use rand::{XorShiftRng, SeedableRng};
 
+use map_generator::{Map, bsp, tower, roomsy, corridor, caves};
+use map_generator::{export, validate};
+
+type Generate = fn(i32, i32, i32, &mut XorShiftRng) -> Map;
+
+const GENERATORS: [(&str, Generate); 5] = [
+    ("bsp", bsp::generate),
+    ("tower", tower::generate),
+    ("roomsy", roomsy::generate),
+    ("corridor", corridor::generate),
+    ("caves", caves::generate)
+];
+
+const USAGE: &str = "usage: map_generator [options]
+
+  --generator NAME   bsp, tower, roomsy, corridor or caves (default roomsy)
+  --width N          map width, at least 6 (default 60)
+  --height N         map height, at least 6 (default 45)
+  --min-size N       smallest room size, at least 2 (default 6)
+  --seed N           seed of the first map (default random)
+  --count N          number of maps, seeded with seed, seed + 1 and so on
+                     (default 1)
+  --format FORMAT    ascii, json, tmx, tiled or none (default ascii)
+  --out PATH         write maps to PATH instead of standard output, with the
+                     seed added to the file name when there is more than one
+  --help             show this message
+
+Statistics of every map are printed to standard error.";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    Json,
+    Tmx,
+    Tiled,
+    None
+}
+
+impl Format {
+    fn export(self, map: &Map) -> String {
+        match self {
+            Format::Ascii => export::to_ascii(map),
+            Format::Json => export::to_json(map),
+            Format::Tmx => export::to_tmx(map),
+            Format::Tiled => export::to_tiled_json(map),
+            Format::None => String::new()
+        }
+    }
+}
+
+const FORMAT_NAMES: [(&str, Format); 5] = [
+    ("ascii", Format::Ascii),
+    ("json", Format::Json),
+    ("tmx", Format::Tmx),
+    ("tiled", Format::Tiled),
+    ("none", Format::None)
+];
+
+struct Options {
+    generator: &'static str,
+    generate: Generate,
+    width: i32,
+    height: i32,
+    min_size: i32,
+    seed: u32,
+    count: u32,
+    format: Format,
+    out: Option<String>
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got \"{}\"", option, value))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        generator: "roomsy",
+        generate: roomsy::generate,
+        width: 60,
+        height: 45,
+        min_size: 6,
+        seed: rand::random(),
+        count: 1,
+        format: Format::Ascii,
+        out: None
+    };
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let value = match args.next() {
+            Some(value) => value,
+            None => return Err(format!("{} expects a value", option))
+        };
+        match option.as_str() {
+            "--generator" => {
+                let (name, generate) = GENERATORS
+                    .iter()
+                    .find(|(name, _)| name == value)
+                    .ok_or_else(|| format!("unknown generator \"{}\"", value))?;
+                options.generator = name;
+                options.generate = *generate;
+            },
+            "--width" => options.width = number(option, value)?,
+            "--height" => options.height = number(option, value)?,
+            "--min-size" => options.min_size = number(option, value)?,
+            "--seed" => options.seed = number(option, value)?,
+            "--count" => options.count = number(option, value)?,
+            "--format" => {
+                options.format = FORMAT_NAMES
+                    .iter()
+                    .find(|(name, _)| name == value)
+                    .map(|(_, format)| *format)
+                    .ok_or_else(|| format!("unknown format \"{}\"", value))?;
+            },
+            "--out" => options.out = Some(value.to_string()),
+            _ => return Err(format!("unknown option {}", option))
+        }
+    }
+    if options.width < 6 || options.height < 6 || options.min_size < 2 {
+        return Err("maps must be at least 6 by 6 with rooms of at least 2".to_string());
+    }
+    if options.count > 1 && options.out.is_none() && options.format != Format::Ascii && options.format != Format::None {
+        return Err("more than one map needs --out unless the format is ascii".to_string());
+    }
+    Ok(options)
+}
+
+struct Stats {
+    rooms: usize,
+    doors: usize,
+    floor_ratio: f32,
+    corridor_length: usize,
+    problems: Vec<validate::Problem>
+}
+
+impl Stats {
+    // Corridors are the floor tiles outside of every room, doorways included.
+    fn new(map: &Map) -> Stats {
+        let in_room = |x: i32, y: i32| map.rooms.iter().any(|r| x >= r.x1 && x < r.x2 && y >= r.y1 && y < r.y2);
+        let mut floor = 0;
+        let mut corridor_length = 0;
+        for x in 0..map.width {
+            for y in 0..map.height {
+                if map.get_or_wall(x, y).is_passable() {
+                    floor += 1;
+                    if !in_room(x, y) {
+                        corridor_length += 1;
+                    }
+                }
+            }
+        }
+        Stats {
+            rooms: map.rooms.len(),
+            doors: map.doors.len(),
+            floor_ratio: floor as f32 / map.data.len() as f32,
+            corridor_length,
+            problems: validate::validate(map)
+        }
+    }
+}
+
+// Adds the seed to the file name, "maps/cave.tmx" becomes "maps/cave-12.tmx".
+fn numbered_path(out: &str, seed: u32) -> String {
+    let path = Path::new(out);
+    let stem = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, seed, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, seed)
+    };
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
 fn main() {
-    let mut rng: XorShiftRng = SeedableRng::from_seed([0, 1, 3, 4]);
-    let map = map_generator::roomsy::generate(40, 20, 4, &mut rng);
-    println!("{:?}", map);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut all_stats = vec![];
+    for seed in (0..options.count).map(|i| options.seed.wrapping_add(i)) {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([seed, 0x5eed, 3, 4]);
+        let map = (options.generate)(options.width, options.height, options.min_size, &mut rng);
+
+        let output = options.format.export(&map);
+        match options.out {
+            _ if options.format == Format::None => {},
+            Some(ref out) => {
+                let path = if options.count > 1 { numbered_path(out, seed) } else { out.to_string() };
+                if let Err(err) = fs::write(&path, output) {
+                    eprintln!("error: could not write {}: {}", path, err);
+                    process::exit(1);
+                }
+            },
+            None => println!("{}", output)
+        }
+
+        let stats = Stats::new(&map);
+        eprintln!(
+            "{} seed {}: {} rooms, {} doors, {:.1}% floor, {} corridor tiles",
+            options.generator, seed, stats.rooms, stats.doors, stats.floor_ratio * 100.0, stats.corridor_length
+        );
+        for problem in &stats.problems {
+            eprintln!("  {}", problem);
+        }
+        all_stats.push(stats);
+    }
+
+    if all_stats.len() > 1 {
+        let count = all_stats.len() as f32;
+        eprintln!(
+            "{} maps: {:.1} rooms, {:.1} doors, {:.1}% floor, {:.1} corridor tiles on average, {} with problems",
+            all_stats.len(),
+            all_stats.iter().map(|s| s.rooms as f32).sum::<f32>() / count,
+            all_stats.iter().map(|s| s.doors as f32).sum::<f32>() / count,
+            all_stats.iter().map(|s| s.floor_ratio).sum::<f32>() / count * 100.0,
+            all_stats.iter().map(|s| s.corridor_length as f32).sum::<f32>() / count,
+            all_stats.iter().filter(|s| !s.problems.is_empty()).count()
+        );
+    }
 }
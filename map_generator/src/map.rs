@@ -1,27 +1,72 @@
 use std::fmt;
 use geo::{Rect, Point};
 
-#[derive(Clone, PartialEq)]
+// Contents the game should spawn at a position, placed by vaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Feature {
+    Monster,
+    Item,
+    Rune
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Tile {
+    Wall,
+    Floor,
+    Door,
+    Stairs,
+    Water,
+    DeepWater,
+    Lava,
+    Chasm,
+    Rubble
+}
+
+impl Tile {
+    // Tiles anyone can walk over without coming to harm.
+    pub fn is_passable(self) -> bool {
+        match self {
+            Tile::Floor | Tile::Door | Tile::Stairs | Tile::Water | Tile::Rubble => true,
+            Tile::Wall | Tile::DeepWater | Tile::Lava | Tile::Chasm => false
+        }
+    }
+
+    pub fn glyph(self) -> char {
+        match self {
+            Tile::Wall => '#',
+            Tile::Floor => '.',
+            Tile::Door => '+',
+            Tile::Stairs => '<',
+            Tile::Water => '~',
+            Tile::DeepWater => 'w',
+            Tile::Lava => '=',
+            Tile::Chasm => ':',
+            Tile::Rubble => '%'
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Map {
     pub width: i32,
     pub height: i32,
     pub stairs: Option<Point>,
-    pub data: Vec<i32>,
+    pub data: Vec<Tile>,
     pub rooms: Vec<Rect>,
-    pub doors: Vec<Point>
+    pub doors: Vec<Point>,
+    pub features: Vec<(Point, Feature)>
 }
 
 impl Map {
     pub fn new(width: i32, height: i32) -> Map {
-        let mut data: Vec<i32> = vec![];
-        data.resize((width * height) as usize, 0);
         Map {
             width,
             height,
-            data,
+            data: vec![Tile::Wall; (width * height) as usize],
             stairs: None,
             rooms: vec![],
-            doors: vec![]
+            doors: vec![],
+            features: vec![]
         }
     }
 
@@ -30,12 +75,17 @@ impl Map {
     }
 
     pub fn set_stairs(&mut self, pos: Point) {
+        self.set(pos.x, pos.y, Tile::Stairs);
         self.stairs = Some(pos);
     }
 
+    pub fn add_door(&mut self, pos: Point) {
+        self.set(pos.x, pos.y, Tile::Door);
+        self.doors.push(pos);
+    }
+
     pub fn pad_map(map: Map) -> Map {
-        let mut data: Vec<i32> = vec![];
-        data.resize(((map.width + 2) * (map.height + 2)) as usize, 0);
+        let mut data = vec![Tile::Wall; ((map.width + 2) * (map.height + 2)) as usize];
         for x in 0..map.width {
             for y in 0..map.height {
                 match map.get(x, y) {
@@ -50,13 +100,17 @@ impl Map {
         let doors = map.doors.iter().map(|door| {
             Point::new(door.x + 1, door.y + 1)
         }).collect();
+        let features = map.features.iter().map(|(pos, feature)| {
+            (Point::new(pos.x + 1, pos.y + 1), *feature)
+        }).collect();
         Map {
             width: map.width + 2,
             height: map.height + 2,
-            stairs: map.stairs,
+            stairs: map.stairs.map(|stairs| Point::new(stairs.x + 1, stairs.y + 1)),
             data,
             rooms,
             doors,
+            features,
         }
     }
 
@@ -68,7 +122,7 @@ impl Map {
         self.rooms.push(room);
     }
 
-    pub fn get(&self, x: i32, y: i32) -> Option<i32> {
+    pub fn get(&self, x: i32, y: i32) -> Option<Tile> {
         let index = self.to_index(x, y);
         if index < self.data.len() {
             Some(self.data[self.to_index(x, y)])
@@ -77,7 +131,16 @@ impl Map {
         }
     }
 
-    pub fn set(&mut self, x: i32, y: i32, v: i32) {
+    // Like `get`, but everything outside of the map is wall.
+    pub fn get_or_wall(&self, x: i32, y: i32) -> Tile {
+        if self.in_bounds(x, y) {
+            self.data[self.to_index(x, y)]
+        } else {
+            Tile::Wall
+        }
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, v: Tile) {
         let index = self.to_index(x, y);
         self.data[index] = v;
     }
@@ -85,26 +148,12 @@ impl Map {
 
 impl fmt::Debug for Map {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use std::char;
-        let mut map = "".to_string();
         for y in 0..self.height {
-            map = format!("{}\n", map);
+            writeln!(f)?;
             for x in 0..self.width {
-                let c = self.data[self.to_index(x, y)];
-                if c == 0 {
-                    map = format!("{}.", map);
-                } else if c < 10 {
-                    map = format!("{}{}", map, c);
-                } else if c < 37 {
-                    match char::from_u32((c + 55) as u32) {
-                        Some(d) => map = format!("{}{}", map, d),
-                        None => map = format!("{}?", map)
-                    }
-                } else {
-                    map = format!("{}?", map);
-                }
+                write!(f, "{}", self.data[self.to_index(x, y)].glyph())?;
             }
         }
-        write!(f, "{}", map)
+        Ok(())
     }
 }
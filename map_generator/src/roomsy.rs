@@ -1,9 +1,14 @@
 use rand::{Rng};
+use std::collections::HashSet;
 
-use geo::{Point};
-use super::Map;
+use geo::{Point, Rect};
+use super::{Map, Tile};
 
-#[derive(Debug)]
+const MAX_ROOMS: usize = 40;
+const FILL_RATIO: f32 = 0.45;
+const MAX_FAILURES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Orientation {
     Up,
     Down,
@@ -12,113 +17,215 @@ enum Orientation {
 }
 
 impl Orientation {
+    // The direction a room attached at the doorway would grow in, which is
+    // away from the room the doorway belongs to.
     fn from_doorway(doorway: Point, map: &RoomsyMap) -> Option<Orientation> {
-        let left = map.map.get(doorway.x - 1, doorway.y)?;
-        if left == 1 {
+        let open = |x: i32, y: i32| map.map.get_or_wall(x, y).is_passable();
+        if open(doorway.x - 1, doorway.y) && !open(doorway.x + 1, doorway.y) {
             return Some(Orientation::Right);
         }
-        let right = map.map.get(doorway.x + 1, doorway.y)?;
-        if right == 1 {
+        if open(doorway.x + 1, doorway.y) && !open(doorway.x - 1, doorway.y) {
             return Some(Orientation::Left);
         }
-        let up = map.map.get(doorway.x, doorway.y - 1)?;
-        if up == 1 {
+        if open(doorway.x, doorway.y - 1) && !open(doorway.x, doorway.y + 1) {
             return Some(Orientation::Down);
         }
-        let down = map.map.get(doorway.x, doorway.y + 1)?;
-        if down == 1 {
+        if open(doorway.x, doorway.y + 1) && !open(doorway.x, doorway.y - 1) {
             return Some(Orientation::Up);
         }
         None
     }
 }
 
+// Walls around rooms are claimed as doorways so new rooms can be attached to
+// them, and no other room is carved through them.
 #[derive(Debug)]
 struct RoomsyMap {
     map: Map,
-    doorways: Vec<Point>
+    doorways: Vec<Point>,
+    claimed: HashSet<Point>
 }
 
 impl RoomsyMap {
     fn new(width: i32, height: i32) -> Self {
         RoomsyMap {
             map: Map::new(width, height),
-            doorways: vec![]
+            doorways: vec![],
+            claimed: HashSet::new()
         }
     }
-}
 
-fn add_left_room(doorway: Point, width: i32, height: i32, map: &mut RoomsyMap) {
-    let left = doorway.x - width;
-    let top = doorway.y - 3;
-    for x in left..doorway.x {
-        for y in top..(top + width) {
-            map.map.set(x, y, 1);
+    fn floor_ratio(&self) -> f32 {
+        let floor = self.map.data.iter().filter(|v| v.is_passable()).count();
+        floor as f32 / self.map.data.len() as f32
+    }
+
+    // A room fits when it stays off the map edge, only covers unused tiles
+    // and its walls don't cut into another room. Walls may be shared.
+    fn can_fit(&self, room: &Rect) -> bool {
+        if room.x1 < 1 || room.y1 < 1 || room.x2 >= self.map.width || room.y2 >= self.map.height {
+            return false;
+        }
+        for x in (room.x1 - 1)..=room.x2 {
+            for y in (room.y1 - 1)..=room.y2 {
+                let inside = x >= room.x1 && x < room.x2 && y >= room.y1 && y < room.y2;
+                let v = self.map.get_or_wall(x, y);
+                let unused = v == Tile::Wall && !self.claimed.contains(&Point::new(x, y));
+                if (inside && !unused) || (!inside && v.is_passable()) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn carve_room(&mut self, room: Rect) {
+        for x in room.x1..room.x2 {
+            for y in room.y1..room.y2 {
+                self.map.set(x, y, Tile::Floor);
+            }
+        }
+        // corners never become doorways
+        for x in room.x1..room.x2 {
+            self.add_doorway(Point::new(x, room.y1 - 1));
+            self.add_doorway(Point::new(x, room.y2));
+        }
+        for y in room.y1..room.y2 {
+            self.add_doorway(Point::new(room.x1 - 1, y));
+            self.add_doorway(Point::new(room.x2, y));
+        }
+        self.map.add_room(room);
+    }
+
+    fn add_doorway(&mut self, pos: Point) {
+        if self.map.in_bounds(pos.x, pos.y) && self.claimed.insert(pos) {
+            self.doorways.push(pos);
         }
     }
-    add_horizontal_doorways(top - 1, left, width, map);
-    add_horizontal_doorways(top + height, left, width, map);
-    add_vertical_doorways(left - 1, top, height, map);
-    add_vertical_doorways(left + width, top, height, map);
-}
 
-fn add_horizontal_doorways(y: i32, left: i32, width: i32, map: &mut RoomsyMap) {
-    for x in left..(left + width) {
-        map.map.set(x, y, 2);
-        map.doorways.push(Point::new(x, y));
+    fn open_door(&mut self, pos: Point) {
+        self.map.add_door(pos);
     }
 }
 
-fn add_vertical_doorways(x: i32, top: i32, height: i32, map: &mut RoomsyMap) {
-    for y in top..(top + height) {
-        map.map.set(x, y, 3);
-        map.doorways.push(Point::new(x, y));
+fn random_size<T: Rng>(min_size: i32, rng: &mut T) -> i32 {
+    rng.gen_range(min_size, min_size + 5)
+}
+
+// Places a room on the far side of the doorway, shifted sideways by a random
+// amount so the door doesn't always end up in the middle of a wall.
+fn room_at<T: Rng>(doorway: Point, orientation: Orientation, min_size: i32, rng: &mut T) -> Rect {
+    let width = random_size(min_size, rng);
+    let height = random_size(min_size, rng);
+    match orientation {
+        Orientation::Left => Rect::new(doorway.x - width, doorway.y - rng.gen_range(0, height), width, height),
+        Orientation::Right => Rect::new(doorway.x + 1, doorway.y - rng.gen_range(0, height), width, height),
+        Orientation::Up => Rect::new(doorway.x - rng.gen_range(0, width), doorway.y - height, width, height),
+        Orientation::Down => Rect::new(doorway.x - rng.gen_range(0, width), doorway.y + 1, width, height)
     }
 }
 
-fn add_room<T: Rng>(map: &mut RoomsyMap, rng: &mut T) {
-    let mut doorways = vec![];
-    for x in 0..map.map.width {
-        for y in 0..map.map.height {
-            if map.map.get(x, y) == Some(2) {
-                doorways.push(Point::new(x, y));
-            }
+fn add_room<T: Rng>(map: &mut RoomsyMap, min_size: i32, rng: &mut T) -> bool {
+    if map.doorways.is_empty() {
+        return false;
+    }
+    let index = rng.gen_range(0, map.doorways.len());
+    let doorway = map.doorways[index];
+    let orientation = match Orientation::from_doorway(doorway, map) {
+        Some(orientation) => orientation,
+        None => {
+            // the wall is now between two rooms, or already a door
+            map.doorways.swap_remove(index);
+            return false;
         }
+    };
+    let room = room_at(doorway, orientation, min_size, rng);
+    if !map.can_fit(&room) {
+        return false;
     }
+    map.carve_room(room);
+    map.open_door(doorway);
+    map.doorways.swap_remove(index);
+    true
+}
 
-    match rng.choose(doorways.as_slice()) {
-        Some(doorway) => {
-            let width = 5;
-            let height = 5;
-            match Orientation::from_doorway(*doorway, map) {
-                Some(orientation) => {
-                    match orientation {
-                        Orientation::Left => add_left_room(*doorway, width, height, map),
-                        _ => {}
-                    }
-                },
-                None => {}
-            }
-        },
-        None => {}
-    }
+fn seed<T: Rng>(map: &mut RoomsyMap, min_size: i32, rng: &mut T) {
+    // the first room has to fit inside the outer wall
+    let width = random_size(min_size, rng).min(map.map.width - 2);
+    let height = random_size(min_size, rng).min(map.map.height - 2);
+    let x = map.map.width / 2 - width / 2;
+    let y = map.map.height / 2 - height / 2;
+    map.carve_room(Rect::new(x, y, width, height));
+}
+
+fn count_doors(room: &Rect, map: &Map) -> usize {
+    map.doors
+        .iter()
+        .filter(|d| d.x >= room.x1 - 1 && d.x <= room.x2 && d.y >= room.y1 - 1 && d.y <= room.y2)
+        .count()
 }
 
-fn seed(map: &mut RoomsyMap) {
-    let x = map.map.width / 2;
-    let y = map.map.height / 2;
-    for i in x-1..=x+1 {
-        for j in y-3..=y+3 {
-            map.map.set(i, j, 1);
+// Like the corridor generator, stairs go in the dead end room farthest from
+// the start.
+fn place_stairs(map: &mut Map) {
+    let start = map.rooms[0].center();
+    let mut stairs = start;
+    for room in map.rooms.iter().skip(1) {
+        if count_doors(room, map) == 1 && room.center().distance(start) > stairs.distance(start) {
+            stairs = room.center();
+        }
+    }
+    if stairs == start {
+        if let Some(room) = map.rooms.iter().skip(1).max_by_key(|r| r.center().tile_distance(start)) {
+            stairs = room.center();
         }
     }
-    map.map.set(x-2, y, 2);
-    map.doorways.push(Point::new(x-2, y));
+    map.set_stairs(stairs);
 }
 
 pub fn generate<T: Rng>(width: i32, height: i32, min_size: i32, rng: &mut T) -> Map {
     let mut map = RoomsyMap::new(width, height);
-    seed(&mut map);
-    add_room(&mut map, rng);
+    seed(&mut map, min_size, rng);
+    let mut failures = 0;
+    while map.map.rooms.len() < MAX_ROOMS && map.floor_ratio() < FILL_RATIO && failures < MAX_FAILURES {
+        if !add_room(&mut map, min_size, rng) {
+            failures += 1;
+        }
+    }
+    place_stairs(&mut map.map);
     map.map
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{XorShiftRng, SeedableRng};
+    use crate::roomsy::*;
+
+    #[test]
+    fn test_rooms_do_not_overlap() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([0, 1, 3, 4]);
+        let map = generate(80, 43, 4, &mut rng);
+        assert!(map.rooms.len() > 5);
+        for (i, a) in map.rooms.iter().enumerate() {
+            for b in map.rooms.iter().skip(i + 1) {
+                let overlap = a.x1 < b.x2 && b.x1 < a.x2 && a.y1 < b.y2 && b.y1 < a.y2;
+                assert!(!overlap, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_doors_connect_rooms() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([4, 3, 2, 1]);
+        let map = generate(80, 43, 4, &mut rng);
+        assert_eq!(map.doors.len(), map.rooms.len() - 1);
+        for door in &map.doors {
+            let horizontal = map.get(door.x - 1, door.y) == Some(Tile::Floor) && map.get(door.x + 1, door.y) == Some(Tile::Floor);
+            let vertical = map.get(door.x, door.y - 1) == Some(Tile::Floor) && map.get(door.x, door.y + 1) == Some(Tile::Floor);
+            assert!(horizontal || vertical);
+        }
+        let stairs = map.stairs.unwrap();
+        assert_eq!(map.get(stairs.x, stairs.y), Some(Tile::Stairs));
+        assert!(stairs != map.rooms[0].center());
+    }
+}
@@ -0,0 +1,124 @@
+use rand::{Rng};
+
+use geo::{Point, Rect};
+use super::{Map, Tile};
+
+const ATTEMPTS: usize = 20;
+
+// A pool takes up one half of a room, away from the walls and the centre.
+// The ring along the walls stays floor, so every doorway of the room is
+// still connected, and the centre is left for whatever the game puts there.
+fn pool_area<T: Rng>(room: &Rect, rng: &mut T) -> Option<Rect> {
+    let center = room.center();
+    let (x1, y1, x2, y2) = (room.x1 + 1, room.y1 + 1, room.x2 - 1, room.y2 - 1);
+    let area = if room.width >= room.height {
+        let (from, to) = if rng.gen() { (x1, center.x) } else { (center.x + 1, x2) };
+        Rect::new(from, y1, to - from, y2 - y1)
+    } else {
+        let (from, to) = if rng.gen() { (y1, center.y) } else { (center.y + 1, y2) };
+        Rect::new(x1, from, x2 - x1, to - from)
+    };
+    if area.width >= 2 && area.height >= 2 {
+        Some(area)
+    } else {
+        None
+    }
+}
+
+fn is_free(map: &Map, pos: Point) -> bool {
+    map.get_or_wall(pos.x, pos.y) == Tile::Floor && !map.features.iter().any(|(p, _)| *p == pos)
+}
+
+// Deep water is edged with shallow water, so it can be waded into.
+fn add_pool<T: Rng>(map: &mut Map, tile: Tile, rng: &mut T) -> bool {
+    if map.rooms.len() < 3 {
+        return false;
+    }
+    for _ in 0..ATTEMPTS {
+        // neither the first room, where the player starts, nor the stairs
+        let room = map.rooms[rng.gen_range(1, map.rooms.len())];
+        if map.stairs.map_or(false, |stairs| room.x1 <= stairs.x && stairs.x < room.x2 && room.y1 <= stairs.y && stairs.y < room.y2) {
+            continue;
+        }
+        let area = match pool_area(&room, rng) {
+            Some(area) => area,
+            None => continue
+        };
+        let cells: Vec<Point> = (area.x1..area.x2)
+            .flat_map(|x| (area.y1..area.y2).map(move |y| Point::new(x, y)))
+            .collect();
+        if !cells.iter().all(|p| is_free(map, *p)) {
+            continue;
+        }
+        for p in cells {
+            let edge = p.x == area.x1 || p.y == area.y1 || p.x == area.x2 - 1 || p.y == area.y2 - 1;
+            let corner = (p.x == area.x1 || p.x == area.x2 - 1) && (p.y == area.y1 || p.y == area.y2 - 1);
+            if corner && area.width > 2 && area.height > 2 {
+                continue;
+            }
+            let tile = if tile == Tile::DeepWater && edge { Tile::Water } else { tile };
+            map.set(p.x, p.y, tile);
+        }
+        return true;
+    }
+    false
+}
+
+fn add_rubble<T: Rng>(map: &mut Map, rng: &mut T) -> bool {
+    for _ in 0..ATTEMPTS {
+        let pos = Point::new(rng.gen_range(0, map.width), rng.gen_range(0, map.height));
+        if is_free(map, pos) {
+            map.set(pos.x, pos.y, Tile::Rubble);
+            return true;
+        }
+    }
+    false
+}
+
+// Adds up to `count` pools of water, lava or chasm to the rooms, or `count`
+// tiles of rubble anywhere on the floor. Returns how many were placed.
+pub fn add_terrain<T: Rng>(map: &mut Map, tile: Tile, count: u32, rng: &mut T) -> u32 {
+    let mut placed = 0;
+    for _ in 0..count {
+        let added = match tile {
+            Tile::Rubble => add_rubble(map, rng),
+            Tile::Water | Tile::DeepWater | Tile::Lava | Tile::Chasm => add_pool(map, tile, rng),
+            Tile::Wall | Tile::Floor | Tile::Door | Tile::Stairs => false
+        };
+        if added {
+            placed += 1;
+        }
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{XorShiftRng, SeedableRng};
+    use crate::{roomsy, caves};
+    use crate::validate::validate;
+    use crate::terrain::*;
+
+    #[test]
+    fn test_terrain_keeps_map_valid() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([3, 1, 4, 1]);
+        for i in 0..20 {
+            let mut map = if i % 2 == 0 {
+                roomsy::generate(60, 45, 6, &mut rng)
+            } else {
+                caves::generate(60, 45, 6, &mut rng)
+            };
+            let mut pools = 0;
+            for tile in &[Tile::Lava, Tile::Chasm, Tile::DeepWater] {
+                pools += add_terrain(&mut map, *tile, 2, &mut rng);
+            }
+            assert_eq!(add_terrain(&mut map, Tile::Rubble, 10, &mut rng), 10);
+            assert!(pools > 0);
+            assert_eq!(validate(&map), vec![]);
+            for room in &map.rooms {
+                let center = room.center();
+                assert!(map.get_or_wall(center.x, center.y).is_passable());
+            }
+        }
+    }
+}
@@ -4,27 +4,57 @@ use geo::{Rect, Point, get_neigbours};
 use super::map::Map;
 use super::bsp::{Leaf, carve, split};
 
+// Every room starts out as its own region, 0 is rock. Region 1 grows as
+// doors connect the others to it.
 struct ConnectionMap {
     pub area_count: u32,
-    pub current: u32,
-    pub map: Map
+    pub map: Map,
+    regions: Vec<i32>
 }
 
 impl ConnectionMap {
     fn new(base_map: &Map) -> Self {
-        let mut map = base_map.clone();
-        for i in 1..=map.rooms.len() {
-            let room = map.rooms[i - 1];
-            for x in 0..room.width {
-                for y in 0..room.height {
-                    map.set(x + room.x1, y + room.y1, i as i32);
+        let map = base_map.clone();
+        let mut regions = vec![0; map.data.len()];
+        for (i, room) in map.rooms.iter().enumerate() {
+            for x in room.x1..room.x2 {
+                for y in room.y1..room.y2 {
+                    regions[(y * map.width + x) as usize] = i as i32 + 1;
                 }
             }
         }
         ConnectionMap {
             area_count: map.rooms.len() as u32,
-            current: 1,
-            map
+            map,
+            regions
+        }
+    }
+
+    fn region(&self, x: i32, y: i32) -> Option<i32> {
+        if self.map.in_bounds(x, y) {
+            Some(self.regions[(y * self.map.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    fn connections(&self, x: i32, y: i32) -> Vec<i32> {
+        get_neigbours(x, y, true).iter().filter_map(|pos| {
+            match self.region(pos.x, pos.y) {
+                Some(v) if v != 0 => Some(v),
+                _ => None
+            }
+        }).collect()
+    }
+
+    fn connect(&mut self, x: i32, y: i32, region: i32) {
+        self.map.add_door(Point::new(x, y));
+        let index = (y * self.map.width + x) as usize;
+        self.regions[index] = 1;
+        for v in self.regions.iter_mut() {
+            if *v == region {
+                *v = 1;
+            }
         }
     }
 
@@ -32,16 +62,10 @@ impl ConnectionMap {
         let mut connection_coords: Vec<(i32, i32, i32)> = vec![];
         for x in 0..self.map.width {
             for y in 0..self.map.height {
-                if self.map.get(x, y) == Some(0) {
-                    let neighbours = get_neigbours(x as i32, y as i32, true);
-                    let connections = neighbours.iter().filter_map(|pos| {
-                        match self.map.get(pos.x, pos.y) {
-                            Some(v) if v != 0 => Some(v),
-                            _ => None
-                        }
-                    }).collect::<Vec<_>>();
+                if self.region(x, y) == Some(0) {
+                    let connections = self.connections(x, y);
                     if connections.len() == 2 {
-                        let has_one_self = connections.iter().filter(|v| **v == 1).collect::<Vec<_>>().len() == 1;
+                        let has_one_self = connections.iter().filter(|v| **v == 1).count() == 1;
                         let fst = connections[0];
                         let snd = connections[1];
                         if has_one_self {
@@ -55,43 +79,14 @@ impl ConnectionMap {
                 }
             }
         }
-        match rng.choose(connection_coords.as_slice()) {
-            Some((x, y, v)) => {
-                self.map.set(*x, *y, 1);
-                self.map.doors.push(Point::new(*x, *y));
-                for i in 0..self.map.width {
-                    for j in 0..self.map.height {
-                        if self.map.get(i, j) == Some(*v) {
-                            self.map.set(i, j, 1);
-                        }
-                    }
-                }
-            },
-            None => {}
+        if let Some((x, y, v)) = rng.choose(connection_coords.as_slice()) {
+            self.connect(*x, *y, *v);
         }
         if rng.gen::<f32>() < 0.6 {
-            match rng.choose(connection_coords.as_slice()) {
-                Some((x, y, v)) => {
-                    let neighbours = get_neigbours(*x, *y, true);
-                    let connections = neighbours.iter().filter_map(|pos| {
-                        match self.map.get(pos.x, pos.y) {
-                            Some(v) if v != 0 => Some(v),
-                            _ => None
-                        }
-                    }).collect::<Vec<_>>();
-                    if connections.len() == 2 {
-                        self.map.set(*x, *y, 1);
-                        self.map.doors.push(Point::new(*x, *y));
-                        for i in 0..self.map.width {
-                            for j in 0..self.map.height {
-                                if self.map.get(i, j) == Some(*v) {
-                                    self.map.set(i, j, 1);
-                                }
-                            }
-                        }
-                    }
-                },
-                None => {}
+            if let Some((x, y, v)) = rng.choose(connection_coords.as_slice()) {
+                if self.region(*x, *y) == Some(0) && self.connections(*x, *y).len() == 2 {
+                    self.connect(*x, *y, *v);
+                }
             }
         }
     }
@@ -134,6 +129,7 @@ pub fn generate<T: Rng>(width: i32, height: i32, min_size: i32, rng: &mut T) ->
         connection_map.iter(rng);
     }
     let mut tower_map = connection_map.map;
-    tower_map.stairs = Some(tower_map.rooms[tower_map.rooms.len() - 1].center());
+    let stairs = tower_map.rooms[tower_map.rooms.len() - 1].center();
+    tower_map.set_stairs(stairs);
     tower_map
 }
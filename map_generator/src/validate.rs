@@ -0,0 +1,126 @@
+use std::fmt;
+use std::collections::VecDeque;
+
+use geo::{Point, Rect, get_neigbours};
+use super::{Map, Tile};
+
+// Everything the game relies on when it turns a generated map into a level.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    NoRooms,
+    DegenerateRoom(Rect),
+    OverlappingRooms(Rect, Rect),
+    MissingStairs,
+    MisplacedStairs(Point),
+    UnreachableStairs(Point),
+    UnreachableFloor{count: usize, example: Point}
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::NoRooms => write!(f, "map has no rooms"),
+            Problem::DegenerateRoom(room) => write!(f, "room {:?} is empty, off the map or has rock inside", room),
+            Problem::OverlappingRooms(a, b) => write!(f, "room {:?} overlaps room {:?}", a, b),
+            Problem::MissingStairs => write!(f, "map has no stairs"),
+            Problem::MisplacedStairs(pos) => write!(f, "stairs at {} are not on a stairs tile", pos),
+            Problem::UnreachableStairs(pos) => write!(f, "stairs at {} can't be reached from the first room", pos),
+            Problem::UnreachableFloor{count, example} => write!(f, "{} floor tiles can't be reached from the first room, one is at {}", count, example)
+        }
+    }
+}
+
+fn is_floor(map: &Map, x: i32, y: i32) -> bool {
+    map.get_or_wall(x, y).is_passable()
+}
+
+// Floor tiles the player can walk to from `start`, diagonal steps included.
+pub fn reachable(map: &Map, start: Point) -> Vec<bool> {
+    let mut seen = vec![false; map.data.len()];
+    if !is_floor(map, start.x, start.y) {
+        return seen;
+    }
+    let mut queue = VecDeque::new();
+    seen[(start.y * map.width + start.x) as usize] = true;
+    queue.push_back(start);
+    while let Some(p) = queue.pop_front() {
+        for n in get_neigbours(p.x, p.y, false) {
+            if is_floor(map, n.x, n.y) && !seen[(n.y * map.width + n.x) as usize] {
+                seen[(n.y * map.width + n.x) as usize] = true;
+                queue.push_back(n);
+            }
+        }
+    }
+    seen
+}
+
+fn check_rooms(map: &Map, problems: &mut Vec<Problem>) {
+    for (i, room) in map.rooms.iter().enumerate() {
+        let degenerate = room.width < 1 || room.height < 1
+            || room.x1 < 0 || room.y1 < 0 || room.x2 > map.width || room.y2 > map.height
+            || !(room.x1..room.x2).all(|x| (room.y1..room.y2).all(|y| map.get_or_wall(x, y) != Tile::Wall));
+        if degenerate {
+            problems.push(Problem::DegenerateRoom(*room));
+        }
+        for other in map.rooms.iter().skip(i + 1) {
+            if room.x1 < other.x2 && other.x1 < room.x2 && room.y1 < other.y2 && other.y1 < room.y2 {
+                problems.push(Problem::OverlappingRooms(*room, *other));
+            }
+        }
+    }
+}
+
+pub fn validate(map: &Map) -> Vec<Problem> {
+    let mut problems = vec![];
+    let start = match map.rooms.first() {
+        Some(room) => room.center(),
+        None => return vec![Problem::NoRooms]
+    };
+    check_rooms(map, &mut problems);
+
+    let seen = reachable(map, start);
+    match map.stairs {
+        None => problems.push(Problem::MissingStairs),
+        Some(stairs) if map.get_or_wall(stairs.x, stairs.y) != Tile::Stairs => problems.push(Problem::MisplacedStairs(stairs)),
+        Some(stairs) if !seen[(stairs.y * map.width + stairs.x) as usize] => problems.push(Problem::UnreachableStairs(stairs)),
+        Some(_) => {}
+    }
+
+    let mut unreachable = (0..map.data.len())
+        .filter(|i| map.data[*i].is_passable() && !seen[*i])
+        .map(|i| Point::new(i as i32 % map.width, i as i32 / map.width));
+    if let Some(example) = unreachable.next() {
+        problems.push(Problem::UnreachableFloor{count: unreachable.count() + 1, example});
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::*;
+
+    #[test]
+    fn test_reports_problems() {
+        let mut map = Map::new(10, 5);
+        assert_eq!(validate(&map), vec![Problem::NoRooms]);
+        for x in 1..4 {
+            for y in 1..4 {
+                map.set(x, y, Tile::Floor);
+            }
+        }
+        map.add_room(Rect::new(1, 1, 3, 3));
+        map.add_room(Rect::new(2, 2, 3, 1));
+        map.set_stairs(Point::new(7, 2));
+        assert_eq!(validate(&map), vec![
+            Problem::OverlappingRooms(Rect::new(1, 1, 3, 3), Rect::new(2, 2, 3, 1)),
+            Problem::DegenerateRoom(Rect::new(2, 2, 3, 1)),
+            Problem::UnreachableStairs(Point::new(7, 2)),
+            Problem::UnreachableFloor{count: 1, example: Point::new(7, 2)}
+        ]);
+        for x in 4..7 {
+            map.set(x, 2, Tile::Floor);
+        }
+        map.rooms.pop();
+        assert_eq!(validate(&map), vec![]);
+    }
+}
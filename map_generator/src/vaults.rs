@@ -0,0 +1,275 @@
+use rand::{Rng};
+
+use geo::{Point, Rect};
+use super::{Map, Feature, Tile};
+
+const PLACEMENT_ATTEMPTS: usize = 100;
+const MAX_TUNNEL_LENGTH: i32 = 15;
+// the game needs a few rooms to place the player and the stairs in
+const MIN_ROOMS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultTile {
+    // the map is left as it is
+    Keep,
+    Wall,
+    Floor,
+    Door,
+    Feature(Feature)
+}
+
+// A hand made set piece, read from an ASCII template:
+//
+//   ' ' leave the map as it is    '#' wall    '.' floor    '+' door
+//   'M' monster    'I' item    'R' rune
+//
+// Doors on the edge of a template are entrances. A tunnel is dug from each
+// of them to the nearest floor when the vault is stamped, so every vault
+// needs at least one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vault {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    tiles: Vec<VaultTile>
+}
+
+impl Vault {
+    pub fn parse(name: &str, template: &str) -> Result<Vault, String> {
+        let lines: Vec<&str> = template.lines().map(|line| line.trim_end()).collect();
+        // blank lines around the template don't count
+        let first = match lines.iter().position(|line| !line.is_empty()) {
+            Some(first) => first,
+            None => return Err("template is empty".to_string())
+        };
+        let last = lines.iter().rposition(|line| !line.is_empty()).unwrap_or(first);
+        let lines = &lines[first..=last];
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+        let height = lines.len() as i32;
+        let mut tiles = vec![];
+        for (y, line) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+            for x in 0..width {
+                let tile = match chars.next() {
+                    None | Some(' ') => VaultTile::Keep,
+                    Some('#') => VaultTile::Wall,
+                    Some('.') => VaultTile::Floor,
+                    Some('+') => VaultTile::Door,
+                    Some('M') => VaultTile::Feature(Feature::Monster),
+                    Some('I') => VaultTile::Feature(Feature::Item),
+                    Some('R') => VaultTile::Feature(Feature::Rune),
+                    Some(c) => return Err(format!("unknown marker '{}' at line {}, column {}", c, y + 1, x + 1))
+                };
+                tiles.push(tile);
+            }
+        }
+        let vault = Vault {
+            name: name.to_string(),
+            width,
+            height,
+            tiles
+        };
+        if vault.entrances().is_empty() {
+            return Err("a vault needs at least one door on its edge".to_string());
+        }
+        Ok(vault)
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> VaultTile {
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    // Doors on the edge together with the direction leading out of the vault.
+    fn entrances(&self) -> Vec<(Point, Point)> {
+        let mut entrances = vec![];
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.get(x, y) != VaultTile::Door {
+                    continue;
+                }
+                let dir = match (x, y) {
+                    (0, _) => Point::new(-1, 0),
+                    (_, 0) => Point::new(0, -1),
+                    _ if x == self.width - 1 => Point::new(1, 0),
+                    _ if y == self.height - 1 => Point::new(0, 1),
+                    _ => continue
+                };
+                entrances.push((Point::new(x, y), dir));
+            }
+        }
+        entrances
+    }
+}
+
+// Vaults only go into solid rock, with a wall of rock around them.
+fn fits(map: &Map, vault: &Vault, pos: Point, placed: &[Rect]) -> bool {
+    let area = Rect::new(pos.x - 1, pos.y - 1, vault.width + 2, vault.height + 2);
+    if area.x1 < 0 || area.y1 < 0 || area.x2 > map.width || area.y2 > map.height {
+        return false;
+    }
+    if placed.iter().any(|p| overlaps(p, &area)) {
+        return false;
+    }
+    (area.x1..area.x2).all(|x| (area.y1..area.y2).all(|y| map.get_or_wall(x, y) == Tile::Wall))
+}
+
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.x1 < b.x2 && b.x1 < a.x2 && a.y1 < b.y2 && b.y1 < a.y2
+}
+
+fn inside(rect: &Rect, p: Point) -> bool {
+    rect.x1 <= p.x && p.x < rect.x2 && rect.y1 <= p.y && p.y < rect.y2
+}
+
+// Walks out from an entrance through rock until the next step is floor.
+fn dig_tunnel(map: &Map, start: Point, dir: Point, placed: &[Rect]) -> Option<Vec<Point>> {
+    let mut tunnel = vec![];
+    let mut pos = start + dir;
+    for _ in 0..MAX_TUNNEL_LENGTH {
+        if pos.x < 1 || pos.y < 1 || pos.x >= map.width - 1 || pos.y >= map.height - 1 {
+            return None;
+        }
+        if map.get_or_wall(pos.x, pos.y) != Tile::Wall {
+            return Some(tunnel);
+        }
+        if placed.iter().any(|p| inside(p, pos)) {
+            return None;
+        }
+        tunnel.push(pos);
+        pos += dir;
+    }
+    None
+}
+
+fn stamp(map: &mut Map, vault: &Vault, pos: Point, tunnels: Vec<Point>) {
+    for x in 0..vault.width {
+        for y in 0..vault.height {
+            let p = Point::new(pos.x + x, pos.y + y);
+            match vault.get(x, y) {
+                VaultTile::Keep => {},
+                VaultTile::Wall => map.set(p.x, p.y, Tile::Wall),
+                VaultTile::Floor => map.set(p.x, p.y, Tile::Floor),
+                VaultTile::Door => map.add_door(p),
+                VaultTile::Feature(feature) => {
+                    map.set(p.x, p.y, Tile::Floor);
+                    map.features.push((p, feature));
+                }
+            }
+        }
+    }
+    for p in tunnels {
+        map.set(p.x, p.y, Tile::Floor);
+    }
+}
+
+// A spot in solid rock, with tunnels from every entrance to the rest of the
+// map.
+fn find_rock_spot<T: Rng>(map: &Map, vault: &Vault, placed: &[Rect], rng: &mut T) -> Option<(Point, Vec<Point>)> {
+    if vault.width + 2 > map.width || vault.height + 2 > map.height {
+        return None;
+    }
+    for _ in 0..PLACEMENT_ATTEMPTS {
+        let pos = Point::new(
+            rng.gen_range(1, map.width - vault.width),
+            rng.gen_range(1, map.height - vault.height)
+        );
+        if !fits(map, vault, pos, placed) {
+            continue;
+        }
+        let tunnels: Option<Vec<Vec<Point>>> = vault
+            .entrances()
+            .into_iter()
+            .map(|(door, dir)| dig_tunnel(map, pos + door, dir, placed))
+            .collect();
+        if let Some(tunnels) = tunnels {
+            return Some((pos, tunnels.into_iter().flatten().collect()));
+        }
+    }
+    None
+}
+
+// Maps with little rock left get the vault in the middle of a room instead,
+// with a ring of the room's floor around it. The room is given up, so the
+// start room, the stairs and the last few rooms are never used.
+fn find_room_spot<T: Rng>(map: &Map, vault: &Vault, placed: &[Rect], rng: &mut T) -> Option<(Point, usize)> {
+    if map.rooms.len() <= MIN_ROOMS {
+        return None;
+    }
+    let mut candidates: Vec<usize> = (1..map.rooms.len())
+        .filter(|i| {
+            let room = &map.rooms[*i];
+            room.width >= vault.width + 2 && room.height >= vault.height + 2
+                && !placed.iter().any(|p| overlaps(p, room))
+                && map.stairs.map_or(true, |s| !inside(room, s))
+        })
+        .collect();
+    rng.shuffle(&mut candidates);
+    let index = *candidates.first()?;
+    let room = &map.rooms[index];
+    let pos = Point::new(room.x1 + (room.width - vault.width) / 2, room.y1 + (room.height - vault.height) / 2);
+    Some((pos, index))
+}
+
+// Stamps up to `count` randomly picked vaults into a generated map and
+// returns the areas they were placed in. Vaults are not rooms, the game
+// should only spawn what their features ask for.
+pub fn stamp_vaults<T: Rng>(map: &mut Map, vaults: &[Vault], count: usize, rng: &mut T) -> Vec<Rect> {
+    let mut placed: Vec<Rect> = vec![];
+    for _ in 0..count {
+        let vault = match rng.choose(vaults) {
+            Some(vault) => vault,
+            None => break
+        };
+        let pos = if let Some((pos, tunnels)) = find_rock_spot(map, vault, &placed, rng) {
+            stamp(map, vault, pos, tunnels);
+            pos
+        } else if let Some((pos, room)) = find_room_spot(map, vault, &placed, rng) {
+            stamp(map, vault, pos, vec![]);
+            map.rooms.remove(room);
+            pos
+        } else {
+            continue;
+        };
+        placed.push(Rect::new(pos.x - 1, pos.y - 1, vault.width + 2, vault.height + 2));
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{XorShiftRng, SeedableRng};
+    use crate::vaults::*;
+    use crate::corridor;
+
+    const TEMPLATE: &str = "
+#####
+#M.I#
+#.R.+
+#####
+";
+
+    #[test]
+    fn test_parse() {
+        let vault = Vault::parse("test", TEMPLATE).unwrap();
+        assert_eq!((vault.width, vault.height), (5, 4));
+        assert_eq!(vault.get(1, 1), VaultTile::Feature(Feature::Monster));
+        assert_eq!(vault.entrances(), vec![(Point::new(4, 2), Point::new(1, 0))]);
+        assert!(Vault::parse("closed", "###\n#.#\n###").is_err());
+        assert!(Vault::parse("unknown", "#?+").is_err());
+    }
+
+    #[test]
+    fn test_stamp_keeps_vault_reachable() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([0, 1, 3, 4]);
+        let mut map = corridor::generate(80, 43, 6, &mut rng);
+        let vault = Vault::parse("test", TEMPLATE).unwrap();
+        let placed = stamp_vaults(&mut map, &[vault], 1, &mut rng);
+        assert_eq!(placed.len(), 1);
+        assert_eq!(map.features.len(), 3);
+        // the floor right outside the entrance leads somewhere
+        let area = placed[0];
+        let entrance = Point::new(area.x2 - 2, area.y1 + 3);
+        assert!(map.doors.contains(&entrance));
+        assert!(map.get_or_wall(entrance.x + 1, entrance.y).is_passable());
+    }
+}
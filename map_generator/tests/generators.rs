@@ -0,0 +1,69 @@
+// Runs every generator over many seeds and map sizes. The number of seeds per
+// generator can be raised with MAP_GENERATOR_SEEDS, e.g.
+//
+//   MAP_GENERATOR_SEEDS=5000 cargo test --release -p map_generator --test generators
+
+extern crate rand;
+extern crate map_generator;
+
+use std::env;
+use std::panic;
+use rand::{Rng, XorShiftRng, SeedableRng};
+
+use map_generator::{Map, bsp, tower, roomsy, corridor, caves};
+use map_generator::validate::validate;
+
+type Generate = fn(i32, i32, i32, &mut XorShiftRng) -> Map;
+
+const GENERATORS: [(&str, Generate); 5] = [
+    ("bsp", bsp::generate),
+    ("tower", tower::generate),
+    ("roomsy", roomsy::generate),
+    ("corridor", corridor::generate),
+    ("caves", caves::generate)
+];
+
+fn seeds() -> u32 {
+    env::var("MAP_GENERATOR_SEEDS")
+        .ok()
+        .and_then(|seeds| seeds.parse().ok())
+        .unwrap_or(200)
+}
+
+// Picks the size from the seed as well, so a failure can be reproduced from
+// the seed alone.
+fn sweep(widths: (i32, i32), heights: (i32, i32), min_sizes: (i32, i32), check: fn(&Map) -> Vec<String>) {
+    let mut failures = vec![];
+    for (name, generate) in GENERATORS.iter() {
+        for seed in 1..=seeds() {
+            let mut rng: XorShiftRng = SeedableRng::from_seed([seed, 0x5eed, 3, 4]);
+            let width = rng.gen_range(widths.0, widths.1);
+            let height = rng.gen_range(heights.0, heights.1);
+            let min_size = rng.gen_range(min_sizes.0, min_sizes.1);
+            let generate = *generate;
+            let result = panic::catch_unwind(move || generate(width, height, min_size, &mut rng));
+            let problems = match result {
+                Ok(map) => check(&map),
+                Err(_) => vec!["panicked".to_string()]
+            };
+            for problem in problems {
+                failures.push(format!("{} seed {} ({}x{}, min size {}): {}", name, seed, width, height, min_size, problem));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{} problems, first ones:\n{}", failures.len(), failures[..failures.len().min(20)].join("\n"));
+}
+
+#[test]
+fn test_generated_maps_are_valid() {
+    sweep((30, 100), (20, 60), (4, 9), |map| {
+        validate(map).iter().map(|problem| problem.to_string()).collect()
+    });
+}
+
+// Tiny maps may end up without rooms or stairs, but must not bring the
+// generators down.
+#[test]
+fn test_tiny_maps_do_not_panic() {
+    sweep((6, 20), (6, 20), (2, 5), |_| vec![]);
+}